@@ -1,8 +1,8 @@
 #[macro_use]
 extern crate log;
 extern crate nalgebra as na;
-use midir::{MidiOutput, MidiOutputPort};
 
+mod sink;
 
 use vis_core::analyzer;
 
@@ -16,7 +16,7 @@ pub struct VisInfo {
 }
 
 fn main() {
-    vis_core::default_config();
+    vis_core::config_from_args();
     vis_core::default_log();
 
     let mut frames = {
@@ -64,50 +64,35 @@ fn main() {
 
     let note_roll_size = vis_core::CONFIG.get_or("noa.cols.note_roll", 20) as f32;
 
-    // }}}
+    let volume_curve = vis_core::helpers::curve::Curve::from_config(
+        "midi.volume_curve",
+        vis_core::helpers::curve::Curve::Gamma(0.6),
+    );
 
-    let midi_out = MidiOutput::new("no-midi Music Visualizer").unwrap();
+    // Column activation relative to the strongest note
+    let column_on = vis_core::CONFIG.get_or("midi.column_on", 0.8);
+    let column_off = vis_core::CONFIG.get_or("midi.column_off", 0.6);
 
-    // Get an output port (read from console if multiple are available)
-    let out_ports = midi_out.ports();
-    let out_port: &MidiOutputPort = match out_ports.len() {
-        0 => panic!("no MIDI output port found"),
-        _ => {
-            log::debug!("Available output ports:");
-            for p in out_ports.iter() {
-                log::debug!(" - {}", midi_out.port_name(p).unwrap());
-            }
+    // }}}
 
-            if let Some(want_port) = vis_core::CONFIG.get::<String>("midi.output_port") {
-                let mut out_port = None;
-                for p in out_ports.iter() {
-                    if want_port == midi_out.port_name(p).unwrap() {
-                        log::debug!("Chose wanted MIDI output port {:?}", want_port);
-                        out_port = Some(p);
-                    }
-                }
-                out_port.unwrap_or_else(|| {
-                    panic!("Wanted MIDI output port {:?} not found!", want_port)
-                })
-            } else {
-                log::debug!("Choosing MIDI port {:?}", midi_out.port_name(&out_ports[0]));
-                &out_ports[0]
-            }
-        }
-    };
-    let mut conn_out = midi_out.connect(out_port, "midir-test").unwrap();
+    let mut conn_out = sink::MidiSink::open(sink::OnMissing::from_config());
 
     let mut previous_time = 0.0;
     let mut rolling_volume = 0.0;
     let mut last_beat = -100.0;
 
     let mut notes_spectrum = analyzer::Spectrum::new(vec![0.0; notes_num], 220.0, 660.0);
-    let mut notes_rolling_buf = vec![0.0; notes_num];
+    let mut notes_smoother = analyzer::SpectrumSmoother::new(
+        notes_num,
+        220.0,
+        660.0,
+        1.0 - 1.0 / note_roll_size,
+    );
 
     let mut last_beat_num = 0;
 
-    let mut maxima_buf = [(0.0, 0.0); 8];
-
+    let mut column_gates =
+        vec![vis_core::helpers::hysteresis::Gate::new(column_on, column_off); notes_num];
     let mut previous_columns = vec![false; notes_num];
     let mut beat_ended = true;
 
@@ -118,7 +103,7 @@ fn main() {
         trace!("Delta: {}s", delta);
 
         // Audio Info Retrieval {{{
-        let (_volume, maxima, notes_rolling_spectrum, _base_volume) = frame.info(|info| {
+        let (_volume, notes_rolling_spectrum, _base_volume) = frame.info(|info| {
             rolling_volume = info.volume.max(rolling_volume * slowdown);
 
             if info.beat != last_beat_num {
@@ -129,20 +114,10 @@ fn main() {
 
             let notes_spectrum = info.spectrum.fill_spectrum(&mut notes_spectrum);
 
-            for (n, s) in notes_rolling_buf.iter_mut().zip(notes_spectrum.iter()) {
-                *n = (*n * (note_roll_size - 1.0) + s) / note_roll_size;
-            }
-            let notes_rolling_spectrum = vis_core::analyzer::Spectrum::new(
-                &mut *notes_rolling_buf,
-                notes_spectrum.lowest(),
-                notes_spectrum.highest(),
-            );
-
-            let maxima = notes_rolling_spectrum.find_maxima(&mut maxima_buf);
+            let notes_rolling_spectrum = notes_smoother.push(notes_spectrum);
 
             (
                 info.volume,
-                maxima,
                 notes_rolling_spectrum,
                 info.beat_volume,
             )
@@ -154,15 +129,15 @@ fn main() {
         const VELOCITY: u8 = 0x7f;
 
         // let vol_float = (rolling_volume.powf(0.5) / 0.50).min(1.0).powi(2).max(0.15);
-        let vol_float = (((rolling_volume / 0.18).powf(0.6) - 0.2) / 0.8).min(1.0).max(0.15);
+        let vol_float = ((volume_curve.apply(rolling_volume / 0.44) - 0.2) / 0.8).min(1.0).max(0.15);
         let vol = (vol_float * 127.0) as u8;
-        conn_out.send(&[NOTE_ON_MSG, 70 as u8, vol]).unwrap();
+        conn_out.send(&[NOTE_ON_MSG, 70 as u8, vol]);
 
         let beat_dur = 0.1;
         if frame.time == last_beat && vol_float != 0.15 {
-            conn_out.send(&[NOTE_ON_MSG, 66 as u8, VELOCITY]).unwrap();
+            conn_out.send(&[NOTE_ON_MSG, 66 as u8, VELOCITY]);
         } else if frame.time - last_beat > beat_dur && !beat_ended {
-            conn_out.send(&[NOTE_OFF_MSG, 66 as u8, VELOCITY]).unwrap();
+            conn_out.send(&[NOTE_OFF_MSG, 66 as u8, VELOCITY]);
             beat_ended = true;
         }
 
@@ -172,17 +147,18 @@ fn main() {
             "  "
         };
 
-        let mut columns = vec![false; notes_num];
-        for (f, _) in maxima.iter().take(3) {
-            let note = notes_rolling_spectrum.freq_to_id(*f);
-            columns[note] = true;
-        }
+        let strongest = notes_rolling_spectrum.max() + 0.0001;
+        let columns = column_gates
+            .iter_mut()
+            .zip(notes_rolling_spectrum.iter())
+            .map(|(gate, v)| gate.update(v / strongest))
+            .collect::<Vec<_>>();
 
         for (i, (prev, now)) in previous_columns.iter().copied().zip(columns.iter().copied()).enumerate() {
             if !prev && now {
-                conn_out.send(&[NOTE_ON_MSG, 50 + i as u8, VELOCITY]).unwrap();
+                conn_out.send(&[NOTE_ON_MSG, 50 + i as u8, VELOCITY]);
             } else if prev && !now {
-                conn_out.send(&[NOTE_OFF_MSG, 50 + i as u8, VELOCITY]).unwrap();
+                conn_out.send(&[NOTE_OFF_MSG, 50 + i as u8, VELOCITY]);
             }
         }
 
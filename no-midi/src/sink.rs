@@ -0,0 +1,113 @@
+use midir::{MidiOutput, MidiOutputConnection};
+
+/// What to do when no MIDI output is available
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnMissing {
+    /// Log a warning and keep running without sending anything
+    Dummy,
+    /// Exit with an error message
+    Exit,
+}
+
+impl OnMissing {
+    /// Read from config as `"midi.on_missing"`, either `"dummy"` or `"exit"`
+    pub fn from_config() -> OnMissing {
+        match &*vis_core::CONFIG.get_or("midi.on_missing", "exit".to_string()) {
+            "dummy" => OnMissing::Dummy,
+            "exit" => OnMissing::Exit,
+            other => panic!("Unknown value {:?} for midi.on_missing!", other),
+        }
+    }
+}
+
+/// MIDI output which does nothing if no device is present
+pub struct MidiSink {
+    conn: Option<MidiOutputConnection>,
+}
+
+impl MidiSink {
+    /// Connect to the configured MIDI output port
+    pub fn open(on_missing: OnMissing) -> MidiSink {
+        MidiSink::or_missing(connect(), on_missing)
+    }
+
+    fn or_missing(conn: Result<MidiOutputConnection, String>, on_missing: OnMissing) -> MidiSink {
+        match conn {
+            Ok(conn) => MidiSink { conn: Some(conn) },
+            Err(e) => match on_missing {
+                OnMissing::Dummy => {
+                    log::warn!("{}, not sending any MIDI messages", e);
+                    MidiSink { conn: None }
+                }
+                OnMissing::Exit => {
+                    log::error!("{}", e);
+                    log::error!("Set `midi.on_missing = \"dummy\"` to run without MIDI output");
+                    std::process::exit(1);
+                }
+            },
+        }
+    }
+
+    /// Send a message, if connected
+    pub fn send(&mut self, message: &[u8]) {
+        if let Some(ref mut conn) = self.conn {
+            if let Err(e) = conn.send(message) {
+                log::warn!("Failed to send MIDI message: {}", e);
+            }
+        }
+    }
+
+    /// Whether messages actually go to a device
+    #[cfg(test)]
+    pub fn is_connected(&self) -> bool {
+        self.conn.is_some()
+    }
+}
+
+/// Connect to the wanted or first available MIDI output port
+fn connect() -> Result<MidiOutputConnection, String> {
+    let midi_out = MidiOutput::new("no-midi Music Visualizer")
+        .map_err(|e| format!("Can't initialize MIDI output: {}", e))?;
+
+    let out_ports = midi_out.ports();
+    if out_ports.is_empty() {
+        return Err("No MIDI output port found".to_string());
+    }
+
+    log::debug!("Available output ports:");
+    for p in out_ports.iter() {
+        log::debug!(" - {}", midi_out.port_name(p).unwrap_or_default());
+    }
+
+    let out_port = if let Some(want_port) = vis_core::CONFIG.get::<String>("midi.output_port") {
+        let port = out_ports
+            .iter()
+            .find(|p| midi_out.port_name(p).ok().as_ref() == Some(&want_port))
+            .ok_or_else(|| format!("Wanted MIDI output port {:?} not found", want_port))?;
+        log::debug!("Chose wanted MIDI output port {:?}", want_port);
+        port
+    } else {
+        log::debug!("Choosing MIDI port {:?}", midi_out.port_name(&out_ports[0]));
+        &out_ports[0]
+    };
+
+    midi_out
+        .connect(out_port, "midir-test")
+        .map_err(|e| format!("Can't connect to MIDI output port: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_device_dummy() {
+        let mut sink = MidiSink::or_missing(
+            Err("No MIDI output port found".to_string()),
+            OnMissing::Dummy,
+        );
+
+        assert!(!sink.is_connected());
+        sink.send(&[0x90, 66, 0x7f]);
+    }
+}
@@ -0,0 +1,114 @@
+#[macro_use]
+extern crate log;
+
+use vis_core::analyzer;
+use vis_core::helpers::osc;
+
+#[derive(Debug, Clone)]
+pub struct VisInfo {
+    beat: u64,
+    volume: f32,
+    analyzer: analyzer::FourierAnalyzer,
+    spectrum: analyzer::Spectrum<Vec<f32>>,
+}
+
+fn main() {
+    vis_core::config_from_args();
+    vis_core::default_log();
+
+    let mut frames = {
+        // Analyzer {{{
+        let mut beat = analyzer::BeatBuilder::new().build();
+        let mut beat_num = 0;
+
+        let analyzer = analyzer::FourierBuilder::new().plan();
+
+        vis_core::Visualizer::new(
+            VisInfo {
+                beat: 0,
+                volume: 0.0,
+                spectrum: analyzer::Spectrum::new(vec![0.0; analyzer.buckets()], 0.0, 1000.0),
+                analyzer,
+            },
+            move |info, samples| {
+                if beat.detect(samples) {
+                    beat_num += 1;
+                }
+                info.beat = beat_num;
+                info.volume = samples.volume(0.3);
+
+                info.analyzer.analyze(samples);
+                info.spectrum.fill_from(&info.analyzer.average());
+
+                info
+            },
+        )
+        .async_analyzer(300)
+        .frames()
+        // }}}
+    };
+
+    // Config {{{
+    let notes_num = 10;
+    let slowdown = vis_core::CONFIG.get_or("noa.cols.slowdown", 0.95);
+
+    let frame_time =
+        std::time::Duration::from_micros(1000000 / vis_core::CONFIG.get_or("noa.fps", 30));
+
+    let note_roll_size = vis_core::CONFIG.get_or("noa.cols.note_roll", 20) as f32;
+
+    let volume_curve = vis_core::helpers::curve::Curve::from_config(
+        "osc.volume_curve",
+        vis_core::helpers::curve::Curve::Gamma(0.6),
+    );
+    // }}}
+
+    let mut sender = osc::OscSender::from_config().unwrap_or_else(|e| {
+        error!("Can't open OSC socket: {}", e);
+        std::process::exit(1);
+    });
+
+    let mut previous_time = 0.0;
+    let mut rolling_volume = 0.0;
+
+    let mut notes_spectrum = analyzer::Spectrum::new(vec![0.0; notes_num], 220.0, 660.0);
+    let mut notes_smoother =
+        analyzer::SpectrumSmoother::new(notes_num, 220.0, 660.0, 1.0 - 1.0 / note_roll_size);
+    let mut notes_relative = analyzer::Spectrum::new(vec![0.0; notes_num], 220.0, 660.0);
+
+    for frame in frames.iter() {
+        let start = std::time::Instant::now();
+        let delta = frame.time - previous_time;
+        trace!("Delta: {}s", delta);
+
+        // Audio Info Retrieval {{{
+        let beat = frame.info(|info| {
+            rolling_volume = info.volume.max(rolling_volume * slowdown);
+
+            let notes_spectrum = info.spectrum.fill_spectrum(&mut notes_spectrum);
+            let notes_rolling_spectrum = notes_smoother.push(notes_spectrum);
+
+            let strongest = notes_rolling_spectrum.max() + 0.0001;
+            for (rel, v) in notes_relative.iter_mut().zip(notes_rolling_spectrum.iter()) {
+                *rel = v / strongest;
+            }
+
+            info.beat
+        });
+        // }}}
+
+        let volume = volume_curve.apply(rolling_volume / 0.44).min(1.0);
+        sender.send_volume(volume);
+        sender.send_beat(beat);
+        sender.send_spectrum(&notes_relative, "/notes");
+
+        previous_time = frame.time;
+
+        let end = std::time::Instant::now();
+        let dur = end - start;
+        if dur < frame_time {
+            let sleep = frame_time - dur;
+            std::thread::sleep(sleep);
+        }
+    }
+}
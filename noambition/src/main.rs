@@ -6,6 +6,7 @@ extern crate nalgebra as na;
 
 use glium::glutin;
 use vis_core::analyzer;
+use vis_core::ConfigExt;
 
 use glutin::platform::run_return::EventLoopExtRunReturn;
 
@@ -71,7 +72,7 @@ pub struct VisInfo {
 }
 
 fn main() {
-    vis_core::default_config();
+    vis_core::config_from_args();
     vis_core::default_log();
 
     let mut frames = {
@@ -127,7 +128,7 @@ fn main() {
     let base_height = vis_core::CONFIG.get_or("noa.cols.base_height", 0.2);
     let base_speed = vis_core::CONFIG.get_or("noa.cols.speed", 0.1);
     let slowdown = vis_core::CONFIG.get_or("noa.cols.slowdown", 0.995);
-    let speed_deviation = vis_core::CONFIG.get_or("noa.cols.speed_deviation", 50.0);
+    let speed_deviation = vis_core::CONFIG.get_or("noa.cols.speed_deviation", 20.0);
     let ampli_top = vis_core::CONFIG.get_or("noa.cols.amp_top", 0.7);
     let ampli_bottom = vis_core::CONFIG.get_or("noa.cols.amp_bottom", 0.2);
 
@@ -135,7 +136,7 @@ fn main() {
         std::time::Duration::from_micros(1000000 / vis_core::CONFIG.get_or("noa.fps", 30));
 
     // Colors
-    let colors: Vec<[f32; 4]> = vis_core::CONFIG.get_or(
+    let colors: Vec<[f32; 4]> = vis_core::CONFIG.get_vec(
         "noa.cols.colors",
         vec![
             [1.0, 0.007443, 0.318893, 1.0],
@@ -155,8 +156,10 @@ fn main() {
     let note_roll_size = vis_core::CONFIG.get_or("noa.cols.note_roll", 20) as f32;
 
     // Camera
-    let cam_height = vis_core::CONFIG.get_or("noa.camera.height", 1.0);
-    let cam_look = vis_core::CONFIG.get_or("noa.camera.look_height", 0.8);
+    let camera = vis_core::helpers::camera::Camera::from_config("noa.camera");
+
+    // Input
+    let keys = vis_core::helpers::input::KeyMap::from_config("input.keys");
 
     // }}}
 
@@ -347,16 +350,20 @@ fn main() {
     let mut previous_time = 0.0;
     let mut previous_offset = 0.0;
     let mut rolling_volume = 0.0;
-    let mut write_row = rows * 3 / 4;
+    // Relative amplitude of each column, one row per grid row in the order of `lines_verts`
+    let mut spectral_rows =
+        vis_core::helpers::ScrollBuffer::new(rows, cols * 2, 0.0).starting_at(rows * 3 / 4);
     let mut last_beat = -100.0;
 
     let mut notes_spectrum = analyzer::Spectrum::new(vec![0.0; notes_num], 220.0, 660.0);
-    let mut notes_rolling_buf = vec![0.0; notes_num];
+    let mut notes_smoother =
+        analyzer::SpectrumSmoother::new(notes_num, 220.0, 660.0, 1.0 - 1.0 / note_roll_size);
     let mut row_buf = Vec::with_capacity(nrow);
     let mut row_spectrum = vec![0.0; cols];
+    let mut row_amplitudes = vec![0.0; cols * 2];
 
     let mut beat_rolling = 0.0;
-    let mut last_beat_num = 0;
+    let mut beat_edge = vis_core::helpers::anim::BeatEdge::new();
 
     let mut maxima_buf = [(0.0, 0.0); 8];
 
@@ -371,21 +378,13 @@ fn main() {
         let (volume, maxima, notes_rolling_spectrum, base_volume) = frame.info(|info| {
             rolling_volume = info.volume.max(rolling_volume * slowdown);
 
-            if info.beat != last_beat_num {
+            if beat_edge.update(info.beat) {
                 last_beat = frame.time;
-                last_beat_num = info.beat;
             }
 
             let notes_spectrum = info.spectrum.fill_spectrum(&mut notes_spectrum);
 
-            for (n, s) in notes_rolling_buf.iter_mut().zip(notes_spectrum.iter()) {
-                *n = (*n * (note_roll_size - 1.0) + s) / note_roll_size;
-            }
-            let notes_rolling_spectrum = vis_core::analyzer::Spectrum::new(
-                &mut *notes_rolling_buf,
-                notes_spectrum.lowest(),
-                notes_spectrum.highest(),
-            );
+            let notes_rolling_spectrum = notes_smoother.push(notes_spectrum);
 
             let maxima = notes_rolling_spectrum.find_maxima(&mut maxima_buf);
 
@@ -399,14 +398,8 @@ fn main() {
         // }}}
 
         // GL Matrices {{{
-        let view = na::Matrix4::look_at_rh(
-            &na::Point3::new(0.0, -1.0, cam_height),
-            &na::Point3::new(0.0, 10.0, cam_look),
-            &na::Vector3::new(0.0, 0.0, 1.0),
-        );
-
-        let perspective =
-            na::Matrix4::new_perspective(aspect, std::f32::consts::FRAC_PI_4, 0.001, 100.0);
+        let view = camera.view();
+        let perspective = camera.perspective(aspect);
         // }}}
 
         // Grid {{{
@@ -454,13 +447,9 @@ fn main() {
                     .left()
                     .slice(100.0, 800.0)
                     .fill_buckets(&mut row_spectrum[..]);
-                let row_offset = nrow * write_row;
                 let max = left.max() + 0.0001;
-                for (i, v) in left.iter().enumerate() {
-                    lines_buf[row_offset + i * 2 + 1].position[2] =
-                        base_height / 2.0 + v / max * ampli_top;
-                    lines_buf[row_offset + i * 2].position[2] =
-                        -base_height / 2.0 - v / max * ampli_bottom;
+                for (a, v) in row_amplitudes[..cols].iter_mut().zip(left.iter()) {
+                    *a = v / max;
                 }
 
                 let right = info
@@ -468,17 +457,17 @@ fn main() {
                     .right()
                     .slice(100.0, 800.0)
                     .fill_buckets(&mut row_spectrum[..]);
-                let row_offset = nrow * write_row + cols * 2;
                 let max = right.max() + 0.0001;
-                for (i, v) in right.iter().enumerate() {
-                    lines_buf[row_offset + i * 2 + 1].position[2] =
-                        base_height / 2.0 + v / max * ampli_top;
-                    lines_buf[row_offset + i * 2].position[2] =
-                        -base_height / 2.0 - v / max * ampli_bottom;
+                for (a, v) in row_amplitudes[cols..].iter_mut().zip(right.iter()) {
+                    *a = v / max;
                 }
             });
 
-            write_row = (write_row + 1) % rows;
+            spectral_rows.push_row(&row_amplitudes);
+            for (verts, a) in lines_buf.chunks_mut(2).zip(spectral_rows.as_slice()) {
+                verts[0].position[2] = -base_height / 2.0 - a * ampli_bottom;
+                verts[1].position[2] = base_height / 2.0 + a * ampli_top;
+            }
         }
         // }}}
         // }}}
@@ -508,8 +497,8 @@ fn main() {
 
         // Lines {{{
         let uniforms = uniform! {
-            perspective_matrix: Into::<[[f32; 4]; 4]>::into(perspective),
-            view_matrix: Into::<[[f32; 4]; 4]>::into(view),
+            perspective_matrix: perspective,
+            view_matrix: view,
             model_matrix: Into::<[[f32; 4]; 4]>::into(model_grid),
             Colors: &lines_colors,
             volume: rolling_volume,
@@ -526,8 +515,8 @@ fn main() {
 
         // Points {{{
         let uniforms = uniform! {
-            perspective_matrix: Into::<[[f32; 4]; 4]>::into(perspective),
-            view_matrix: Into::<[[f32; 4]; 4]>::into(view),
+            perspective_matrix: perspective,
+            view_matrix: view,
             model_matrix: Into::<[[f32; 4]; 4]>::into(model_grid),
             Colors: &points_colors,
             volume: rolling_volume,
@@ -607,11 +596,13 @@ fn main() {
                     glutin::event::WindowEvent::KeyboardInput {
                         input:
                             glutin::event::KeyboardInput {
-                                virtual_keycode: Some(glutin::event::VirtualKeyCode::Escape),
+                                virtual_keycode: Some(key),
                                 ..
                             },
                         ..
-                    } => closed = true,
+                    } if keys.key(&key) == Some(vis_core::helpers::input::Action::Quit) => {
+                        closed = true
+                    }
                     _ => (),
                 },
                 _ => (),
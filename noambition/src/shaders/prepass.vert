@@ -16,7 +16,7 @@ smooth out vec4 frag_color;
 
 void main() {
     frag_position = model_matrix * position;
-    frag_position.z += exp(-pow(frag_position.y / 5.0 - 4.0, 2.0)) * (pow(frag_position.x / 8.0, 2.0) * 2.0 + 0.1) * (volume * 30.0 + 0.1);
+    frag_position.z += exp(-pow(frag_position.y / 5.0 - 4.0, 2.0)) * (pow(frag_position.x / 8.0, 2.0) * 2.0 + 0.1) * (volume * 12.0 + 0.1);
     frag_color = colors[color_id];
     frag_color.a = frag_color.a * (1.0 - smoothstep(15.0, 25.0, frag_position.y));
     gl_Position = perspective_matrix * view_matrix * frag_position;
@@ -8,13 +8,13 @@ const LINES: usize = 200;
 struct AnalyzerResult {
     analyzer: analyzer::FourierAnalyzer,
     average: analyzer::Spectrum<Vec<f32>>,
-    beat: usize,
+    beat: u64,
 }
 
 fn main() {
     use sfml::graphics::RenderTarget;
 
-    vis_core::default_config();
+    vis_core::config_from_args();
     vis_core::default_log();
 
     let context_settings = sfml::window::ContextSettings {
@@ -35,11 +35,16 @@ fn main() {
     let view = graphics::View::from_rect(graphics::Rect::new(0.0, 0.0, 1.0, LINES as f32));
     window.set_view(&view);
 
+    // Smooth scaling of the history texture to the window size
+    let smooth = vis_core::CONFIG.get_or("spectral.smooth", true);
+
     let mut texture = graphics::Texture::new().unwrap();
-    assert!(texture.create(1600, 800));
+    assert!(texture.create(BUCKETS as u32, LINES as u32));
+    texture.set_smooth(smooth);
 
-    let mut rectangle = graphics::RectangleShape::new();
-    rectangle.set_size(system::Vector2f::new(1.0 / BUCKETS as f32, 1.0));
+    let mut spectrogram = analyzer::Spectrogram::new(LINES, BUCKETS);
+    let mut beats = std::collections::VecDeque::from(vec![false; LINES]);
+    let mut pixels = vec![0u8; BUCKETS * LINES * 4];
 
     // Analyzer {{{
     let mut frames = {
@@ -73,7 +78,8 @@ fn main() {
     };
     // }}}
 
-    let mut last_beat = 0;
+    let keys = vis_core::helpers::input::KeyMap::from_config("input.keys");
+    let mut beat_edge = vis_core::helpers::anim::BeatEdge::new();
     'main: for frame in frames.iter() {
         log::trace!("Frame: {:7}@{:.3}", frame.frame, frame.time);
 
@@ -82,62 +88,60 @@ fn main() {
 
             match event {
                 Event::Closed => break 'main,
-                Event::KeyPressed {
-                    code: sfml::window::Key::Escape,
-                    ..
-                } => break 'main,
+                Event::KeyPressed { code, .. }
+                    if keys.key(&code) == Some(vis_core::helpers::input::Action::Quit) =>
+                {
+                    break 'main
+                }
                 _ => (),
             }
         }
 
-        // Move window content up
+        frame.info(|info| {
+            spectrogram.push(&info.average);
+
+            beats.pop_front();
+            beats.push_back(beat_edge.update(info.beat));
+        });
+
+        // Render the history, newest row at the bottom
+        let offset = LINES - spectrogram.len();
+        for (y, (row, beat)) in spectrogram
+            .iter()
+            .zip(beats.iter().skip(offset))
+            .enumerate()
         {
-            use sfml::graphics::Transformable;
+            let max = row.max();
+            let n50 = row.freq_to_id(50.0);
+            let n100 = row.freq_to_id(100.0);
 
-            // SAFETY: Pray for the best.
-            unsafe {
-                texture.update_from_render_window(&window, 0, 0);
+            let start = (offset + y) * BUCKETS * 4;
+            let line = &mut pixels[start..start + BUCKETS * 4];
+            for (i, (b, pixel)) in row.iter().zip(line.chunks_exact_mut(4)).enumerate() {
+                let int = ((b / max).sqrt() * 255.0) as u8;
+                let color = if *beat {
+                    [255, 255, 255, 255]
+                } else if i == n50 || i == n100 {
+                    [255, 0, 0, 255]
+                } else {
+                    [int, int, int, 255]
+                };
+                pixel.copy_from_slice(&color);
             }
-            window.clear(graphics::Color::BLACK);
+        }
+
+        // SAFETY: `pixels` holds exactly BUCKETS x LINES RGBA pixels, the size of the texture.
+        unsafe {
+            texture.update_from_pixels(&pixels, BUCKETS as u32, LINES as u32, 0, 0);
+        }
+
+        window.clear(graphics::Color::BLACK);
+        {
             let mut rect_img = graphics::RectangleShape::with_texture(&texture);
             rect_img.set_size(system::Vector2f::new(1.0, LINES as f32));
-            rect_img.set_position(system::Vector2f::new(0.0, -1.0));
             window.draw(&rect_img);
         }
 
-        frame.info(|info| {
-            use sfml::graphics::Shape;
-
-            let max = info.average.max();
-            let n50 = info.average.freq_to_id(50.0);
-            let n100 = info.average.freq_to_id(100.0);
-
-            let beat = if info.beat > last_beat {
-                last_beat = info.beat;
-                rectangle.set_fill_color(graphics::Color::rgb(255, 255, 255));
-                true
-            } else {
-                false
-            };
-
-            for (i, b) in info.average.iter().enumerate() {
-                use sfml::graphics::Transformable;
-
-                let int = ((b / max).sqrt() * 255.0) as u8;
-                if !beat {
-                    rectangle.set_fill_color(graphics::Color::rgb(int, int, int));
-                    if i == n50 || i == n100 {
-                        rectangle.set_fill_color(graphics::Color::rgb(255, 0, 0));
-                    }
-                }
-                rectangle.set_position(system::Vector2f::new(
-                    i as f32 / BUCKETS as f32,
-                    LINES as f32 - 1.0,
-                ));
-                window.draw(&rectangle);
-            }
-        });
-
         window.display();
         std::thread::sleep(std::time::Duration::from_millis(10));
     }
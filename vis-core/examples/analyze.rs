@@ -28,7 +28,7 @@ fn main() {
             analyzer.analyze(samples);
 
             info.spectrum.fill_from(&analyzer.average());
-            info.volume = samples.volume(0.3) * 400.0;
+            info.volume = samples.volume(0.3) * 160.0;
             info.beat = info.spectrum.slice(50.0, 100.0).max() * 0.01;
             info
         },
@@ -0,0 +1,85 @@
+//! Compare the complex transform of both channels against `realfft`
+//!
+//! The analyzer transforms the real signals of both channels with one complex FFT each and
+//! only looks at the lower half of the output.  `realfft` computes just that half with a
+//! half-length complex FFT, which in theory halves the work.  This benchmark measures whether
+//! that pays off against the SIMD kernels `rustfft` uses for the full transform.
+//!
+//! Run with `cargo run --release --example fft_bench`.
+use std::time;
+use vis_core::analyzer::fourier::{FftBackend, RustFftBackend};
+
+use rustfft::num_complex::Complex;
+
+fn bench<F: FnMut()>(iterations: usize, mut f: F) -> time::Duration {
+    // Warm up caches and the scratch allocations of rustfft
+    for _ in 0..iterations / 10 {
+        f();
+    }
+
+    let start = time::Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    start.elapsed() / iterations as u32
+}
+
+fn main() {
+    const ITERATIONS: usize = 20000;
+
+    for &length in &[512, 2048] {
+        let backend = RustFftBackend::new(length);
+        let real_fft = realfft::RealFftPlanner::<f32>::new().plan_fft_forward(length);
+        let signal = |freq: f32| {
+            (0..length)
+                .map(|i| (i as f32 * freq).sin())
+                .collect::<Vec<_>>()
+        };
+        let inputs = [signal(0.1), signal(0.37)];
+
+        let mut complex = [
+            vec![Complex::new(0.0, 0.0); length],
+            vec![Complex::new(0.0, 0.0); length],
+        ];
+        let mut real_input = real_fft.make_input_vec();
+        let mut real = [real_fft.make_output_vec(), real_fft.make_output_vec()];
+        let mut scratch = real_fft.make_scratch_vec();
+
+        let process_complex = || {
+            for (buf, input) in complex.iter_mut().zip(inputs.iter()) {
+                for (c, s) in buf.iter_mut().zip(input.iter()) {
+                    *c = Complex::new(*s, 0.0);
+                }
+                backend.process(buf);
+            }
+        };
+        let process_real = || {
+            for (out, input) in real.iter_mut().zip(inputs.iter()) {
+                real_input.copy_from_slice(input);
+                real_fft
+                    .process_with_scratch(&mut real_input, out, &mut scratch)
+                    .unwrap();
+            }
+        };
+
+        let complex_time = bench(ITERATIONS, process_complex);
+        let real_time = bench(ITERATIONS, process_real);
+
+        // Both variants have to agree on the bins the analyzer uses
+        let error = complex
+            .iter()
+            .zip(real.iter())
+            .flat_map(|(c, r)| c.iter().zip(r.iter()))
+            .map(|(a, b)| (a - b).norm())
+            .fold(0.0, f32::max);
+
+        println!(
+            "length {:5}: complex {:>10?}, realfft {:>10?}, speedup {:.2}x (error {:e})",
+            length,
+            complex_time,
+            real_time,
+            complex_time.as_secs_f64() / real_time.as_secs_f64(),
+            error,
+        );
+    }
+}
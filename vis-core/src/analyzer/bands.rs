@@ -0,0 +1,148 @@
+//! Logarithmic Frequency Bands
+use crate::analyzer;
+use crate::analyzer::spectrum::Storage;
+
+/// Precomputed logarithmically spaced frequency bands
+///
+/// Bar visualizers usually want bands of equal musical width instead of equal width in Hz.
+/// `LogBands` computes the band edges once and then sums the energy of any spectrum into
+/// the bands.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let bands = analyzer::LogBands::new(50.0, 4000.0, 16);
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 512], 0.0, 4000.0);
+///
+/// let mut bars = [0.0; 16];
+/// bands.accumulate(&spectrum, &mut bars);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LogBands {
+    edges: Vec<analyzer::Frequency>,
+}
+
+impl LogBands {
+    /// Create `count` bands spanning `lowest` to `highest`
+    pub fn new(
+        lowest: analyzer::Frequency,
+        highest: analyzer::Frequency,
+        count: usize,
+    ) -> LogBands {
+        assert!(lowest > 0.0, "Lowest frequency must be positive!");
+        assert!(lowest < highest, "Invalid frequency range!");
+        assert!(count > 0, "At least one band is needed!");
+
+        let ratio = (highest / lowest).powf(1.0 / count as f32);
+        let mut edges = (0..=count)
+            .map(|i| lowest * ratio.powi(i as i32))
+            .collect::<Vec<_>>();
+        // Avoid rounding errors at the top
+        edges[count] = highest;
+
+        LogBands { edges }
+    }
+
+    /// Return the number of bands
+    pub fn len(&self) -> usize {
+        self.edges.len() - 1
+    }
+
+    /// Return whether there are no bands
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the `len() + 1` band edges, starting with the lowest frequency
+    pub fn edges(&self) -> &[analyzer::Frequency] {
+        &self.edges
+    }
+
+    /// Return the lower and upper edge of band `i`
+    pub fn band(&self, i: usize) -> (analyzer::Frequency, analyzer::Frequency) {
+        (self.edges[i], self.edges[i + 1])
+    }
+
+    /// Return the band containing frequency `f` or `None` if it is out of range
+    ///
+    /// Bands include their lower edge.  The highest band also includes its upper edge.
+    pub fn band_of(&self, f: analyzer::Frequency) -> Option<usize> {
+        if f < self.edges[0] || f > self.edges[self.len()] {
+            return None;
+        }
+
+        let i = self.edges.partition_point(|e| *e <= f);
+        Some((i - 1).min(self.len() - 1))
+    }
+
+    /// Sum the energy of `spectrum` into `out`, one element per band
+    ///
+    /// Buckets outside of the bands are ignored.
+    pub fn accumulate<S: Storage>(
+        &self,
+        spectrum: &analyzer::Spectrum<S>,
+        out: &mut [analyzer::SignalStrength],
+    ) {
+        assert_eq!(
+            out.len(),
+            self.len(),
+            "Output has the wrong number of bands!"
+        );
+
+        for o in out.iter_mut() {
+            *o = 0.0;
+        }
+
+        for (i, v) in spectrum.iter().enumerate() {
+            if let Some(band) = self.band_of(spectrum.id_to_freq(i)) {
+                out[band] += v;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edges() {
+        let bands = LogBands::new(50.0, 3200.0, 6);
+
+        assert_eq!(bands.len(), 6);
+        for (e, expected) in bands
+            .edges()
+            .iter()
+            .zip([50.0, 100.0, 200.0, 400.0, 800.0, 1600.0, 3200.0].iter())
+        {
+            assert!((e - expected).abs() < 1e-2, "{} != {}", e, expected);
+        }
+
+        assert_eq!(bands.band_of(49.0), None);
+        assert_eq!(bands.band_of(50.0), Some(0));
+        assert_eq!(bands.band_of(150.0), Some(1));
+        assert_eq!(bands.band_of(3200.0), Some(5));
+        assert_eq!(bands.band_of(3201.0), None);
+    }
+
+    #[test]
+    fn test_accumulate() {
+        let bands = LogBands::new(50.0, 3200.0, 6);
+        let spectrum = analyzer::Spectrum::new(
+            (0..401).map(|i| (i % 13) as f32).collect::<Vec<_>>(),
+            0.0,
+            4000.0,
+        );
+
+        let mut out = [0.0; 6];
+        bands.accumulate(&spectrum, &mut out);
+
+        // Energy in range is conserved
+        let expected = spectrum.slice(50.0, 3200.0).iter().sum::<f32>();
+        assert_eq!(out.iter().sum::<f32>(), expected);
+
+        // Upper bands span more buckets
+        let count = |(low, high): (f32, f32)| spectrum.slice(low, high).len();
+        assert!(count(bands.band(5)) > count(bands.band(0)));
+    }
+}
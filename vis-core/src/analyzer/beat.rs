@@ -1,5 +1,6 @@
 //! Beat Detection
 use crate::analyzer;
+use std::collections;
 
 /// Builder for BeatDetector
 ///
@@ -42,6 +43,19 @@ pub struct BeatBuilder {
     ///
     /// Defaults to `8000` or `"audio.rate"`.
     pub rate: Option<usize>,
+
+    /// Time constant of the smoothed volume in detection cycles
+    ///
+    /// The smoothed volume covers about two thirds of a sudden change after this many cycles.
+    /// `1.0` disables smoothing.  Defaults to `8.0`, can also be set from config as
+    /// `"audio.beat.volume_smoothing"`.
+    pub volume_smoothing: Option<f32>,
+
+    /// Number of intervals between beats used for the tempo estimate
+    ///
+    /// Must be at least `3`.  Defaults to `8`, can also be set from config as
+    /// `"audio.beat.history"`.
+    pub history: Option<usize>,
 }
 
 impl BeatBuilder {
@@ -90,6 +104,18 @@ impl BeatBuilder {
         self
     }
 
+    /// Set the time constant of the smoothed volume
+    pub fn volume_smoothing(&mut self, cycles: f32) -> &mut BeatBuilder {
+        self.volume_smoothing = Some(cycles);
+        self
+    }
+
+    /// Set the number of beat intervals for the tempo estimate
+    pub fn history(&mut self, n: usize) -> &mut BeatBuilder {
+        self.history = Some(n);
+        self
+    }
+
     /// Build the detector
     pub fn build(&mut self) -> BeatDetector {
         BeatDetector::from_builder(self)
@@ -109,6 +135,8 @@ impl BeatBuilder {
 ///     .fourier_length(16)
 ///     .downsample(10)
 ///     .rate(8000)
+///     .volume_smoothing(8.0)
+///     .history(8)
 ///     .build();
 ///
 /// let isbeat = beat.detect(&samples);
@@ -119,12 +147,18 @@ pub struct BeatDetector {
     range: (analyzer::Frequency, analyzer::Frequency),
 
     last_volume: analyzer::SignalStrength,
+    smoothing: analyzer::SignalStrength,
+    smoothed_volume: analyzer::SignalStrength,
     last_delta: analyzer::SignalStrength,
     last_beat_delta: analyzer::SignalStrength,
+    last_confidence: analyzer::SignalStrength,
 
     last_peak: analyzer::SignalStrength,
     last_valley: analyzer::SignalStrength,
 
+    history: usize,
+    beat_times: collections::VecDeque<f64>,
+
     analyzer: analyzer::FourierAnalyzer,
 }
 
@@ -134,6 +168,18 @@ impl BeatDetector {
         let decay = build
             .decay
             .unwrap_or_else(|| crate::CONFIG.get_or("audio.beat.decay", 2000.0));
+        let volume_smoothing = build
+            .volume_smoothing
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.beat.volume_smoothing", 8.0));
+        assert!(
+            volume_smoothing >= 1.0,
+            "Volume smoothing must be at least one cycle!"
+        );
+        let history = build
+            .history
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.beat.history", 8));
+        assert!(history >= 3, "History must hold at least 3 intervals!");
+
         BeatDetector {
             decay: 1.0 - 1.0 / decay,
             trigger: build
@@ -147,12 +193,18 @@ impl BeatDetector {
             }),
 
             last_volume: 0.0,
+            smoothing: 1.0 - 1.0 / volume_smoothing,
+            smoothed_volume: 0.0,
             last_delta: 0.0,
             last_beat_delta: 0.0,
+            last_confidence: 0.0,
 
             last_peak: 0.0,
             last_valley: 0.0,
 
+            history,
+            beat_times: collections::VecDeque::with_capacity(history + 1),
+
             analyzer: analyzer::FourierBuilder {
                 window: Some(analyzer::window::nuttall),
                 length: Some(
@@ -170,6 +222,10 @@ impl BeatDetector {
                         .rate
                         .unwrap_or_else(|| crate::CONFIG.get_or("audio.rate", 8000)),
                 ),
+                channels: Some(analyzer::fourier::Channels::Both),
+                backend: None,
+                drop_dc: Some(false),
+                hop: None,
             }
             .plan(),
         }
@@ -180,6 +236,25 @@ impl BeatDetector {
         self.last_volume
     }
 
+    /// Get the volume smoothed over the last detection cycles
+    ///
+    /// Unlike [`last_volume`](#method.last_volume), this follows changes with a delay set by
+    /// [`BeatBuilder::volume_smoothing`](struct.BeatBuilder.html#structfield.volume_smoothing),
+    /// so it can drive visuals directly without flickering.
+    pub fn smoothed_volume(&self) -> analyzer::SignalStrength {
+        self.smoothed_volume
+    }
+
+    /// Get the confidence of the beat detected during the last detection cycle
+    ///
+    /// The confidence is the size of the beat relative to the previous beats, in `[0, 1]`.
+    /// A beat barely passing the trigger has a confidence close to the trigger value, while
+    /// a clearly dominant beat has a confidence of `1.0`.  Cycles without a beat have a
+    /// confidence of `0.0`.
+    pub fn last_confidence(&self) -> analyzer::SignalStrength {
+        self.last_confidence
+    }
+
     /// Detect a beat
     ///
     /// Returns true if this cycle is a beat and false otherwise.
@@ -191,6 +266,72 @@ impl BeatDetector {
             .slice(self.range.0, self.range.1)
             .mean();
 
+        let isbeat = self.detect_volume(volume);
+        if isbeat {
+            self.record_beat(samples.generation() as f64 / samples.rate() as f64);
+        }
+        isbeat
+    }
+
+    /// Detect a beat in an already computed spectrum
+    ///
+    /// Use this instead of [`detect`](#method.detect) to share the transform of the main
+    /// analyzer instead of computing a second one.  The spectrum must cover the beat range
+    /// and should be recent, because the detection latency depends on its window.
+    ///
+    /// A spectrum carries no timestamp, so beats detected this way don't count towards the
+    /// [tempo estimate](#method.bpm).
+    pub fn detect_from_spectrum<S: analyzer::spectrum::Storage>(
+        &mut self,
+        spectrum: &analyzer::Spectrum<S>,
+    ) -> bool {
+        let volume = spectrum.slice(self.range.0, self.range.1).mean();
+
+        self.detect_volume(volume)
+    }
+
+    /// Estimate the tempo from the intervals between the last beats
+    ///
+    /// Looks at the last [`history`](struct.BeatBuilder.html#structfield.history) intervals.
+    /// Intervals more than 25% off their median, eg. from a missed or spurious beat, are
+    /// ignored and the rest are averaged.  Returns `None` until at least three consistent
+    /// intervals were seen.  Only beats found by [`detect`](#method.detect) are timed.
+    pub fn bpm(&self) -> Option<f32> {
+        let mut intervals = self
+            .beat_times
+            .iter()
+            .zip(self.beat_times.iter().skip(1))
+            .map(|(a, b)| b - a)
+            .collect::<Vec<_>>();
+        if intervals.len() < 3 {
+            return None;
+        }
+
+        intervals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let median = intervals[intervals.len() / 2];
+        let consistent = intervals
+            .iter()
+            .filter(|i| (*i - median).abs() <= median * 0.25)
+            .collect::<Vec<_>>();
+        if consistent.len() < 3 || median <= 0.0 {
+            return None;
+        }
+
+        let mean = consistent.iter().copied().sum::<f64>() / consistent.len() as f64;
+        Some((60.0 / mean) as f32)
+    }
+
+    /// Remember the time of a beat in seconds
+    fn record_beat(&mut self, time: f64) {
+        if self.beat_times.len() > self.history {
+            self.beat_times.pop_front();
+        }
+        self.beat_times.push_back(time);
+    }
+
+    fn detect_volume(&mut self, volume: analyzer::SignalStrength) -> bool {
+        self.last_confidence = 0.0;
+
         // Decay beat_delta to allow quieter beats to be detected
         self.last_beat_delta = self.last_beat_delta * self.decay;
         let delta = volume - self.last_volume;
@@ -201,6 +342,7 @@ impl BeatDetector {
 
             // Check if the peak is big enough
             if beat_delta > (self.last_beat_delta * self.trigger) {
+                self.last_confidence = (beat_delta / self.last_beat_delta).min(1.0);
                 self.last_beat_delta = self.last_beat_delta.max(beat_delta);
                 true
             } else {
@@ -214,6 +356,8 @@ impl BeatDetector {
         };
 
         self.last_volume = volume;
+        self.smoothed_volume =
+            self.smoothed_volume * self.smoothing + volume * (1.0 - self.smoothing);
         // Only write delta if the last two volumes weren't the same
         if delta != 0.0 {
             self.last_delta = delta;
@@ -222,3 +366,338 @@ impl BeatDetector {
         isbeat
     }
 }
+
+/// Beat detection in multiple frequency bands
+///
+/// Runs one [`BeatDetector`](struct.BeatDetector.html) per band on a shared spectrum and
+/// fuses their beats into a single weighted confidence, eg. to let the kick drive a pulse
+/// more than the hi-hats.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// # let spectrum = analyzer::Spectrum::new(vec![0.0; 400], 0.0, 4000.0);
+/// let band = |low, high| {
+///     analyzer::BeatBuilder::new()
+///         .decay(2000.0)
+///         .trigger(0.4)
+///         .range(low, high)
+///         .fourier_length(16)
+///         .downsample(10)
+///         .rate(8000)
+///         .volume_smoothing(8.0)
+///         .history(8)
+///         .build()
+/// };
+/// let mut beat = analyzer::MultiBandBeat::new()
+///     .band(band(50.0, 100.0), 1.0)
+///     .band(band(3000.0, 3500.0), 0.3);
+///
+/// beat.detect(&spectrum);
+/// let pulse = beat.fused_confidence();
+/// ```
+#[derive(Default)]
+pub struct MultiBandBeat {
+    bands: Vec<(BeatDetector, analyzer::SignalStrength)>,
+    beats: Vec<bool>,
+}
+
+impl MultiBandBeat {
+    /// Create a detector without any bands
+    pub fn new() -> MultiBandBeat {
+        Default::default()
+    }
+
+    /// Add a band with its weight in the fused confidence
+    ///
+    /// The band's frequency range is the range of `detector`.
+    pub fn band(
+        mut self,
+        detector: BeatDetector,
+        weight: analyzer::SignalStrength,
+    ) -> MultiBandBeat {
+        assert!(weight >= 0.0, "Band weight must not be negative!");
+
+        self.bands.push((detector, weight));
+        self.beats.push(false);
+        self
+    }
+
+    /// Detect beats in all bands of `spectrum`
+    ///
+    /// Returns true if any band has a beat in this cycle.
+    pub fn detect<S: analyzer::spectrum::Storage>(
+        &mut self,
+        spectrum: &analyzer::Spectrum<S>,
+    ) -> bool {
+        for ((detector, _), beat) in self.bands.iter_mut().zip(self.beats.iter_mut()) {
+            *beat = detector.detect_from_spectrum(spectrum);
+        }
+
+        self.beats.iter().any(|b| *b)
+    }
+
+    /// Return which bands had a beat during the last detection cycle
+    pub fn beats(&self) -> &[bool] {
+        &self.beats
+    }
+
+    /// Return the weighted average of the band confidences of the last detection cycle
+    ///
+    /// Bands without a beat contribute a confidence of `0`, so the result is in `[0, 1]` and
+    /// only reaches `1` if all bands had a strong beat.
+    pub fn fused_confidence(&self) -> analyzer::SignalStrength {
+        let total = self
+            .bands
+            .iter()
+            .map(|(_, w)| w)
+            .sum::<analyzer::SignalStrength>();
+        if total == 0.0 {
+            return 0.0;
+        }
+
+        self.bands
+            .iter()
+            .map(|(detector, weight)| detector.last_confidence() * weight)
+            .sum::<analyzer::SignalStrength>()
+            / total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confidence() {
+        let mut beat = BeatBuilder::new()
+            .decay(2000.0)
+            .trigger(0.4)
+            .range(50.0, 100.0)
+            .fourier_length(16)
+            .downsample(10)
+            .rate(8000)
+            .volume_smoothing(8.0)
+            .history(8)
+            .build();
+
+        let mut confidences = Vec::new();
+        for volume in [0.0, 10.0, 0.0, 5.0, 0.0, 9.0, 0.0, 2.0, 0.0] {
+            if beat.detect_volume(volume) {
+                confidences.push(beat.last_confidence());
+            } else {
+                assert_eq!(beat.last_confidence(), 0.0);
+            }
+        }
+
+        // The 2.0 peak is too small to be a beat
+        assert_eq!(confidences.len(), 3);
+        assert_eq!(confidences[0], 1.0);
+        assert!(confidences[1] >= 0.4 && confidences[1] < 0.6);
+        assert!(confidences[2] > confidences[1]);
+    }
+
+    #[test]
+    fn test_detect_from_spectrum() {
+        let build = || {
+            BeatBuilder::new()
+                .decay(2000.0)
+                .trigger(0.4)
+                .range(50.0, 100.0)
+                .fourier_length(16)
+                .downsample(10)
+                .rate(8000)
+                .volume_smoothing(8.0)
+                .history(8)
+                .build()
+        };
+        let mut beat = build();
+        let mut shared = build();
+        let mut analyzer = analyzer::FourierBuilder::new()
+            .window(analyzer::window::nuttall)
+            .length(16)
+            .downsample(10)
+            .rate(8000)
+            .channels(analyzer::fourier::Channels::Both)
+            .drop_dc(false)
+            .plan();
+
+        let buf = analyzer::SampleBuffer::new(1000, 8000);
+        let mut beats = 0;
+        for i in 0..200 {
+            // A 75 Hz tone pulsing twice a second
+            let samples = (0..80)
+                .map(|j| {
+                    let t = (i * 80 + j) as f32 / 8000.0;
+                    let envelope = (-(t % 0.5) * 20.0).exp();
+                    let s = (t * 75.0 * 2.0 * std::f32::consts::PI).sin() * envelope;
+                    [s, s]
+                })
+                .collect::<Vec<_>>();
+            buf.push(&samples);
+
+            analyzer.analyze(&buf);
+            let isbeat = beat.detect(&buf);
+            assert_eq!(isbeat, shared.detect_from_spectrum(&analyzer.average()));
+            assert_eq!(beat.last_confidence(), shared.last_confidence());
+
+            if isbeat {
+                beats += 1;
+            }
+        }
+
+        assert!(beats > 0);
+    }
+
+    #[test]
+    fn test_fused_confidence() {
+        let band = |low, high| {
+            BeatBuilder::new()
+                .decay(2000.0)
+                .trigger(0.4)
+                .range(low, high)
+                .fourier_length(16)
+                .downsample(10)
+                .rate(8000)
+                .volume_smoothing(8.0)
+                .history(8)
+                .build()
+        };
+        let multi = || {
+            MultiBandBeat::new()
+                .band(band(50.0, 100.0), 1.0)
+                .band(band(3000.0, 3500.0), 0.3)
+        };
+
+        // Pulse the buckets between `low` and `high` once
+        let pulse = |beat: &mut MultiBandBeat, low, high| {
+            let mut fused: analyzer::SignalStrength = 0.0;
+            for volume in [0.0, 0.5, 1.0, 0.5, 0.0, 0.0] {
+                let mut spectrum = analyzer::Spectrum::new(vec![0.0; 401], 0.0, 4000.0);
+                for i in spectrum.freq_to_id(low)..=spectrum.freq_to_id(high) {
+                    spectrum[i] = volume;
+                }
+                beat.detect(&spectrum);
+                fused = fused.max(beat.fused_confidence());
+            }
+            fused
+        };
+
+        let mut kick = multi();
+        let kick_fused = pulse(&mut kick, 50.0, 100.0);
+
+        let mut hats = multi();
+        let hat_fused = pulse(&mut hats, 3000.0, 3500.0);
+
+        assert!(hat_fused > 0.0);
+        assert!(kick_fused > hat_fused);
+        assert!(kick_fused <= 1.0);
+        assert_eq!(kick.beats(), [false, false]);
+    }
+
+    #[test]
+    fn test_smoothed_volume() {
+        let mut beat = BeatBuilder::new()
+            .decay(2000.0)
+            .trigger(0.4)
+            .range(50.0, 100.0)
+            .fourier_length(16)
+            .downsample(10)
+            .rate(8000)
+            .volume_smoothing(8.0)
+            .history(8)
+            .build();
+
+        // Band energy jumping from 0 to a noisy 1.0
+        let (mut raw, mut smoothed) = (Vec::new(), Vec::new());
+        for i in 0..64 {
+            let noise = if i % 2 == 0 { 0.3 } else { -0.3 };
+            let volume = if i < 16 { 0.0 } else { 1.0 + noise };
+            beat.detect_volume(volume);
+            assert_eq!(beat.last_volume(), volume);
+            raw.push(beat.last_volume());
+            smoothed.push(beat.smoothed_volume());
+        }
+
+        // Lags behind the step
+        assert_eq!(raw[16], 1.3);
+        assert!(smoothed[16] < 0.2);
+        assert!(smoothed[23] > 0.5 && smoothed[23] < 0.8);
+
+        // Follows the level, but with much less noise
+        let swing = |v: &[f32]| {
+            v.windows(2)
+                .map(|w| (w[1] - w[0]).abs())
+                .fold(0.0f32, f32::max)
+        };
+        assert!((smoothed[63] - 1.0).abs() < 0.1);
+        assert!(swing(&smoothed[48..]) < swing(&raw[48..]) / 5.0);
+    }
+
+    #[test]
+    fn test_bpm_intervals() {
+        let mut beat = BeatBuilder::new()
+            .decay(2000.0)
+            .trigger(0.4)
+            .range(50.0, 100.0)
+            .fourier_length(16)
+            .downsample(10)
+            .rate(8000)
+            .volume_smoothing(8.0)
+            .history(8)
+            .build();
+        assert_eq!(beat.bpm(), None);
+
+        // 128 BPM with some timing noise
+        let interval = 60.0 / 128.0;
+        for (i, noise) in [0.0, 0.01, -0.01, 0.005].iter().enumerate() {
+            beat.record_beat(i as f64 * interval + noise);
+        }
+        assert!((beat.bpm().unwrap() - 128.0).abs() < 3.0);
+
+        // A spurious beat in between and a missed one are ignored
+        beat.record_beat(3.5 * interval);
+        beat.record_beat(4.0 * interval);
+        beat.record_beat(6.0 * interval);
+        for i in 7..10 {
+            beat.record_beat(i as f64 * interval);
+        }
+        assert!((beat.bpm().unwrap() - 128.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_bpm_detect() {
+        let mut beat = BeatBuilder::new()
+            .decay(2000.0)
+            .trigger(0.4)
+            .range(50.0, 100.0)
+            .fourier_length(16)
+            .downsample(10)
+            .rate(8000)
+            .volume_smoothing(8.0)
+            .history(8)
+            .build();
+
+        // A 75 Hz tone pulsing twice a second, ie. 120 BPM
+        let buf = analyzer::SampleBuffer::new(1000, 8000);
+        let mut estimates = Vec::new();
+        for i in 0..600 {
+            let samples = (0..80)
+                .map(|j| {
+                    let t = (i * 80 + j) as f32 / 8000.0;
+                    let envelope = (-(t % 0.5) * 20.0).exp();
+                    let s = (t * 75.0 * 2.0 * std::f32::consts::PI).sin() * envelope;
+                    [s, s]
+                })
+                .collect::<Vec<_>>();
+            buf.push(&samples);
+
+            beat.detect(&buf);
+            estimates.push(beat.bpm());
+        }
+
+        assert_eq!(estimates[0], None);
+        let bpm = estimates.last().unwrap().unwrap();
+        assert!((bpm - 120.0).abs() < 2.0, "Estimated {} BPM", bpm);
+    }
+}
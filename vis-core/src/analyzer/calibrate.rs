@@ -0,0 +1,204 @@
+//! Input Calibration
+use crate::analyzer;
+
+/// Settings suggested by a [`Calibrator`](struct.Calibrator.html)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    /// Gain for both channels, relative to the gains in effect while calibrating
+    ///
+    /// Use it for [`RecorderBuilder::channel_gains`](../recorder/struct.RecorderBuilder.html#method.channel_gains).
+    pub gain: analyzer::Sample,
+
+    /// Beat trigger, for [`BeatBuilder::trigger`](struct.BeatBuilder.html#structfield.trigger)
+    pub trigger: analyzer::SignalStrength,
+
+    /// Volume of the quietest parts of the input, after applying `gain`
+    ///
+    /// Anything at or below this level is noise, which makes it a good
+    /// [silence threshold](struct.SilenceBuilder.html#structfield.threshold).
+    pub noise_floor: analyzer::SignalStrength,
+}
+
+impl Calibration {
+    /// Format the suggestions as a config snippet
+    ///
+    /// Merge it into the config file to persist the calibration.
+    pub fn to_toml(&self) -> String {
+        format!(
+            "[audio]\nchannel_gains = [{:?}, {:?}]\n\n\
+             [audio.beat]\ntrigger = {:?}\n\n\
+             [audio.silence]\nthreshold = {:?}\n",
+            self.gain, self.gain, self.trigger, self.noise_floor,
+        )
+    }
+}
+
+/// Guided setup of gain and thresholds for a new venue
+///
+/// Observes the volume for a few seconds and derives settings from its statistics:
+///
+/// * The **gain** brings the 95th percentile of the volume to the target level.
+/// * The **trigger** follows the dynamics of the input.  Punchy music with a large gap
+///   between its loud and its typical parts gets a higher trigger to reject small bumps,
+///   heavily compressed music a lower one.  It stays in `[0.2, 0.6]`.
+/// * The **noise floor** is the 5th percentile of the volume.  For a good estimate, the
+///   observed period should contain a quiet moment.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// # let samples = analyzer::SampleBuffer::new(32000, 8000);
+/// # let times = vec![0.0, 0.5, 1.0, 5.5];
+/// let mut calibrator = analyzer::Calibrator::new(5.0);
+///
+/// for time in times {
+///     if calibrator.update(samples.volume(0.1), time) {
+///         break;
+///     }
+/// }
+///
+/// if let Some(calibration) = calibrator.calibration() {
+///     println!("{}", calibration.to_toml());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Calibrator {
+    duration: f32,
+    target: analyzer::SignalStrength,
+
+    start: Option<f32>,
+    done: bool,
+
+    floor: analyzer::QuantileTracker,
+    median: analyzer::QuantileTracker,
+    peak: analyzer::QuantileTracker,
+}
+
+impl Calibrator {
+    /// Create a calibrator observing the input for `duration` seconds
+    pub fn new(duration: f32) -> Calibrator {
+        assert!(duration > 0.0, "Calibration duration must be positive!");
+
+        Calibrator {
+            duration,
+            target: 0.5,
+
+            start: None,
+            done: false,
+
+            floor: analyzer::QuantileTracker::new(0.05),
+            median: analyzer::QuantileTracker::new(0.5),
+            peak: analyzer::QuantileTracker::new(0.95),
+        }
+    }
+
+    /// Set the volume the loud parts of the input should reach after applying the gain
+    ///
+    /// Defaults to `0.5`.
+    pub fn target(mut self, target: analyzer::SignalStrength) -> Calibrator {
+        assert!(target > 0.0, "Target volume must be positive!");
+        self.target = target;
+        self
+    }
+
+    /// Feed the current volume at `time` seconds
+    ///
+    /// Returns true once the calibration period is over.  Later volumes are ignored.
+    pub fn update(&mut self, volume: analyzer::SignalStrength, time: f32) -> bool {
+        if self.done {
+            return true;
+        }
+
+        let start = *self.start.get_or_insert(time);
+        self.floor.update(volume);
+        self.median.update(volume);
+        self.peak.update(volume);
+
+        if time - start >= self.duration {
+            log::debug!("Calibration finished after {}s", time - start);
+            self.done = true;
+        }
+        self.done
+    }
+
+    /// Return whether the calibration period is over
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Return the suggested settings from the volumes seen so far
+    ///
+    /// Can be called before the period is over for a preview.  Returns `None` if nothing but
+    /// silence was observed.
+    pub fn calibration(&self) -> Option<Calibration> {
+        let peak = self.peak.quantile();
+        if peak <= 0.0 {
+            return None;
+        }
+
+        let gain = self.target / peak;
+        let dynamics = ((peak - self.median.quantile()) / peak).clamp(0.0, 1.0);
+
+        Some(Calibration {
+            gain,
+            trigger: 0.2 + 0.4 * dynamics,
+            noise_floor: self.floor.quantile().max(0.0) * gain,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_level() {
+        let mut calibrator = Calibrator::new(5.0);
+        assert!(calibrator.calibration().is_none());
+
+        // Steady input around 0.1 with a short quiet break
+        let mut i = 0;
+        while !calibrator.update(
+            if (200..300).contains(&i) {
+                0.001
+            } else {
+                0.1 + 0.02 * (i as f32 * 0.7).sin()
+            },
+            i as f32 * 0.01,
+        ) {
+            i += 1;
+        }
+        assert_eq!(i, 500);
+        assert!(calibrator.is_done());
+
+        let calibration = calibrator.calibration().unwrap();
+        assert!(
+            calibration.gain > 3.8 && calibration.gain < 4.6,
+            "{:?}",
+            calibration
+        );
+        // Little dynamics, a low trigger
+        assert!(calibration.trigger >= 0.2 && calibration.trigger < 0.3);
+        assert!(calibration.noise_floor < 0.01);
+
+        let config = calibration
+            .to_toml()
+            .parse::<ezconf::toml::Value>()
+            .unwrap();
+        assert_eq!(
+            config["audio"]["beat"]["trigger"]
+                .as_float()
+                .map(|t| t as f32),
+            Some(calibration.trigger)
+        );
+    }
+
+    #[test]
+    fn test_silence() {
+        let mut calibrator = Calibrator::new(1.0);
+        for i in 0..200 {
+            calibrator.update(0.0, i as f32 * 0.01);
+        }
+        assert!(calibrator.calibration().is_none());
+    }
+}
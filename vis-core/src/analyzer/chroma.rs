@@ -0,0 +1,148 @@
+//! Chroma / Pitch Class Profile
+use crate::analyzer;
+use crate::analyzer::spectrum::Storage;
+
+/// Names of the 12 pitch classes, starting with C
+pub const PITCH_CLASSES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Return the pitch class (`0` = C, `11` = B) nearest to a frequency
+///
+/// Uses equal temperament with A4 tuned to 440 Hz.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer::chroma;
+/// assert_eq!(chroma::pitch_class(440.0), 9);
+/// assert_eq!(chroma::PITCH_CLASSES[chroma::pitch_class(261.63)], "C");
+/// ```
+pub fn pitch_class(f: analyzer::Frequency) -> usize {
+    assert!(f > 0.0, "Frequency must be positive!");
+
+    let note = 69.0 + 12.0 * (f / 440.0).log2();
+    (note.round() as i64).rem_euclid(12) as usize
+}
+
+/// Spectrum folded into 12 pitch classes
+///
+/// The energy of all buckets belonging to the same semitone is summed across octaves.
+/// Useful for key-reactive color themes.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 4096], 0.0, 4000.0);
+///
+/// let mut chroma = analyzer::chroma::ChromaVector::new();
+/// chroma.update(&spectrum);
+/// if let Some(p) = chroma.dominant_pitch_class() {
+///     println!("Dominant: {}", analyzer::chroma::PITCH_CLASSES[p]);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChromaVector {
+    chroma: [analyzer::SignalStrength; 12],
+    low: analyzer::Frequency,
+    high: analyzer::Frequency,
+}
+
+impl Default for ChromaVector {
+    fn default() -> ChromaVector {
+        ChromaVector::new()
+    }
+}
+
+impl ChromaVector {
+    /// Create a new chroma vector
+    pub fn new() -> ChromaVector {
+        ChromaVector {
+            chroma: [0.0; 12],
+            low: 50.0,
+            high: 5000.0,
+        }
+    }
+
+    /// Set the frequency range taken into account
+    ///
+    /// Low buckets are too coarse to separate semitones, so they are ignored.  Defaults
+    /// to 50 Hz - 5000 Hz.
+    pub fn range(mut self, low: analyzer::Frequency, high: analyzer::Frequency) -> ChromaVector {
+        assert!(low > 0.0 && low < high, "Invalid frequency range!");
+
+        self.low = low;
+        self.high = high;
+        self
+    }
+
+    /// Fold a spectrum into the 12 pitch classes
+    pub fn update<S: Storage>(
+        &mut self,
+        spectrum: &analyzer::Spectrum<S>,
+    ) -> &[analyzer::SignalStrength; 12] {
+        self.chroma = [0.0; 12];
+
+        for (i, v) in spectrum.iter().enumerate() {
+            let f = spectrum.id_to_freq(i);
+            if f >= self.low && f <= self.high {
+                self.chroma[pitch_class(f)] += v;
+            }
+        }
+
+        &self.chroma
+    }
+
+    /// Return the chroma calculated during the last update, starting with C
+    pub fn chroma(&self) -> &[analyzer::SignalStrength; 12] {
+        &self.chroma
+    }
+
+    /// Return the pitch class with the most energy or `None` if the spectrum was silent
+    pub fn dominant_pitch_class(&self) -> Option<usize> {
+        self.chroma
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v > 0.0)
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .map(|(i, _)| i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pitch_class() {
+        assert_eq!(pitch_class(440.0), 9);
+        assert_eq!(pitch_class(220.0), 9);
+        assert_eq!(pitch_class(261.63), 0);
+        assert_eq!(pitch_class(32.70), 0);
+        assert_eq!(pitch_class(493.88), 11);
+    }
+
+    #[test]
+    fn test_c_major() {
+        let mut spectrum = analyzer::Spectrum::new(vec![0.0; 4001], 0.0, 4000.0);
+        let mut chroma = ChromaVector::new();
+
+        chroma.update(&spectrum);
+        assert_eq!(chroma.dominant_pitch_class(), None);
+
+        // C4, E4, G4, C5
+        spectrum[262] = 1.0;
+        spectrum[330] = 1.0;
+        spectrum[392] = 1.0;
+        spectrum[523] = 1.0;
+
+        let c = chroma.update(&spectrum);
+        for (i, v) in c.iter().enumerate() {
+            match PITCH_CLASSES[i] {
+                "C" => assert_eq!(*v, 2.0),
+                "E" | "G" => assert_eq!(*v, 1.0),
+                _ => assert_eq!(*v, 0.0, "{} is lit", PITCH_CLASSES[i]),
+            }
+        }
+        assert_eq!(chroma.dominant_pitch_class(), Some(0));
+    }
+}
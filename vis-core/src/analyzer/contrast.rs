@@ -0,0 +1,144 @@
+//! Spectral Contrast
+use crate::analyzer;
+use crate::analyzer::spectrum::Storage;
+
+/// Difference between peaks and valleys within logarithmic sub-bands
+///
+/// For each band, the strongest and the weakest buckets (a `quantile` of the band each) are
+/// averaged and the contrast is computed as the difference of their logarithms.  Tonal music
+/// with clear harmonics has a high contrast, while noisy music has a low one.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 512], 0.0, 4000.0);
+///
+/// let mut contrast = analyzer::SpectralContrast::new(100.0, 3200.0, 5);
+/// let bass_contrast = contrast.update(&spectrum)[0];
+/// # assert_eq!(bass_contrast, 0.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpectralContrast {
+    bands: analyzer::LogBands,
+    quantile: f32,
+    contrast: Vec<analyzer::SignalStrength>,
+    scratch: Vec<Vec<analyzer::SignalStrength>>,
+}
+
+impl SpectralContrast {
+    /// Create a new extractor for `count` bands spanning `lowest` to `highest`
+    pub fn new(
+        lowest: analyzer::Frequency,
+        highest: analyzer::Frequency,
+        count: usize,
+    ) -> SpectralContrast {
+        SpectralContrast {
+            bands: analyzer::LogBands::new(lowest, highest, count),
+            quantile: 0.2,
+            contrast: vec![0.0; count],
+            scratch: vec![Vec::new(); count],
+        }
+    }
+
+    /// Set the fraction of each band averaged for its peak and its valley
+    ///
+    /// Must be in `(0, 0.5]`.  Defaults to `0.2`.
+    pub fn quantile(mut self, quantile: f32) -> SpectralContrast {
+        assert!(quantile > 0.0 && quantile <= 0.5, "Quantile out of range!");
+
+        self.quantile = quantile;
+        self
+    }
+
+    /// Return the bands the contrast is computed for
+    pub fn bands(&self) -> &analyzer::LogBands {
+        &self.bands
+    }
+
+    /// Compute the contrast of each band of a spectrum
+    ///
+    /// Bands without any buckets have a contrast of `0`.
+    pub fn update<S: Storage>(
+        &mut self,
+        spectrum: &analyzer::Spectrum<S>,
+    ) -> &[analyzer::SignalStrength] {
+        for s in self.scratch.iter_mut() {
+            s.clear();
+        }
+        for (i, v) in spectrum.iter().enumerate() {
+            if let Some(band) = self.bands.band_of(spectrum.id_to_freq(i)) {
+                self.scratch[band].push(*v);
+            }
+        }
+
+        for (c, values) in self.contrast.iter_mut().zip(self.scratch.iter_mut()) {
+            if values.is_empty() {
+                *c = 0.0;
+                continue;
+            }
+
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let n = ((values.len() as f32 * self.quantile).round() as usize).max(1);
+            let mean = |v: &[analyzer::SignalStrength]| v.iter().sum::<f32>() / n as f32;
+
+            let valley = mean(&values[..n]);
+            let peak = mean(&values[values.len() - n..]);
+            *c = (peak + 1e-9).log10() - (valley + 1e-9).log10();
+        }
+
+        &self.contrast
+    }
+
+    /// Return the contrast computed during the last update
+    pub fn contrast(&self) -> &[analyzer::SignalStrength] {
+        &self.contrast
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tonal_vs_noise() {
+        // Harmonics on a quiet floor
+        let tonal = analyzer::Spectrum::new(
+            (0..512)
+                .map(|i| if i % 16 == 0 { 1.0 } else { 0.01 })
+                .collect::<Vec<_>>(),
+            0.0,
+            4000.0,
+        );
+        // Pseudo-random broadband noise
+        let mut seed = 1u32;
+        let noise = analyzer::Spectrum::new(
+            (0..512)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    0.5 + (seed >> 16) as f32 / 65536.0 * 0.5
+                })
+                .collect::<Vec<_>>(),
+            0.0,
+            4000.0,
+        );
+
+        let mut contrast = SpectralContrast::new(200.0, 3200.0, 4);
+        let tonal = contrast.update(&tonal).to_vec();
+        let noise = contrast.update(&noise).to_vec();
+        assert_eq!(contrast.contrast(), &noise[..]);
+
+        for (t, n) in tonal.iter().zip(noise.iter()) {
+            assert!(*n >= 0.0 && *n < 0.5, "{}", n);
+            assert!(t > n, "{} <= {}", t, n);
+        }
+    }
+
+    #[test]
+    fn test_empty_band() {
+        // Bands below the lowest bucket stay at zero
+        let spectrum = analyzer::Spectrum::new(vec![1.0; 16], 1000.0, 2000.0);
+        let mut contrast = SpectralContrast::new(100.0, 2000.0, 4);
+
+        assert_eq!(contrast.update(&spectrum)[0], 0.0);
+    }
+}
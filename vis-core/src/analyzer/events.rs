@@ -0,0 +1,344 @@
+//! Event Aggregation
+use crate::analyzer;
+
+/// Kinds of events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// A beat, see [`BeatDetector`](../beat/struct.BeatDetector.html)
+    Beat,
+    /// The start of a note or sound, see [`OnsetEvents`](struct.OnsetEvents.html)
+    Onset,
+    /// The input went silent, see [`SilenceDetector`](../silence/struct.SilenceDetector.html)
+    Silence,
+    /// The signal returned after a silence gap
+    TrackBoundary,
+    /// The input reached full scale
+    Clip,
+}
+
+/// An event reported by a detector
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Event {
+    /// What happened
+    pub kind: EventKind,
+    /// Time of the event in seconds
+    ///
+    /// This is the time passed to [`Events::update`](struct.Events.html#method.update).
+    pub time: f32,
+    /// Strength of the event, its meaning depends on the kind
+    ///
+    /// Beats report their confidence, onsets their spectral flux, clips their peak amplitude.  Other events report `1.0`.
+    pub strength: analyzer::SignalStrength,
+}
+
+/// A detector which can be run by [`Events`](struct.Events.html)
+///
+/// Implemented for the detectors of this crate and for closures, so custom detectors can be
+/// plugged in as well.
+pub trait Detector: Send {
+    /// Look at the latest samples and push any detected events
+    fn detect(&mut self, samples: &analyzer::SampleBuffer, time: f32, events: &mut Vec<Event>);
+}
+
+impl<F> Detector for F
+where
+    F: FnMut(&analyzer::SampleBuffer, f32, &mut Vec<Event>) + Send,
+{
+    fn detect(&mut self, samples: &analyzer::SampleBuffer, time: f32, events: &mut Vec<Event>) {
+        self(samples, time, events)
+    }
+}
+
+impl Detector for analyzer::BeatDetector {
+    fn detect(&mut self, samples: &analyzer::SampleBuffer, time: f32, events: &mut Vec<Event>) {
+        if analyzer::BeatDetector::detect(self, samples) {
+            events.push(Event {
+                kind: EventKind::Beat,
+                time,
+                strength: self.last_confidence(),
+            });
+        }
+    }
+}
+
+/// Uses the volume of the last 0.1 seconds.
+impl Detector for analyzer::SilenceDetector {
+    fn detect(&mut self, samples: &analyzer::SampleBuffer, time: f32, events: &mut Vec<Event>) {
+        let was_silent = self.is_silent();
+
+        let kind = if self.update(samples.volume(0.1), time) {
+            EventKind::TrackBoundary
+        } else if !was_silent && self.is_silent() {
+            EventKind::Silence
+        } else {
+            return;
+        };
+
+        events.push(Event {
+            kind,
+            time,
+            strength: 1.0,
+        });
+    }
+}
+
+/// A detector for clipping input
+///
+/// Reports a [`Clip`](enum.EventKind.html#variant.Clip) event if any sample pushed since the
+/// last call reached `threshold`.
+#[derive(Debug, Clone)]
+pub struct ClipDetector {
+    threshold: analyzer::Sample,
+    generation: u64,
+}
+
+impl ClipDetector {
+    /// Create a new clip detector triggering on samples of at least `threshold` magnitude
+    pub fn new(threshold: analyzer::Sample) -> ClipDetector {
+        ClipDetector {
+            threshold,
+            generation: 0,
+        }
+    }
+}
+
+impl Detector for ClipDetector {
+    fn detect(&mut self, samples: &analyzer::SampleBuffer, time: f32, events: &mut Vec<Event>) {
+        let new = samples.samples_since(self.generation).min(samples.size());
+        self.generation = samples.generation();
+
+        let peak = samples
+            .iter(new, 1)
+            .flat_map(|s| s.into_iter())
+            .fold(0.0 as analyzer::Sample, |peak, s| peak.max(s.abs()));
+
+        if new > 0 && peak >= self.threshold {
+            events.push(Event {
+                kind: EventKind::Clip,
+                time,
+                strength: peak,
+            });
+        }
+    }
+}
+
+/// A detector for note onsets
+///
+/// Runs an [`OnsetDetector`](../onset/struct.OnsetDetector.html) on the spectrum of the mono
+/// downmix and reports an [`Onset`](enum.EventKind.html#variant.Onset) event with the spectral
+/// flux as strength whenever it triggers.
+#[derive(Debug)]
+pub struct OnsetEvents {
+    analyzer: analyzer::FourierAnalyzer,
+    onset: analyzer::OnsetDetector,
+}
+
+impl OnsetEvents {
+    /// Create a new onset detector analyzing the samples with `analyzer`
+    pub fn new(analyzer: analyzer::FourierAnalyzer, onset: analyzer::OnsetDetector) -> OnsetEvents {
+        OnsetEvents { analyzer, onset }
+    }
+}
+
+impl Detector for OnsetEvents {
+    fn detect(&mut self, samples: &analyzer::SampleBuffer, time: f32, events: &mut Vec<Event>) {
+        let spectrum = self.analyzer.mono(samples);
+        if self.onset.detect(&spectrum) {
+            events.push(Event {
+                kind: EventKind::Onset,
+                time,
+                strength: self.onset.last_flux(),
+            });
+        }
+    }
+}
+
+/// Aggregator running multiple detectors
+///
+/// Instead of polling each detector on its own, register them once and call
+/// [`update`](#method.update) every analyzer cycle to get all events at once.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// # let samples = analyzer::SampleBuffer::new(32000, 8000);
+/// # let time = 0.0;
+/// # let beat = analyzer::BeatBuilder::new()
+/// #     .decay(2000.0)
+/// #     .trigger(0.4)
+/// #     .range(50.0, 100.0)
+/// #     .fourier_length(16)
+/// #     .downsample(10)
+/// #     .rate(8000)
+/// #     .volume_smoothing(8.0)
+/// #     .history(8)
+/// #     .build();
+/// let mut events = analyzer::Events::new()
+///     .with(beat)
+///     .with(analyzer::events::ClipDetector::new(0.99))
+///     .subscribe(analyzer::EventKind::Beat);
+///
+/// for event in events.update(&samples, time) {
+///     println!("Beat at {}s", event.time);
+/// }
+/// ```
+#[derive(Default)]
+pub struct Events {
+    detectors: Vec<Box<dyn Detector>>,
+    subscribed: Vec<EventKind>,
+}
+
+impl std::fmt::Debug for Events {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Events")
+            .field("detectors", &self.detectors.len())
+            .field("subscribed", &self.subscribed)
+            .finish()
+    }
+}
+
+impl Events {
+    /// Create an aggregator without any detectors
+    pub fn new() -> Events {
+        Default::default()
+    }
+
+    /// Register a detector
+    pub fn with<D: Detector + 'static>(mut self, detector: D) -> Events {
+        self.detectors.push(Box::new(detector));
+        self
+    }
+
+    /// Only report events of `kind` (and other subscribed kinds)
+    ///
+    /// Without any subscriptions, all events are reported.
+    pub fn subscribe(mut self, kind: EventKind) -> Events {
+        self.subscribed.push(kind);
+        self
+    }
+
+    /// Run all detectors on the latest samples
+    ///
+    /// `time` is used as the timestamp of all events of this cycle.
+    pub fn update(&mut self, samples: &analyzer::SampleBuffer, time: f32) -> Vec<Event> {
+        let mut events = Vec::new();
+        for detector in self.detectors.iter_mut() {
+            detector.detect(samples, time, &mut events);
+        }
+
+        if !self.subscribed.is_empty() {
+            events.retain(|e| self.subscribed.contains(&e.kind));
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+
+    fn beat() -> analyzer::BeatDetector {
+        analyzer::BeatBuilder::new()
+            .decay(2000.0)
+            .trigger(0.4)
+            .range(50.0, 100.0)
+            .fourier_length(16)
+            .downsample(10)
+            .rate(8000)
+            .volume_smoothing(8.0)
+            .history(8)
+            .build()
+    }
+
+    /// Run `events` on a 75 Hz tone pulsing twice a second which clips at 1s
+    fn run(events: &mut Events) -> Vec<Event> {
+        let buf = analyzer::SampleBuffer::new(1000, 8000);
+        let mut all = Vec::new();
+        for i in 0..200 {
+            let samples = (0..80)
+                .map(|j| {
+                    let t = (i * 80 + j) as f32 / 8000.0;
+                    let envelope = (-(t % 0.5) * 20.0).exp();
+                    let s = (t * 75.0 * 2.0 * std::f32::consts::PI).sin() * envelope;
+                    if i == 100 && j == 40 {
+                        [1.0, s]
+                    } else {
+                        [s * 0.9, s * 0.9]
+                    }
+                })
+                .collect::<Vec<_>>();
+            buf.push(&samples);
+
+            all.extend(events.update(&buf, i as f32 * 0.01));
+        }
+        all
+    }
+
+    #[test]
+    fn test_beat_and_clip() {
+        let mut events = Events::new().with(beat()).with(ClipDetector::new(0.99));
+        let all = run(&mut events);
+
+        assert!(all.iter().any(|e| e.kind == EventKind::Beat));
+        let clips = all
+            .iter()
+            .filter(|e| e.kind == EventKind::Clip)
+            .collect::<Vec<_>>();
+        assert_eq!(clips.len(), 1);
+        assert_eq!(clips[0].time, 1.0);
+        assert_eq!(clips[0].strength, 1.0);
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let mut events = Events::new()
+            .with(beat())
+            .with(ClipDetector::new(0.99))
+            .subscribe(EventKind::Clip);
+        let all = run(&mut events);
+
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].kind, EventKind::Clip);
+    }
+
+    #[test]
+    fn test_onset() {
+        let mut rec = crate::recorder::synth::SynthBuilder::new()
+            .rate(8000)
+            .buffer_size(8000)
+            .sine(1000.0, 0.2)
+            .clicks(120.0)
+            .channel_gains([1.0, 1.0])
+            .create();
+
+        let analyzer = analyzer::FourierBuilder::new()
+            .rate(8000)
+            .length(128)
+            .window(analyzer::window::nuttall)
+            .downsample(1)
+            .channels(analyzer::fourier::Channels::Both)
+            .drop_dc(false)
+            .plan();
+        let onset = analyzer::OnsetBuilder::new()
+            .window(20)
+            .factor(1.5)
+            .min_flux(1.0)
+            .build();
+        let mut events = Events::new().with(OnsetEvents::new(analyzer, onset));
+
+        let mut all = Vec::new();
+        for i in 1..=200 {
+            let time = i as f32 * 0.01;
+            rec.sync(time);
+            all.extend(events.update(rec.sample_buffer(), time));
+        }
+
+        // 2 seconds at 120 BPM
+        assert!((3..=5).contains(&all.len()), "{:?}", all);
+        for event in all.iter() {
+            assert_eq!(event.kind, EventKind::Onset);
+            assert!(event.strength >= 1.0);
+        }
+    }
+}
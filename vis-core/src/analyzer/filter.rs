@@ -0,0 +1,876 @@
+//! Filters operating on consecutive spectra
+use crate::analyzer;
+use crate::analyzer::spectrum::{Storage, StorageMut};
+
+/// Spectral whitening
+///
+/// Divides each bucket by its own slowly adapting running magnitude, so all frequencies
+/// contribute equally regardless of the spectral tilt of the music.  This is a common
+/// preprocessing step for onset and pitch detection.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 256], 0.0, 4000.0);
+/// let mut whitened = analyzer::Spectrum::new(vec![0.0; 256], 0.0, 1.0);
+///
+/// let mut whitener = analyzer::Whitener::new(0.01);
+/// whitener.apply(&spectrum, &mut whitened);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Whitener {
+    rate: analyzer::SignalStrength,
+    floor: analyzer::SignalStrength,
+    running: Vec<analyzer::SignalStrength>,
+}
+
+impl Whitener {
+    /// Create a new whitener
+    ///
+    /// `rate` is the adaptation rate of the running magnitude in `(0, 1]`.  The lower it is,
+    /// the slower the whitener adapts.
+    pub fn new(rate: analyzer::SignalStrength) -> Whitener {
+        assert!(rate > 0.0 && rate <= 1.0, "Adaptation rate out of range!");
+
+        Whitener {
+            rate,
+            floor: 1e-9,
+            running: Vec::new(),
+        }
+    }
+
+    /// Set the lowest running magnitude used for division
+    ///
+    /// Keeps silent buckets from being amplified into noise.  Defaults to `1e-9`.
+    pub fn floor(mut self, floor: analyzer::SignalStrength) -> Whitener {
+        self.floor = floor;
+        self
+    }
+
+    /// Whiten `input` and write the result into `out`
+    ///
+    /// `out` must have the same number of buckets as `input` and will be respanned to match it.
+    pub fn apply<'a, S: Storage, S2: StorageMut>(
+        &mut self,
+        input: &analyzer::Spectrum<S>,
+        out: &'a mut analyzer::Spectrum<S2>,
+    ) -> &'a mut analyzer::Spectrum<S2> {
+        if self.running.len() != input.len() {
+            self.running = input.iter().cloned().collect();
+        }
+
+        input.map_into(out, |v| v);
+        for (o, r) in out.iter_mut().zip(self.running.iter_mut()) {
+            *r += self.rate * (*o - *r);
+            *o /= r.max(self.floor);
+        }
+
+        out
+    }
+}
+
+/// Lightweight de-reverberation by spectral subtraction
+///
+/// Keeps a single-pole estimate of the reverb tail per bucket and subtracts it from the
+/// spectrum.  Sustained energy is attenuated while transients pass, which sharpens the
+/// display in echoey rooms.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 256], 0.0, 4000.0);
+/// let mut dry = analyzer::Spectrum::new(vec![0.0; 256], 0.0, 1.0);
+///
+/// let mut dereverb = analyzer::DereverbLite::new(0.9).strength(0.5);
+/// dereverb.apply(&spectrum, &mut dry);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DereverbLite {
+    decay: analyzer::SignalStrength,
+    strength: analyzer::SignalStrength,
+    tail: Vec<analyzer::SignalStrength>,
+}
+
+impl DereverbLite {
+    /// Create a new de-reverberator
+    ///
+    /// `decay` is the pole of the tail estimate in `[0, 1)`.  The higher it is, the longer
+    /// the reverb tail which is assumed.
+    pub fn new(decay: analyzer::SignalStrength) -> DereverbLite {
+        assert!((0.0..1.0).contains(&decay), "Decay out of range!");
+
+        DereverbLite {
+            decay,
+            strength: 0.5,
+            tail: Vec::new(),
+        }
+    }
+
+    /// Set how much of the tail estimate is subtracted
+    ///
+    /// Must be in `[0, 1]`.  Defaults to `0.5`.
+    pub fn strength(mut self, strength: analyzer::SignalStrength) -> DereverbLite {
+        assert!((0.0..=1.0).contains(&strength), "Strength out of range!");
+
+        self.strength = strength;
+        self
+    }
+
+    /// Subtract the tail from `input` and write the result into `out`
+    ///
+    /// `out` must have the same number of buckets as `input` and will be respanned to match it.
+    /// An update with a different number of buckets restarts the tail estimate.
+    pub fn apply<'a, S: Storage, S2: StorageMut>(
+        &mut self,
+        input: &analyzer::Spectrum<S>,
+        out: &'a mut analyzer::Spectrum<S2>,
+    ) -> &'a mut analyzer::Spectrum<S2> {
+        if self.tail.len() != input.len() {
+            self.tail = vec![0.0; input.len()];
+        }
+
+        input.map_into(out, |v| v);
+        for (o, t) in out.iter_mut().zip(self.tail.iter_mut()) {
+            let v = *o;
+            *o = (v - self.strength * *t).max(0.0);
+            *t = self.decay * *t + (1.0 - self.decay) * v;
+        }
+
+        out
+    }
+}
+
+/// Spectral flux as a full spectrum
+///
+/// Keeps the previous spectrum and produces the positive (half-wave rectified) difference
+/// per bucket, exposing where energy is increasing.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 256], 0.0, 4000.0);
+///
+/// let mut flux = analyzer::FluxSpectrum::new();
+/// let rising = flux.update(&spectrum).slice(50.0, 100.0).mean();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FluxSpectrum {
+    previous: Vec<analyzer::SignalStrength>,
+    flux: analyzer::Spectrum<Vec<analyzer::SignalStrength>>,
+}
+
+impl FluxSpectrum {
+    /// Create a new flux spectrum
+    pub fn new() -> FluxSpectrum {
+        Default::default()
+    }
+
+    /// Update with the latest spectrum and return the flux
+    ///
+    /// The first update (or an update with a different number of buckets) yields zero flux.
+    pub fn update<S: Storage>(
+        &mut self,
+        spectrum: &analyzer::Spectrum<S>,
+    ) -> analyzer::Spectrum<&[analyzer::SignalStrength]> {
+        if self.previous.len() != spectrum.len() {
+            self.previous = spectrum.iter().cloned().collect();
+            self.flux = analyzer::Spectrum::new(vec![0.0; spectrum.len()], 0.0, 1.0);
+        }
+
+        spectrum.map_into(&mut self.flux, |v| v);
+        for (f, p) in self.flux.iter_mut().zip(self.previous.iter_mut()) {
+            let delta = *f - *p;
+            *p = *f;
+            *f = delta.max(0.0);
+        }
+
+        self.flux.as_ref()
+    }
+
+    /// Return the flux calculated during the last update
+    pub fn flux(&self) -> analyzer::Spectrum<&[analyzer::SignalStrength]> {
+        self.flux.as_ref()
+    }
+}
+
+/// Decaying maximum of recent spectra
+///
+/// Keeps the maximum per bucket, decaying over time as `max(trail * decay, new)`.  This is
+/// the spectral analogue of a rolling volume and useful for motion-blur like visuals.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 256], 0.0, 4000.0);
+///
+/// let mut trail = analyzer::SpectrumTrail::new(0.9);
+/// let peak = trail.update(&spectrum).slice(50.0, 100.0).max();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpectrumTrail {
+    decay: analyzer::SignalStrength,
+    running: Vec<analyzer::SignalStrength>,
+    trail: analyzer::Spectrum<Vec<analyzer::SignalStrength>>,
+}
+
+impl SpectrumTrail {
+    /// Create a new trail
+    ///
+    /// `decay` is applied once per update and has to be in `[0, 1)`.
+    pub fn new(decay: analyzer::SignalStrength) -> SpectrumTrail {
+        assert!((0.0..1.0).contains(&decay), "Decay out of range!");
+
+        SpectrumTrail {
+            decay,
+            running: Vec::new(),
+            trail: Default::default(),
+        }
+    }
+
+    /// Update with the latest spectrum and return the trail
+    ///
+    /// An update with a different number of buckets restarts the trail.
+    pub fn update<S: Storage>(
+        &mut self,
+        spectrum: &analyzer::Spectrum<S>,
+    ) -> analyzer::Spectrum<&[analyzer::SignalStrength]> {
+        if self.running.len() != spectrum.len() {
+            self.running = vec![0.0; spectrum.len()];
+            self.trail = analyzer::Spectrum::new(vec![0.0; spectrum.len()], 0.0, 1.0);
+        }
+
+        for (r, v) in self.running.iter_mut().zip(spectrum.iter()) {
+            *r = v.max(*r * self.decay);
+        }
+
+        spectrum.map_into(&mut self.trail, |v| v);
+        for (t, r) in self.trail.iter_mut().zip(self.running.iter()) {
+            *t = *r;
+        }
+
+        self.trail.as_ref()
+    }
+
+    /// Return the trail calculated during the last update
+    pub fn trail(&self) -> analyzer::Spectrum<&[analyzer::SignalStrength]> {
+        self.trail.as_ref()
+    }
+}
+
+/// Peak indicator for bar displays
+///
+/// Keeps the peak of each bucket.  New maxima are taken over instantly, otherwise the peaks
+/// fall by a factor per second, so they slowly sink down onto the live bars.  Unlike
+/// [`SpectrumTrail`](struct.SpectrumTrail.html), the decay depends on the elapsed time
+/// instead of the number of updates.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 64], 0.0, 4000.0);
+///
+/// let mut hold = analyzer::PeakHold::new(0.25);
+/// hold.observe(&spectrum, 0.016);
+/// let peak = hold.peaks()[10];
+/// ```
+#[derive(Debug, Clone)]
+pub struct PeakHold {
+    decay: analyzer::SignalStrength,
+    peaks: analyzer::Spectrum<Vec<analyzer::SignalStrength>>,
+}
+
+impl PeakHold {
+    /// Create a new peak hold
+    ///
+    /// After one second without new maxima, a peak has fallen to `decay` times its value.
+    /// `decay` has to be in `[0, 1)`.
+    pub fn new(decay: analyzer::SignalStrength) -> PeakHold {
+        assert!((0.0..1.0).contains(&decay), "Decay out of range!");
+
+        PeakHold {
+            decay,
+            peaks: analyzer::Spectrum::new(Vec::new(), 0.0, 0.0),
+        }
+    }
+
+    /// Update with the latest spectrum, `delta` seconds after the last update
+    ///
+    /// If the number of buckets or the span of the spectrum changed, the peaks are resampled
+    /// to the new buckets first.  Frequencies which were not covered before start at `0`.
+    pub fn observe<S: Storage>(
+        &mut self,
+        spectrum: &analyzer::Spectrum<S>,
+        delta: f32,
+    ) -> analyzer::Spectrum<&[analyzer::SignalStrength]> {
+        if self.peaks.len() != spectrum.len()
+            || self.peaks.lowest() != spectrum.lowest()
+            || self.peaks.highest() != spectrum.highest()
+        {
+            self.respan(spectrum);
+        }
+
+        let factor = self.decay.powf(delta.max(0.0));
+        for (p, v) in self.peaks.iter_mut().zip(spectrum.iter()) {
+            *p = v.max(*p * factor);
+        }
+
+        self.peaks.as_ref()
+    }
+
+    /// Move the peaks to the buckets of `spectrum`, taking the nearest old bucket
+    fn respan<S: Storage>(&mut self, spectrum: &analyzer::Spectrum<S>) {
+        let old = &self.peaks;
+        let peaks = (0..spectrum.len())
+            .map(|i| {
+                let f = spectrum.id_to_freq(i);
+                if !old.is_empty() && f >= old.lowest() && f <= old.highest() {
+                    old[old.freq_to_id(f)]
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+
+        self.peaks = analyzer::Spectrum::new(peaks, spectrum.lowest(), spectrum.highest());
+    }
+
+    /// Return the peaks after the last update
+    pub fn peaks(&self) -> analyzer::Spectrum<&[analyzer::SignalStrength]> {
+        self.peaks.as_ref()
+    }
+}
+
+/// Smoothing over time with a different factor per frequency band
+///
+/// The factor is given as breakpoints of `(frequency, factor)` and linearly interpolated
+/// between them.  Below the first and above the last breakpoint, the factor stays constant.
+/// Each bucket is smoothed as `old * factor + new * (1 - factor)`, so a higher factor means
+/// slower smoothing.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 256], 0.0, 4000.0);
+/// let mut smoothed = analyzer::Spectrum::new(vec![0.0; 256], 0.0, 1.0);
+///
+/// // Smooth bass slower than treble
+/// let mut smoother = analyzer::BandSmoother::new(&[(100.0, 0.9), (2000.0, 0.5)]);
+/// smoother.apply(&spectrum, &mut smoothed);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BandSmoother {
+    breakpoints: Vec<(analyzer::Frequency, analyzer::SignalStrength)>,
+    factors: Vec<analyzer::SignalStrength>,
+    running: Vec<analyzer::SignalStrength>,
+}
+
+impl BandSmoother {
+    /// Create a new band smoother from `(frequency, factor)` breakpoints
+    ///
+    /// Factors have to be in `[0, 1)`.
+    pub fn new(breakpoints: &[(analyzer::Frequency, analyzer::SignalStrength)]) -> BandSmoother {
+        assert!(
+            !breakpoints.is_empty(),
+            "At least one breakpoint is needed!"
+        );
+        assert!(
+            breakpoints.iter().all(|(_, f)| *f >= 0.0 && *f < 1.0),
+            "Smoothing factor out of range!"
+        );
+
+        let mut breakpoints = breakpoints.to_vec();
+        breakpoints.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+
+        BandSmoother {
+            breakpoints,
+            factors: Vec::new(),
+            running: Vec::new(),
+        }
+    }
+
+    /// Return the smoothing factor used at frequency `f`
+    pub fn factor(&self, f: analyzer::Frequency) -> analyzer::SignalStrength {
+        let first = self.breakpoints[0];
+        if f <= first.0 {
+            return first.1;
+        }
+
+        for w in self.breakpoints.windows(2) {
+            let ((f0, s0), (f1, s1)) = (w[0], w[1]);
+            if f <= f1 {
+                return s0 + (s1 - s0) * (f - f0) / (f1 - f0);
+            }
+        }
+
+        self.breakpoints[self.breakpoints.len() - 1].1
+    }
+
+    /// Smooth `input` and write the result into `out`
+    ///
+    /// `out` must have the same number of buckets as `input` and will be respanned to match it.
+    /// The first call (or a call with a different number of buckets) yields `input` unchanged.
+    pub fn apply<'a, S: Storage, S2: StorageMut>(
+        &mut self,
+        input: &analyzer::Spectrum<S>,
+        out: &'a mut analyzer::Spectrum<S2>,
+    ) -> &'a mut analyzer::Spectrum<S2> {
+        if self.running.len() != input.len() {
+            self.running = input.iter().cloned().collect();
+        }
+
+        self.factors.clear();
+        for i in 0..input.len() {
+            self.factors.push(self.factor(input.id_to_freq(i)));
+        }
+
+        input.map_into(out, |v| v);
+        for ((o, r), f) in out
+            .iter_mut()
+            .zip(self.running.iter_mut())
+            .zip(self.factors.iter())
+        {
+            *r = *r * f + *o * (1.0 - f);
+            *o = *r;
+        }
+
+        out
+    }
+}
+
+/// Average of consecutive spectra without storing them
+///
+/// By default, all added spectra are weighted equally, like
+/// [`average_spectrum`](fn.average_spectrum.html).  With a forgetting factor, older spectra
+/// fade out exponentially instead.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 256], 0.0, 4000.0);
+///
+/// let mut average = analyzer::RunningAverage::new();
+/// average.add(&spectrum);
+/// let bass = average.average().slice(50.0, 100.0).mean();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RunningAverage {
+    forget: Option<analyzer::SignalStrength>,
+    count: usize,
+    running: Vec<analyzer::SignalStrength>,
+    average: analyzer::Spectrum<Vec<analyzer::SignalStrength>>,
+}
+
+impl RunningAverage {
+    /// Create a new running average weighting all spectra equally
+    pub fn new() -> RunningAverage {
+        Default::default()
+    }
+
+    /// Create a new exponential running average
+    ///
+    /// Each new spectrum is added as `average * forget + new * (1 - forget)`, so `forget`
+    /// has to be in `[0, 1)`.
+    pub fn with_forgetting(forget: analyzer::SignalStrength) -> RunningAverage {
+        assert!(
+            (0.0..1.0).contains(&forget),
+            "Forgetting factor out of range!"
+        );
+
+        RunningAverage {
+            forget: Some(forget),
+            ..Default::default()
+        }
+    }
+
+    /// Add a spectrum to the average
+    ///
+    /// The span of the average is taken from the latest spectrum.  Adding a spectrum with a
+    /// different number of buckets restarts the average.
+    pub fn add<S: Storage>(&mut self, spectrum: &analyzer::Spectrum<S>) {
+        if self.count == 0 || self.running.len() != spectrum.len() {
+            self.running = vec![0.0; spectrum.len()];
+            self.average = analyzer::Spectrum::new(vec![0.0; spectrum.len()], 0.0, 1.0);
+            self.count = 0;
+        }
+        self.count += 1;
+
+        let factor = match self.forget {
+            Some(forget) if self.count > 1 => forget,
+            _ => 1.0 - 1.0 / self.count as analyzer::SignalStrength,
+        };
+
+        for (r, v) in self.running.iter_mut().zip(spectrum.iter()) {
+            *r = *r * factor + v * (1.0 - factor);
+        }
+
+        spectrum.map_into(&mut self.average, |v| v);
+        for (a, r) in self.average.iter_mut().zip(self.running.iter()) {
+            *a = *r;
+        }
+    }
+
+    /// Return the current average
+    pub fn average(&self) -> &analyzer::Spectrum<Vec<analyzer::SignalStrength>> {
+        &self.average
+    }
+
+    /// Return the number of spectra added since the last reset
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Forget all added spectra
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+/// Exponential smoothing of spectra over time with separate attack and decay
+///
+/// Owns a spectrum with a fixed number of buckets and span.  Each pushed spectrum is merged
+/// in per bucket as `old * factor + new * (1 - factor)`.  Rising buckets use the attack
+/// factor, falling ones the decay factor, so eg. an attack of `0` makes peaks rise instantly
+/// while they still fall slowly, like a VU meter.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 32], 220.0, 660.0);
+///
+/// let mut smoother = analyzer::SpectrumSmoother::new(32, 220.0, 660.0, 0.95).attack(0.0);
+/// let smoothed = smoother.push(&spectrum);
+/// # assert_eq!(smoothed[0], 1.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpectrumSmoother {
+    attack: analyzer::SignalStrength,
+    decay: analyzer::SignalStrength,
+    smoothed: analyzer::Spectrum<Vec<analyzer::SignalStrength>>,
+}
+
+impl SpectrumSmoother {
+    /// Create a new smoother for spectra of `buckets` buckets from `lowest` to `highest`
+    ///
+    /// `factor` is used for both attack and decay and has to be in `[0, 1)`.  All buckets
+    /// start at `0`.
+    pub fn new(
+        buckets: usize,
+        lowest: analyzer::Frequency,
+        highest: analyzer::Frequency,
+        factor: analyzer::SignalStrength,
+    ) -> SpectrumSmoother {
+        assert!(
+            (0.0..1.0).contains(&factor),
+            "Smoothing factor out of range!"
+        );
+
+        SpectrumSmoother {
+            attack: factor,
+            decay: factor,
+            smoothed: analyzer::Spectrum::new(vec![0.0; buckets], lowest, highest),
+        }
+    }
+
+    /// Set the factor used for rising buckets, in `[0, 1)`
+    pub fn attack(mut self, attack: analyzer::SignalStrength) -> SpectrumSmoother {
+        assert!((0.0..1.0).contains(&attack), "Attack factor out of range!");
+        self.attack = attack;
+        self
+    }
+
+    /// Set the factor used for falling buckets, in `[0, 1)`
+    pub fn decay(mut self, decay: analyzer::SignalStrength) -> SpectrumSmoother {
+        assert!((0.0..1.0).contains(&decay), "Decay factor out of range!");
+        self.decay = decay;
+        self
+    }
+
+    /// Merge the latest spectrum and return the smoothed one
+    ///
+    /// Panics if `spectrum` has a different number of buckets or span than the smoother.
+    pub fn push<S: Storage>(
+        &mut self,
+        spectrum: &analyzer::Spectrum<S>,
+    ) -> &analyzer::Spectrum<Vec<analyzer::SignalStrength>> {
+        assert_eq!(
+            spectrum.len(),
+            self.smoothed.len(),
+            "Spectrum has a different number of buckets than the smoother!"
+        );
+        assert!(
+            spectrum.lowest() == self.smoothed.lowest()
+                && spectrum.highest() == self.smoothed.highest(),
+            "Spectrum spans {}-{} Hz but the smoother spans {}-{} Hz!",
+            spectrum.lowest(),
+            spectrum.highest(),
+            self.smoothed.lowest(),
+            self.smoothed.highest(),
+        );
+
+        for (o, v) in self.smoothed.iter_mut().zip(spectrum.iter()) {
+            let factor = if *v > *o { self.attack } else { self.decay };
+            *o = *o * factor + v * (1.0 - factor);
+        }
+
+        &self.smoothed
+    }
+
+    /// Return the smoothed spectrum of the last push
+    pub fn spectrum(&self) -> &analyzer::Spectrum<Vec<analyzer::SignalStrength>> {
+        &self.smoothed
+    }
+
+    /// Reset all buckets to `0`
+    pub fn reset(&mut self) {
+        for v in self.smoothed.iter_mut() {
+            *v = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whitener() {
+        let mut whitener = Whitener::new(0.1);
+
+        let tilted = analyzer::Spectrum::new(
+            (1..=100).map(|i| 1.0 / i as f32).collect::<Vec<_>>(),
+            0.0,
+            1000.0,
+        );
+        let mut out = analyzer::Spectrum::new(vec![0.0; 100], 0.0, 1.0);
+
+        // Start from a flat running magnitude so the whitener actually has to adapt
+        whitener.apply(
+            &analyzer::Spectrum::new(vec![1.0; 100], 0.0, 1000.0),
+            &mut out,
+        );
+
+        for _ in 0..200 {
+            whitener.apply(&tilted, &mut out);
+        }
+
+        assert_eq!(out.lowest(), 0.0);
+        assert_eq!(out.highest(), 1000.0);
+        for v in out.iter() {
+            assert!((v - 1.0).abs() < 1e-3, "{} is not flat", v);
+        }
+    }
+
+    #[test]
+    fn test_dereverb() {
+        let mut dereverb = DereverbLite::new(0.9).strength(0.5);
+        let mut out = analyzer::Spectrum::new(vec![0.0; 4], 0.0, 1.0);
+
+        // A sustained tone ...
+        let mut sustained = 0.0;
+        for _ in 0..100 {
+            let tone = analyzer::Spectrum::new(vec![1.0; 4], 0.0, 400.0);
+            sustained = dereverb.apply(&tone, &mut out)[0];
+        }
+        assert!(sustained > 0.0);
+        assert_eq!(out.highest(), 400.0);
+
+        // ... which is cut and rings out in the room
+        for i in 1..10 {
+            let raw = 0.8f32.powi(i);
+            let tail = analyzer::Spectrum::new(vec![raw; 4], 0.0, 400.0);
+            let processed = dereverb.apply(&tail, &mut out)[0];
+
+            assert!(
+                processed / sustained < raw,
+                "{} does not decay faster than {}",
+                processed / sustained,
+                raw,
+            );
+        }
+    }
+
+    #[test]
+    fn test_flux() {
+        let mut flux = FluxSpectrum::new();
+
+        let mut spectrum = analyzer::Spectrum::new(vec![1.0; 10], 100.0, 1000.0);
+        assert!(flux.update(&spectrum).iter().all(|f| *f == 0.0));
+
+        spectrum[2] = 3.0;
+        spectrum[7] = 0.5;
+        let f = flux.update(&spectrum);
+        assert_eq!(f.lowest(), 100.0);
+        assert_eq!(f.highest(), 1000.0);
+        assert_eq!(f[2], 2.0);
+        assert_eq!(f[7], 0.0);
+        assert_eq!(f[0], 0.0);
+
+        spectrum[2] = 1.0;
+        let f = flux.update(&spectrum);
+        assert_eq!(f[2], 0.0);
+    }
+
+    #[test]
+    fn test_spectrum_trail() {
+        let mut trail = SpectrumTrail::new(0.5);
+
+        let mut spectrum = analyzer::Spectrum::new(vec![0.0; 10], 100.0, 1000.0);
+        spectrum[3] = 8.0;
+        let t = trail.update(&spectrum);
+        assert_eq!(t.lowest(), 100.0);
+        assert_eq!(t.highest(), 1000.0);
+        assert_eq!(t[3], 8.0);
+
+        // The spike persists, decaying with every update
+        spectrum[3] = 0.0;
+        for expected in [4.0, 2.0, 1.0] {
+            assert_eq!(trail.update(&spectrum)[3], expected);
+        }
+
+        // Louder input takes over immediately
+        spectrum[3] = 3.0;
+        assert_eq!(trail.update(&spectrum)[3], 3.0);
+        assert_eq!(trail.trail()[3], 3.0);
+        assert!(trail
+            .trail()
+            .iter()
+            .enumerate()
+            .all(|(i, v)| i == 3 || *v == 0.0));
+    }
+
+    #[test]
+    fn test_band_smoother() {
+        let mut smoother = BandSmoother::new(&[(2000.0, 0.5), (100.0, 0.9)]);
+
+        assert_eq!(smoother.factor(0.0), 0.9);
+        assert!((smoother.factor(1050.0) - 0.7).abs() < 1e-6);
+        assert_eq!(smoother.factor(4000.0), 0.5);
+
+        let mut out = analyzer::Spectrum::new(vec![0.0; 41], 0.0, 1.0);
+        smoother.apply(
+            &analyzer::Spectrum::new(vec![0.0; 41], 0.0, 4000.0),
+            &mut out,
+        );
+
+        // Step input
+        let step = analyzer::Spectrum::new(vec![1.0; 41], 0.0, 4000.0);
+        smoother.apply(&step, &mut out);
+        assert!((out[0] - 0.1).abs() < 1e-6);
+        assert!((out[40] - 0.5).abs() < 1e-6);
+
+        smoother.apply(&step, &mut out);
+        assert!(out[0] < out[10] && out[10] < out[40]);
+    }
+
+    #[test]
+    fn test_running_average() {
+        let spectra = (0..10)
+            .map(|i| {
+                analyzer::Spectrum::new(
+                    (0..32).map(|j| ((i * j) % 7) as f32).collect::<Vec<_>>(),
+                    100.0,
+                    1000.0,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut running = RunningAverage::new();
+        for s in spectra.iter() {
+            running.add(s);
+        }
+        assert_eq!(running.count(), 10);
+
+        let mut expected = analyzer::Spectrum::new(vec![0.0; 32], 0.0, 1.0);
+        analyzer::average_spectrum(&mut expected, &spectra);
+
+        let average = running.average();
+        assert_eq!(average.lowest(), 100.0);
+        assert_eq!(average.highest(), 1000.0);
+        for (a, e) in average.iter().zip(expected.iter()) {
+            assert!((a - e).abs() < 1e-5, "{} != {}", a, e);
+        }
+    }
+
+    #[test]
+    fn test_running_average_forgetting() {
+        let mut running = RunningAverage::with_forgetting(0.5);
+
+        running.add(&analyzer::Spectrum::new(vec![4.0; 8], 0.0, 1.0));
+        assert_eq!(running.average()[0], 4.0);
+        running.add(&analyzer::Spectrum::new(vec![0.0; 8], 0.0, 1.0));
+        assert_eq!(running.average()[0], 2.0);
+        running.add(&analyzer::Spectrum::new(vec![0.0; 8], 0.0, 1.0));
+        assert_eq!(running.average()[0], 1.0);
+
+        running.reset();
+        running.add(&analyzer::Spectrum::new(vec![3.0; 8], 0.0, 1.0));
+        assert_eq!(running.average()[0], 3.0);
+    }
+
+    #[test]
+    fn test_spectrum_smoother() {
+        let mut smoother = SpectrumSmoother::new(8, 100.0, 800.0, 0.5);
+
+        let loud = analyzer::Spectrum::new(vec![4.0; 8], 100.0, 800.0);
+        let silent = analyzer::Spectrum::new(vec![0.0; 8], 100.0, 800.0);
+        assert_eq!(smoother.push(&loud)[0], 2.0);
+        assert_eq!(smoother.push(&loud)[0], 3.0);
+        assert_eq!(smoother.push(&silent)[0], 1.5);
+
+        // Instant attack, slow decay
+        let mut smoother = SpectrumSmoother::new(8, 100.0, 800.0, 0.5)
+            .attack(0.0)
+            .decay(0.75);
+        assert_eq!(smoother.push(&loud)[7], 4.0);
+        assert_eq!(smoother.push(&silent)[7], 3.0);
+        assert_eq!(smoother.spectrum().lowest(), 100.0);
+
+        smoother.reset();
+        assert!(smoother.spectrum().iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_spectrum_smoother_span() {
+        let mut smoother = SpectrumSmoother::new(8, 100.0, 800.0, 0.5);
+        smoother.push(&analyzer::Spectrum::new(vec![0.0; 8], 0.0, 800.0));
+    }
+
+    #[test]
+    fn test_peak_hold() {
+        let mut hold = PeakHold::new(0.25);
+
+        let loud = analyzer::Spectrum::new(vec![8.0; 8], 100.0, 800.0);
+        let silent = analyzer::Spectrum::new(vec![0.0; 8], 100.0, 800.0);
+        assert_eq!(hold.observe(&loud, 0.1)[0], 8.0);
+
+        // Falls with time, not with the number of updates
+        hold.observe(&silent, 0.5);
+        hold.observe(&silent, 0.5);
+        assert!((hold.peaks()[0] - 2.0).abs() < 1e-5);
+        hold.observe(&silent, 0.0);
+        assert!((hold.peaks()[0] - 2.0).abs() < 1e-5);
+
+        // Instant attack
+        assert_eq!(hold.observe(&loud, 0.5)[3], 8.0);
+    }
+
+    #[test]
+    fn test_peak_hold_respan() {
+        let mut hold = PeakHold::new(0.5);
+
+        let spectrum =
+            analyzer::Spectrum::new((0..8).map(|i| i as f32).collect::<Vec<_>>(), 100.0, 800.0);
+        hold.observe(&spectrum, 0.0);
+
+        // Half the buckets over the upper half of the span and beyond
+        let zoomed = analyzer::Spectrum::new(vec![0.0; 4], 500.0, 1100.0);
+        let peaks = hold.observe(&zoomed, 0.0);
+        assert_eq!(peaks.lowest(), 500.0);
+        assert_eq!(peaks.highest(), 1100.0);
+        assert_eq!(
+            peaks.iter().cloned().collect::<Vec<_>>(),
+            [4.0, 6.0, 0.0, 0.0]
+        );
+    }
+}
@@ -92,6 +92,125 @@ pub mod window {
     }
 }
 
+/// Implementation of the fourier transform
+///
+/// By default, [`RustFftBackend`](struct.RustFftBackend.html) computes the transform on the
+/// CPU.  Implement this trait to delegate the transform elsewhere, eg. to the GPU for very
+/// large transforms.
+pub trait FftBackend: std::fmt::Debug + Send + Sync {
+    /// Compute the forward transform of `buffer` in place
+    ///
+    /// `buffer` always has the length the analyzer was planned with.
+    fn process(&self, buffer: &mut [rustfft::num_complex::Complex<Sample>]);
+}
+
+/// CPU fourier transform using `rustfft`
+#[derive(Clone)]
+pub struct RustFftBackend {
+    length: usize,
+    fft: std::sync::Arc<dyn rustfft::Fft<Sample>>,
+}
+
+impl RustFftBackend {
+    /// Plan a forward transform of `length` samples
+    pub fn new(length: usize) -> RustFftBackend {
+        RustFftBackend {
+            length,
+            fft: rustfft::FftPlanner::new().plan_fft_forward(length),
+        }
+    }
+}
+
+impl std::fmt::Debug for RustFftBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "RustFftBackend {{ length: {:?} }}", self.length)
+    }
+}
+
+impl FftBackend for RustFftBackend {
+    fn process(&self, buffer: &mut [rustfft::num_complex::Complex<Sample>]) {
+        self.fft.process(buffer);
+    }
+}
+
+/// Channels to be transformed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channels {
+    /// Only transform the left channel
+    Left,
+    /// Only transform the right channel
+    Right,
+    /// Transform both channels separately
+    Both,
+    /// Mix both channels and transform the mix
+    MonoMix,
+}
+
+impl Channels {
+    /// Get the channel selection for the specified name
+    ///
+    /// Valid names are `"left"`, `"right"`, `"both"` and `"mono_mix"`.
+    pub fn from_name(name: &str) -> Option<Channels> {
+        match name {
+            "left" => Some(Channels::Left),
+            "right" => Some(Channels::Right),
+            "both" => Some(Channels::Both),
+            "mono_mix" => Some(Channels::MonoMix),
+            _ => None,
+        }
+    }
+}
+
+/// Layout of left and right spectra in a single display buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoLayout {
+    /// Left spectrum followed by the right spectrum, both from low to high
+    Stack,
+    /// Left spectrum from high to low followed by the right spectrum from low to high
+    ///
+    /// Low frequencies end up in the middle of the buffer.
+    Mirror,
+    /// Alternating left and right buckets, from low to high
+    Interleave,
+}
+
+impl StereoLayout {
+    /// Return the index of `bucket` of `channel` (`0` left, `1` right) in a buffer holding
+    /// `n` buckets per channel
+    pub fn index(&self, channel: usize, bucket: usize, n: usize) -> usize {
+        assert!(channel < 2, "Channel {} does not exist!", channel);
+        assert!(bucket < n, "Bucket {} does not exist!", bucket);
+
+        match (self, channel) {
+            (StereoLayout::Stack, c) => c * n + bucket,
+            (StereoLayout::Mirror, 0) => n - 1 - bucket,
+            (StereoLayout::Mirror, _) => n + bucket,
+            (StereoLayout::Interleave, c) => bucket * 2 + c,
+        }
+    }
+}
+
+/// Error for sample buffers which can't be analyzed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalyzeError {
+    /// The sample buffer was recorded at a different rate than the analyzer was planned for
+    Rate { buffer: usize, analyzer: usize },
+}
+
+impl std::fmt::Display for AnalyzeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AnalyzeError::Rate { buffer, analyzer } => write!(
+                f,
+                "Sample buffer has a rate of {} Hz but the analyzer expects {} Hz",
+                buffer, analyzer
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AnalyzeError {}
+
 /// Builder for FourierAnalyzer
 #[derive(Debug, Default)]
 pub struct FourierBuilder {
@@ -120,6 +239,41 @@ pub struct FourierBuilder {
     ///
     /// Can also be set from config as `"audio.rate"`.
     pub rate: Option<usize>,
+
+    /// Channels to be transformed
+    ///
+    /// For effectively mono sources, transforming only one channel halves the work.  In
+    /// that case, both `left()` and `right()` return the spectrum of the transformed
+    /// channel.
+    ///
+    /// Defaults to both channels.  Can also be set from config as `"audio.fourier.channels"`
+    /// (`"left"`, `"right"`, `"both"` or `"mono_mix"`).
+    pub channels: Option<Channels>,
+
+    /// Fourier transform implementation
+    ///
+    /// Defaults to a [`RustFftBackend`](struct.RustFftBackend.html) of the configured length.
+    pub backend: Option<std::sync::Arc<dyn FftBackend>>,
+
+    /// Drop the DC bin
+    ///
+    /// The DC bin (the average of the signal) often dominates the spectrum and skews
+    /// normalization and maxima.  If set, bucket 0 is the first non-DC bin at `lowest()` and
+    /// the last bucket is the Nyquist bin.  Otherwise bucket 0 holds the DC component.
+    ///
+    /// Defaults to `false`.  Can also be set from config as `"audio.fourier.drop_dc"`.
+    pub drop_dc: Option<bool>,
+
+    /// Distance between two windows of [`analyze_stft`](struct.FourierAnalyzer.html#method.analyze_stft)
+    ///
+    /// Counted in samples of the sample buffer, that is before downsampling.  A window
+    /// covers `length * downsample` samples, so the windows overlap by
+    /// `1 - hop / (length * downsample)` and a hop of `length * downsample` analyzes every
+    /// sample exactly once.  Keep the hop a multiple of `downsample` to take every window
+    /// from the same grid of downsampled samples.
+    ///
+    /// Defaults to half a window, for an overlap of 50%.
+    pub hop: Option<usize>,
 }
 
 impl FourierBuilder {
@@ -134,6 +288,50 @@ impl FourierBuilder {
         self
     }
 
+    /// Set the length to the smallest power of two with a resolution of at least `hz`
+    ///
+    /// The resolution depends on rate and downsampling factor, so set those first; if they
+    /// are unset, they are taken from config like in [`plan`](#method.plan).  Overrides any
+    /// length set before.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer::fourier::*;
+    /// let analyzer = FourierBuilder::new()
+    ///     .rate(8000)
+    ///     .downsample(5)
+    ///     .target_resolution(2.0)
+    ///     .window(window::nuttall)
+    ///     .channels(Channels::Both)
+    ///     .drop_dc(false)
+    ///     .plan();
+    /// assert!(analyzer.bin_width() <= 2.0);
+    /// ```
+    pub fn target_resolution(&mut self, hz: analyzer::Frequency) -> &mut FourierBuilder {
+        assert!(hz > 0.0, "Resolution must be positive!");
+
+        let downsample = self
+            .downsample
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.fourier.downsample", 5));
+        let rate = self
+            .rate
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.rate", 8000));
+
+        let min_length =
+            (rate as analyzer::Frequency / downsample as analyzer::Frequency / hz).ceil() as usize;
+        let length = min_length.max(2).next_power_of_two();
+        log::debug!(
+            "Fourier length {} for a resolution of {} Hz ({} / {})",
+            length,
+            hz,
+            rate,
+            downsample,
+        );
+
+        self.length = Some(length);
+        self
+    }
+
     /// Set the window function
     pub fn window(&mut self, f: fn(usize) -> Vec<f32>) -> &mut FourierBuilder {
         self.window = Some(f);
@@ -152,6 +350,31 @@ impl FourierBuilder {
         self
     }
 
+    /// Set the channels to be transformed
+    pub fn channels(&mut self, channels: Channels) -> &mut FourierBuilder {
+        self.channels = Some(channels);
+        self
+    }
+
+    /// Set the fourier transform implementation
+    pub fn backend(&mut self, backend: std::sync::Arc<dyn FftBackend>) -> &mut FourierBuilder {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Set whether the DC bin is dropped
+    pub fn drop_dc(&mut self, drop_dc: bool) -> &mut FourierBuilder {
+        self.drop_dc = Some(drop_dc);
+        self
+    }
+
+    /// Set the distance between two windows of the STFT in samples
+    pub fn hop(&mut self, samples: usize) -> &mut FourierBuilder {
+        assert!(samples > 0, "Hop size must be positive!");
+        self.hop = Some(samples);
+        self
+    }
+
     /// Plan the fourier transform and prepare buffers
     pub fn plan(&mut self) -> FourierAnalyzer {
         let length = self
@@ -167,8 +390,26 @@ impl FourierBuilder {
         let rate = self
             .rate
             .unwrap_or_else(|| crate::CONFIG.get_or("audio.rate", 8000));
+        let channels = self.channels.unwrap_or_else(|| {
+            Channels::from_name(&crate::CONFIG.get_or("audio.fourier.channels", "both".to_string()))
+                .expect("Selected channels not found!")
+        });
+        let backend = self
+            .backend
+            .clone()
+            .unwrap_or_else(|| std::sync::Arc::new(RustFftBackend::new(length)));
+        let drop_dc = self
+            .drop_dc
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.fourier.drop_dc", false));
+
+        let mut fa =
+            FourierAnalyzer::new(length, window, downsample, rate, channels, backend, drop_dc);
+        if let Some(hop) = self.hop {
+            fa.hop = hop;
+        }
+        log::debug!("    Hop Size            = {:8}", fa.hop);
 
-        FourierAnalyzer::new(length, window, downsample, rate)
+        fa
     }
 }
 
@@ -182,6 +423,8 @@ impl FourierBuilder {
 ///     .window(window::nuttall)
 ///     .downsample(5)
 ///     .rate(8000)
+///     .channels(Channels::Both)
+///     .drop_dc(false)
 ///     .plan();
 /// ```
 #[derive(Clone)]
@@ -190,18 +433,28 @@ pub struct FourierAnalyzer {
     buckets: usize,
     window: Vec<Sample>,
     downsample: usize,
+    channels: Channels,
+    drop_dc: bool,
+    transforms: usize,
+
+    hop: usize,
+    /// Generation at which the next STFT window ends
+    stft_next: Option<u64>,
+    stft_samples: Vec<[Sample; 2]>,
 
     rate: usize,
     lowest: analyzer::Frequency,
     highest: analyzer::Frequency,
 
-    fft: std::sync::Arc<dyn rustfft::Fft<Sample>>,
+    fft: std::sync::Arc<dyn FftBackend>,
 
     input: [Vec<rustfft::num_complex::Complex<Sample>>; 2],
-    output: Vec<rustfft::num_complex::Complex<Sample>>,
+    output: [Vec<rustfft::num_complex::Complex<Sample>>; 2],
+    mono_output: Vec<rustfft::num_complex::Complex<Sample>>,
 
     spectra: [analyzer::Spectrum<Vec<analyzer::SignalStrength>>; 2],
     average: analyzer::Spectrum<Vec<analyzer::SignalStrength>>,
+    mono: analyzer::Spectrum<Vec<analyzer::SignalStrength>>,
 }
 
 impl std::fmt::Debug for FourierAnalyzer {
@@ -215,10 +468,17 @@ impl std::fmt::Debug for FourierAnalyzer {
 }
 
 impl FourierAnalyzer {
-    fn new(length: usize, window: Vec<f32>, downsample: usize, rate: usize) -> FourierAnalyzer {
+    fn new(
+        length: usize,
+        window: Vec<f32>,
+        downsample: usize,
+        rate: usize,
+        channels: Channels,
+        fft: std::sync::Arc<dyn FftBackend>,
+        drop_dc: bool,
+    ) -> FourierAnalyzer {
         use rustfft::num_traits::Zero;
 
-        let fft = rustfft::FftPlanner::new().plan_fft_forward(length);
         let buckets = length / 2;
 
         let downsampled_rate = rate as f32 / downsample as f32;
@@ -230,6 +490,13 @@ impl FourierAnalyzer {
             buckets,
             window,
             downsample,
+            channels,
+            drop_dc,
+            transforms: 0,
+
+            hop: (length * downsample / 2).max(1),
+            stft_next: None,
+            stft_samples: Vec::new(),
 
             rate,
             lowest,
@@ -238,13 +505,18 @@ impl FourierAnalyzer {
             fft,
 
             input: [Vec::with_capacity(length), Vec::with_capacity(length)],
-            output: vec![rustfft::num_complex::Complex::zero(); length],
+            output: [
+                vec![rustfft::num_complex::Complex::zero(); length],
+                vec![rustfft::num_complex::Complex::zero(); length],
+            ],
+            mono_output: vec![rustfft::num_complex::Complex::zero(); length],
 
             spectra: [
                 analyzer::Spectrum::new(vec![0.0; buckets], lowest, highest),
                 analyzer::Spectrum::new(vec![0.0; buckets], lowest, highest),
             ],
             average: analyzer::Spectrum::new(vec![0.0; buckets], lowest, highest),
+            mono: analyzer::Spectrum::new(vec![0.0; buckets], lowest, highest),
         };
 
         log::debug!("FourierAnalyzer({:p}):", &fa);
@@ -258,6 +530,9 @@ impl FourierAnalyzer {
         );
         log::debug!("    Lowest  Frequency   = {:8.3} Hz", lowest);
         log::debug!("    Highest Frequency   = {:8.3} Hz", highest);
+        log::debug!("    Channels            = {:?}", channels);
+        log::debug!("    Drop DC             = {:?}", drop_dc);
+        log::debug!("    Backend             = {:?}", fa.fft);
 
         fa
     }
@@ -280,28 +555,154 @@ impl FourierAnalyzer {
         self.highest
     }
 
+    /// Return the frequency resolution in Hz per bucket
+    ///
+    /// This is `highest / buckets`, which equals `rate / (downsample * length)`.
+    #[inline]
+    pub fn bin_width(&self) -> analyzer::Frequency {
+        self.highest / self.buckets as analyzer::Frequency
+    }
+
+    /// Return the number of transforms computed so far
+    pub fn transforms(&self) -> usize {
+        self.transforms
+    }
+
     /// Analyze a `SampleBuffer`
     ///
-    /// Returns the left and right channel data as spectra
+    /// Returns the left and right channel data as spectra.  Each bucket holds the power
+    /// (squared magnitude) of its frequency; use
+    /// [`Spectrum::sqrt_into`](struct.Spectrum.html#method.sqrt_into) to get amplitudes.
+    ///
+    /// Panics if the rate of `buf` does not match the analyzer.  Use
+    /// [`try_analyze`](#method.try_analyze) to handle this instead.
     pub fn analyze(
         &mut self,
         buf: &analyzer::SampleBuffer,
     ) -> [analyzer::Spectrum<&[analyzer::SignalStrength]>; 2] {
+        self.try_analyze(buf).unwrap_or_else(|e| panic!("{}!", e))
+    }
+
+    /// Analyze a `SampleBuffer`, failing if its rate does not match
+    ///
+    /// Like [`analyze`](#method.analyze), but returns an error instead of panicking when the
+    /// recorder runs at a different rate than the analyzer was planned for.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// # use vis_core::analyzer::fourier::*;
+    /// let mut analyzer = FourierBuilder::new()
+    ///     .length(512)
+    ///     .window(window::nuttall)
+    ///     .downsample(5)
+    ///     .rate(8000)
+    ///     .channels(Channels::Both)
+    ///     .drop_dc(false)
+    ///     .plan();
+    ///
+    /// let samples = analyzer::SampleBuffer::new(32000, 44100);
+    /// assert!(analyzer.try_analyze(&samples).is_err());
+    /// ```
+    pub fn try_analyze(
+        &mut self,
+        buf: &analyzer::SampleBuffer,
+    ) -> Result<[analyzer::Spectrum<&[analyzer::SignalStrength]>; 2], analyzer::AnalyzeError> {
         log::trace!("FourierAnalyzer({:p}): Analyzing ...", &self);
 
-        assert_eq!(
-            buf.rate(),
-            self.rate,
-            "Samplerate of buffer does not match!"
-        );
+        self.check_rate(buf)?;
+
+        self.transform_stereo(buf.iter(self.length, self.downsample));
+
+        Ok([self.spectra[0].as_ref(), self.spectra[1].as_ref()])
+    }
+
+    /// Analyze all windows which ended since the last call, [`hop`](#method.hop) samples apart
+    ///
+    /// Unlike [`analyze`](#method.analyze), which only looks at the latest samples, this is a
+    /// short-time fourier transform over the whole stream: consecutive calls continue where
+    /// the previous one stopped, so no samples are missed between frames, eg. to draw a
+    /// spectrogram without gaps.  The first call yields just the latest window.  Windows
+    /// which were already pushed out of the buffer are skipped.
+    ///
+    /// The spectra are yielded oldest first, the iterator computes them lazily.  Afterwards,
+    /// [`left`](#method.left) and [`right`](#method.right) hold the last computed window.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// # use vis_core::analyzer::fourier::*;
+    /// let mut analyzer = FourierBuilder::new()
+    ///     .length(256)
+    ///     .window(window::nuttall)
+    ///     .downsample(2)
+    ///     .rate(8000)
+    ///     .channels(Channels::Both)
+    ///     .drop_dc(false)
+    ///     .hop(128)
+    ///     .plan();
+    /// let samples = analyzer::SampleBuffer::new(8000, 8000);
+    /// analyzer.analyze_stft(&samples).count();
+    ///
+    /// samples.push(&[[0.0; 2]; 1024]);
+    /// let mut spectrogram = analyzer::Spectrogram::new(100, 64);
+    /// for [left, _] in analyzer.analyze_stft(&samples) {
+    ///     spectrogram.push(&left);
+    /// }
+    /// # assert_eq!(spectrogram.len(), 8);
+    /// ```
+    pub fn analyze_stft<'a>(
+        &'a mut self,
+        buf: &analyzer::SampleBuffer,
+    ) -> impl Iterator<Item = [analyzer::Spectrum<Vec<analyzer::SignalStrength>>; 2]> + 'a {
+        if let Err(e) = self.check_rate(buf) {
+            panic!("{}!", e);
+        }
+
+        let mut samples = std::mem::take(&mut self.stft_samples);
+        let generation = buf.copy_all(&mut samples) as i64;
+        let span = (self.length * self.downsample) as i64;
+        let hop = self.hop as i64;
+
+        // Generation of the oldest sample in the copy, negative for the initial silence
+        let base = generation - samples.len() as i64;
+        let mut next = self.stft_next.map(|n| n as i64).unwrap_or(generation);
+        if next - span < base {
+            let skipped = (base + span - next + hop - 1) / hop;
+            if self.stft_next.is_some() {
+                log::debug!("FourierAnalyzer({:p}): Skipping {} windows", self, skipped);
+            }
+            next += skipped * hop;
+        }
+
+        let count = if next <= generation {
+            (generation - next) / hop + 1
+        } else {
+            0
+        };
+        self.stft_next = Some((next + count * hop).max(0) as u64);
+
+        let start = (next - base) as usize;
+        let end = start + (count * hop) as usize;
+        Stft {
+            ends: (start..end).step_by(self.hop),
+            samples,
+            analyzer: self,
+        }
+    }
 
+    /// Transform the windowed `samples` of both channels into `spectra`
+    fn transform_stereo<I: Iterator<Item = [Sample; 2]>>(&mut self, samples: I) {
         // Copy samples to left and right buffer
         self.input[0].clear();
         self.input[1].clear();
-        for ([l, r], window) in buf
-            .iter(self.length, self.downsample)
-            .zip(self.window.iter())
-        {
+        for ([l, r], window) in samples.zip(self.window.iter()) {
+            let (l, r) = match self.channels {
+                Channels::Both => (l, r),
+                Channels::Left => (l, 0.0),
+                Channels::Right => (r, 0.0),
+                Channels::MonoMix => ((l + r) / 2.0, 0.0),
+            };
             self.input[0].push(rustfft::num_complex::Complex::new(l * window, 0.0));
             self.input[1].push(rustfft::num_complex::Complex::new(r * window, 0.0));
         }
@@ -309,19 +710,38 @@ impl FourierAnalyzer {
         debug_assert_eq!(self.input[0].len(), self.window.len());
         debug_assert_eq!(self.input[1].len(), self.window.len());
 
-        self.output.copy_from_slice(&self.input[0]);
-        self.fft.process(&mut self.output);
-        for (s, o) in self.spectra[0].iter_mut().zip(self.output.iter()) {
-            *s = o.norm_sqr();
+        self.transform(0);
+        if self.channels == Channels::Both {
+            self.transform(1);
+        } else {
+            // Only one channel was transformed, mirror it
+            let (first, second) = self.spectra.split_at_mut(1);
+            for (r, l) in second[0].iter_mut().zip(first[0].iter()) {
+                *r = *l;
+            }
         }
+    }
 
-        self.output.copy_from_slice(&self.input[1]);
-        self.fft.process(&mut self.output);
-        for (s, o) in self.spectra[1].iter_mut().zip(self.output.iter()) {
-            *s = o.norm_sqr();
+    fn check_rate(&self, buf: &analyzer::SampleBuffer) -> Result<(), analyzer::AnalyzeError> {
+        if buf.rate() == self.rate {
+            Ok(())
+        } else {
+            Err(analyzer::AnalyzeError::Rate {
+                buffer: buf.rate(),
+                analyzer: self.rate,
+            })
         }
+    }
 
-        [self.spectra[0].as_ref(), self.spectra[1].as_ref()]
+    fn transform(&mut self, channel: usize) {
+        let output = &mut self.output[channel];
+        output.copy_from_slice(&self.input[channel]);
+        self.fft.process(output);
+        let first = if self.drop_dc { 1 } else { 0 };
+        for (s, o) in self.spectra[channel].iter_mut().zip(output[first..].iter()) {
+            *s = o.norm_sqr();
+        }
+        self.transforms += 1;
     }
 
     /// Get the left channels spectral data from the last transform
@@ -334,7 +754,197 @@ impl FourierAnalyzer {
         self.spectra[1].as_ref()
     }
 
+    /// Fill `out` with the complex bins of a channel from the last transform
+    ///
+    /// `channel` is `0` for left and `1` for right.  The magnitude path stays the default,
+    /// the bins are only copied when asked for.  The power of each bin equals the bucket of
+    /// [`left`](#method.left) or [`right`](#method.right) and `out` gets the same span.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// # use vis_core::analyzer::fourier::*;
+    /// # let mut analyzer = FourierBuilder::new()
+    /// #     .length(512)
+    /// #     .window(window::nuttall)
+    /// #     .downsample(5)
+    /// #     .rate(8000)
+    /// #     .channels(Channels::Both)
+    /// #     .drop_dc(false)
+    /// #     .plan();
+    /// # let samples = analyzer::SampleBuffer::new(32000, 8000);
+    /// analyzer.analyze(&samples);
+    ///
+    /// let mut complex = analyzer::ComplexSpectrum::default();
+    /// analyzer.fill_complex(0, &mut complex);
+    /// let phase = complex.phase(10);
+    /// ```
+    pub fn fill_complex(&self, channel: usize, out: &mut analyzer::ComplexSpectrum) {
+        let first = if self.drop_dc { 1 } else { 0 };
+        out.assign(
+            &self.raw_output(channel)[first..first + self.buckets],
+            self.lowest,
+            self.highest,
+        );
+    }
+
+    /// Return the unprocessed output of the last transform of a channel
+    ///
+    /// `channel` is `0` for left and `1` for right.  This is exactly what the FFT backend
+    /// produced for the windowed input: all `length` complex bins, including DC, the Nyquist
+    /// bin and the mirrored negative frequencies, without dropping or scaling anything.  Meant
+    /// for advanced processing which needs more than the [`buckets`](#method.buckets) of the
+    /// spectra.
+    pub fn raw_output(&self, channel: usize) -> &[rustfft::num_complex::Complex<Sample>] {
+        assert!(channel < 2, "Invalid channel {}!", channel);
+
+        // Only one channel is transformed unless both are requested
+        if self.channels == Channels::Both {
+            &self.output[channel]
+        } else {
+            &self.output[0]
+        }
+    }
+
+    /// Fill a display buffer with the left and right spectra from the last transform
+    ///
+    /// Each channel gets `out.len() / 2` buckets, merging adjacent buckets like
+    /// [`Spectrum::fill_buckets`](struct.Spectrum.html#method.fill_buckets).
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer::fourier::*;
+    /// # let analyzer = FourierBuilder::new()
+    /// #     .length(512)
+    /// #     .window(window::nuttall)
+    /// #     .downsample(5)
+    /// #     .rate(8000)
+    /// #     .channels(Channels::Both)
+    /// #     .drop_dc(false)
+    /// #     .plan();
+    /// let mut display = [0.0; 64];
+    /// analyzer.fill_stereo_display(&mut display, StereoLayout::Mirror);
+    /// ```
+    pub fn fill_stereo_display(&self, out: &mut [analyzer::SignalStrength], layout: StereoLayout) {
+        assert_eq!(out.len() % 2, 0, "Display buffer must hold both channels!");
+        let n = out.len() / 2;
+
+        for o in out.iter_mut() {
+            *o = 0.0;
+        }
+
+        if n == 0 {
+            return;
+        }
+
+        for (channel, spectrum) in self.spectra.iter().enumerate() {
+            for (i, v) in spectrum.iter().enumerate() {
+                out[layout.index(channel, i * n / self.buckets, n)] += v;
+            }
+        }
+    }
+
+    /// Analyze a mono downmix of a `SampleBuffer`
+    ///
+    /// Transforms `(l + r) / 2` once instead of each channel on its own, which halves the work
+    /// if only a single spectrum is needed.  This is different from
+    /// [`average`](#method.average), which averages the power spectra of both channels: the
+    /// downmix happens before the transform, so signals which are out of phase between the
+    /// channels cancel out here, like they would on a mono speaker.
+    ///
+    /// Uses its own buffers, so the results of [`analyze`](#method.analyze) stay untouched.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// # use vis_core::analyzer::fourier::*;
+    /// # let mut analyzer = FourierBuilder::new()
+    /// #     .length(512)
+    /// #     .window(window::nuttall)
+    /// #     .downsample(5)
+    /// #     .rate(8000)
+    /// #     .channels(Channels::Both)
+    /// #     .drop_dc(false)
+    /// #     .plan();
+    /// # let samples = analyzer::SampleBuffer::new(32000, 8000);
+    /// let spectrum = analyzer.mono(&samples);
+    /// ```
+    pub fn mono(
+        &mut self,
+        buf: &analyzer::SampleBuffer,
+    ) -> analyzer::Spectrum<&[analyzer::SignalStrength]> {
+        if let Err(e) = self.check_rate(buf) {
+            panic!("{}!", e);
+        }
+
+        let samples = buf
+            .iter(self.length, self.downsample)
+            .map(|[l, r]| (l + r) / 2.0);
+        self.transform_mono(samples)
+    }
+
+    /// Transform a single channel of a sample buffer
+    ///
+    /// Like [`mono`](#method.mono), but analyzes one channel of the buffer instead of the
+    /// downmix, regardless of the [`Channels`](enum.Channels.html) this analyzer was planned
+    /// with.  Use one analyzer per channel to process left and right in separate pipelines.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// # use vis_core::analyzer::fourier::*;
+    /// # let build = || FourierBuilder::new()
+    /// #     .length(512)
+    /// #     .window(window::nuttall)
+    /// #     .downsample(5)
+    /// #     .rate(8000)
+    /// #     .channels(Channels::Left)
+    /// #     .drop_dc(false)
+    /// #     .plan();
+    /// # let (mut left, mut right) = (build(), build());
+    /// # let samples = analyzer::SampleBuffer::new(32000, 8000);
+    /// let left_spectrum = left.analyze_view(&samples.channel_view(0));
+    /// let right_spectrum = right.analyze_view(&samples.channel_view(1));
+    /// ```
+    pub fn analyze_view(
+        &mut self,
+        view: &analyzer::MonoView,
+    ) -> analyzer::Spectrum<&[analyzer::SignalStrength]> {
+        if let Err(e) = self.check_rate(view.buffer()) {
+            panic!("{}!", e);
+        }
+
+        let samples = view.iter(self.length, self.downsample);
+        self.transform_mono(samples)
+    }
+
+    fn transform_mono<I: Iterator<Item = Sample>>(
+        &mut self,
+        samples: I,
+    ) -> analyzer::Spectrum<&[analyzer::SignalStrength]> {
+        for (o, (s, window)) in self
+            .mono_output
+            .iter_mut()
+            .zip(samples.zip(self.window.iter()))
+        {
+            *o = rustfft::num_complex::Complex::new(s * window, 0.0);
+        }
+
+        self.fft.process(&mut self.mono_output);
+        let first = if self.drop_dc { 1 } else { 0 };
+        for (s, o) in self.mono.iter_mut().zip(self.mono_output[first..].iter()) {
+            *s = o.norm_sqr();
+        }
+        self.transforms += 1;
+
+        self.mono.as_ref()
+    }
+
     /// Calculate the average spectrum
+    ///
+    /// This is the mean of the left and right power spectra from the last
+    /// [`analyze`](#method.analyze).  See [`mono`](#method.mono) for analyzing a downmix
+    /// instead.
     pub fn average(&mut self) -> analyzer::Spectrum<&[analyzer::SignalStrength]> {
         analyzer::average_spectrum(&mut self.average, &self.spectra);
 
@@ -342,6 +952,37 @@ impl FourierAnalyzer {
     }
 }
 
+/// Lazy iterator over the windows of an STFT
+struct Stft<'a> {
+    analyzer: &'a mut FourierAnalyzer,
+    samples: Vec<[Sample; 2]>,
+    /// Indices into `samples` at which the windows end
+    ends: std::iter::StepBy<std::ops::Range<usize>>,
+}
+
+impl Iterator for Stft<'_> {
+    type Item = [analyzer::Spectrum<Vec<analyzer::SignalStrength>>; 2];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let end = self.ends.next()?;
+        let span = self.analyzer.length * self.analyzer.downsample;
+        let window = self.samples[end - span..end]
+            .iter()
+            .step_by(self.analyzer.downsample)
+            .cloned();
+
+        self.analyzer.transform_stereo(window);
+        Some(self.analyzer.spectra.clone())
+    }
+}
+
+impl Drop for Stft<'_> {
+    fn drop(&mut self) {
+        // Keep the allocation for the next call
+        self.analyzer.stft_samples = std::mem::take(&mut self.samples);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -353,6 +994,8 @@ mod tests {
             .length(512)
             .window(window::from_str("nuttall").unwrap())
             .downsample(8)
+            .channels(Channels::Both)
+            .drop_dc(false)
             .plan();
     }
 
@@ -363,6 +1006,8 @@ mod tests {
             .length(512)
             .window(window::from_str("nuttall").unwrap())
             .downsample(2)
+            .channels(Channels::Both)
+            .drop_dc(false)
             .plan();
 
         let buf = crate::analyzer::SampleBuffer::new(1024, 8000);
@@ -371,4 +1016,432 @@ mod tests {
 
         analyzer.analyze(&buf);
     }
+
+    #[test]
+    fn test_rate_mismatch() {
+        let mut analyzer = FourierBuilder::new()
+            .rate(8000)
+            .length(512)
+            .window(window::from_str("nuttall").unwrap())
+            .downsample(2)
+            .channels(Channels::Both)
+            .drop_dc(false)
+            .plan();
+
+        let buf = crate::analyzer::SampleBuffer::new(1024, 44100);
+        let err = analyzer.try_analyze(&buf).unwrap_err();
+        assert_eq!(
+            err,
+            AnalyzeError::Rate {
+                buffer: 44100,
+                analyzer: 8000
+            }
+        );
+        assert!(err.to_string().contains("44100 Hz"));
+        assert!(err.to_string().contains("8000 Hz"));
+
+        let buf = crate::analyzer::SampleBuffer::new(1024, 8000);
+        assert!(analyzer.try_analyze(&buf).is_ok());
+    }
+
+    #[test]
+    fn test_stft() {
+        let mut analyzer = FourierBuilder::new()
+            .rate(8000)
+            .length(256)
+            .window(window::nuttall)
+            .downsample(2)
+            .channels(Channels::Both)
+            .drop_dc(false)
+            .hop(128)
+            .plan();
+        let buf = crate::analyzer::SampleBuffer::new(4096, 8000);
+        let mut time = 0;
+        let mut push = |n: usize| {
+            let samples = (time..time + n)
+                .map(|i| {
+                    let t = i as f32 / 8000.0;
+                    [(t * 2500.0).sin(), (t * 6000.0).sin() * 0.5]
+                })
+                .collect::<Vec<_>>();
+            buf.push(&samples);
+            time += n;
+        };
+
+        // The first call only yields the latest window
+        push(1000);
+        let spectra = analyzer.analyze_stft(&buf).collect::<Vec<_>>();
+        assert_eq!(spectra.len(), 1);
+
+        push(3 * 128);
+        let spectra = analyzer.analyze_stft(&buf).collect::<Vec<_>>();
+        assert_eq!(spectra.len(), 3);
+        let [left, right] = analyzer.analyze(&buf);
+        assert!(spectra[2][0].iter().eq(left.iter()));
+        assert!(spectra[2][1].iter().eq(right.iter()));
+        assert!(!spectra[1][0].iter().eq(spectra[2][0].iter()));
+
+        // Nothing new, nothing to analyze
+        assert_eq!(analyzer.analyze_stft(&buf).count(), 0);
+
+        // No windows are lost between small pushes
+        let mut count = 0;
+        for _ in 0..10 {
+            push(100);
+            count += analyzer.analyze_stft(&buf).count();
+        }
+        assert_eq!(count, 1000 / 128);
+        push(24);
+        assert_eq!(analyzer.analyze_stft(&buf).count(), 1);
+
+        // Windows which left the buffer are skipped
+        push(8192);
+        assert_eq!(analyzer.analyze_stft(&buf).count(), (4096 - 512) / 128 + 1);
+    }
+
+    #[test]
+    fn test_bin_width() {
+        let analyzer = FourierBuilder::new()
+            .rate(8000)
+            .length(512)
+            .window(window::none)
+            .downsample(5)
+            .channels(Channels::Both)
+            .drop_dc(false)
+            .plan();
+
+        // 8000 Hz / 5 / 512
+        assert_eq!(analyzer.bin_width(), 3.125);
+        assert!((analyzer.left().bin_width() - analyzer.bin_width()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_complex() {
+        for channels in [Channels::Both, Channels::Right] {
+            let mut analyzer = FourierBuilder::new()
+                .rate(8000)
+                .length(64)
+                .window(window::nuttall)
+                .downsample(1)
+                .channels(channels)
+                .drop_dc(true)
+                .plan();
+
+            let buf = crate::analyzer::SampleBuffer::new(64, 8000);
+            buf.push(
+                &(0..64)
+                    .map(|i| {
+                        let t = i as f32 / 8000.0;
+                        let s = (t * 1000.0 * 2.0 * std::f32::consts::PI).sin();
+                        [s, 0.5 * s + 0.1]
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            analyzer.analyze(&buf);
+
+            let mut complex = analyzer::ComplexSpectrum::default();
+            let mut power = analyzer::Spectrum::new(vec![0.0; analyzer.buckets()], 0.0, 1.0);
+            for (channel, expected) in [analyzer.left(), analyzer.right()].iter().enumerate() {
+                analyzer.fill_complex(channel, &mut complex);
+                complex.power_into(&mut power);
+
+                assert_eq!(complex.len(), expected.len());
+                assert_eq!(power.lowest(), expected.lowest());
+                assert_eq!(power.highest(), expected.highest());
+                for (p, e) in power.iter().zip(expected.iter()) {
+                    assert!((p - e).abs() <= e.abs() * 1e-5, "{} != {}", p, e);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mono() {
+        let mut analyzer = FourierBuilder::new()
+            .rate(8000)
+            .length(64)
+            .window(window::nuttall)
+            .downsample(1)
+            .channels(Channels::Both)
+            .drop_dc(false)
+            .plan();
+
+        // The same 1000 Hz sine on both channels, but inverted on the right
+        let buf = crate::analyzer::SampleBuffer::new(64, 8000);
+        buf.push(
+            &(0..64)
+                .map(|i| {
+                    let t = i as f32 / 8000.0;
+                    let s = (t * 1000.0 * 2.0 * std::f32::consts::PI).sin();
+                    [s, -s]
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        analyzer.analyze(&buf);
+        let left = analyzer.left().iter().copied().collect::<Vec<_>>();
+        assert!(analyzer.average().max() > 1.0);
+
+        // The downmix cancels out, unlike the average
+        let (buckets, lowest) = (analyzer.buckets(), analyzer.lowest());
+        let mono = analyzer.mono(&buf);
+        assert_eq!(mono.len(), buckets);
+        assert_eq!(mono.lowest(), lowest);
+        assert!(mono.max() < 1e-6);
+        assert_eq!(analyzer.transforms(), 3);
+
+        // Results of analyze() are untouched
+        assert_eq!(analyzer.left().iter().copied().collect::<Vec<_>>(), left);
+
+        // Identical channels give the same spectrum as either of them
+        buf.push(
+            &(0..64)
+                .map(|i| [(i as f32 * 0.3).sin(); 2])
+                .collect::<Vec<_>>(),
+        );
+        analyzer.analyze(&buf);
+        let left = analyzer.left().iter().copied().collect::<Vec<_>>();
+        let mono = analyzer.mono(&buf);
+        for (m, l) in mono.iter().zip(left.iter()) {
+            assert!((m - l).abs() <= l * 1e-5 + 1e-6);
+        }
+
+        // A channel view gives exactly the spectrum of that channel
+        let view = analyzer.analyze_view(&buf.channel_view(0));
+        assert!(view.iter().eq(left.iter()));
+    }
+
+    #[test]
+    fn test_raw_output() {
+        for drop_dc in [false, true] {
+            let mut analyzer = FourierBuilder::new()
+                .rate(8000)
+                .length(64)
+                .window(window::hanning)
+                .downsample(1)
+                .channels(Channels::Left)
+                .drop_dc(drop_dc)
+                .plan();
+
+            let buf = crate::analyzer::SampleBuffer::new(64, 8000);
+            buf.push(
+                &(0..64)
+                    .map(|i| [(i as f32 * 0.7).sin() + 0.2, 0.0])
+                    .collect::<Vec<_>>(),
+            );
+            analyzer.analyze(&buf);
+
+            let first = if drop_dc { 1 } else { 0 };
+            for channel in 0..2 {
+                let raw = analyzer.raw_output(channel);
+                assert_eq!(raw.len(), 64);
+                for (r, s) in raw[first..].iter().zip(analyzer.left().iter()) {
+                    assert_eq!(r.norm_sqr(), *s);
+                }
+            }
+            assert_eq!(analyzer.left().len(), analyzer.buckets());
+        }
+    }
+
+    #[test]
+    fn test_drop_dc() {
+        let analyze = |drop_dc| {
+            let mut analyzer = FourierBuilder::new()
+                .rate(8000)
+                .length(64)
+                .window(window::none)
+                .downsample(1)
+                .channels(Channels::Left)
+                .drop_dc(drop_dc)
+                .plan();
+
+            // A strong DC offset and a weaker sine in bin 8 (1000 Hz)
+            let buf = crate::analyzer::SampleBuffer::new(64, 8000);
+            buf.push(
+                &(0..64)
+                    .map(|i| {
+                        let t = i as f32 / 8000.0;
+                        [2.0 + (t * 1000.0 * 2.0 * std::f32::consts::PI).sin(), 0.0]
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            analyzer.analyze(&buf);
+            analyzer
+        };
+
+        let with_dc = analyze(false);
+        assert_eq!(with_dc.left()[0], with_dc.left().max());
+
+        let analyzer = analyze(true);
+        let left = analyzer.left();
+        assert_eq!(left.len(), analyzer.buckets());
+        assert_eq!(left.lowest(), analyzer.bin_width());
+        assert_eq!(left.id_to_freq(0), 125.0);
+
+        // The sine is the maximum now and sits at its actual frequency
+        let peak = left.freq_to_id(1000.0);
+        assert_eq!(left[peak], left.max());
+        assert!(left[0] < 1e-3 * left.max());
+    }
+
+    #[test]
+    fn test_target_resolution() {
+        for &(rate, downsample, hz) in &[
+            (8000, 5, 10.0),
+            (8000, 5, 3.125),
+            (44100, 1, 10.0),
+            (48000, 4, 0.5),
+            (8000, 1, 10000.0),
+        ] {
+            let analyzer = FourierBuilder::new()
+                .rate(rate)
+                .downsample(downsample)
+                .target_resolution(hz)
+                .window(window::none)
+                .channels(Channels::Left)
+                .drop_dc(false)
+                .plan();
+
+            assert!(analyzer.bin_width() <= hz, "{} Hz", hz);
+            // Half the length would not be enough anymore
+            assert!(analyzer.bin_width() * 2.0 > hz || analyzer.buckets() == 1);
+            assert!(analyzer.buckets().is_power_of_two());
+        }
+    }
+
+    #[test]
+    fn test_stereo_layout() {
+        let indices = |layout: StereoLayout| {
+            let mut indices = vec![(0, 0); 6];
+            for c in 0..2 {
+                for b in 0..3 {
+                    indices[layout.index(c, b, 3)] = (c, b);
+                }
+            }
+            indices
+        };
+
+        assert_eq!(
+            indices(StereoLayout::Stack),
+            [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]
+        );
+        assert_eq!(
+            indices(StereoLayout::Mirror),
+            [(0, 2), (0, 1), (0, 0), (1, 0), (1, 1), (1, 2)]
+        );
+        assert_eq!(
+            indices(StereoLayout::Interleave),
+            [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn test_fill_stereo_display() {
+        let mut analyzer = FourierBuilder::new()
+            .rate(8000)
+            .length(64)
+            .window(window::none)
+            .downsample(1)
+            .channels(Channels::Both)
+            .drop_dc(false)
+            .plan();
+
+        let buf = crate::analyzer::SampleBuffer::new(64, 8000);
+        buf.push(&[[1.0, 0.0]; 64]);
+        analyzer.analyze(&buf);
+
+        for layout in [
+            StereoLayout::Stack,
+            StereoLayout::Mirror,
+            StereoLayout::Interleave,
+        ] {
+            let mut display = [-1.0; 8];
+            analyzer.fill_stereo_display(&mut display, layout);
+
+            let expected = analyzer.left().fill_buckets_alloc(4);
+            for b in 0..4 {
+                assert_eq!(display[layout.index(0, b, 4)], expected[b]);
+                assert_eq!(display[layout.index(1, b, 4)], 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_channels() {
+        let buf = crate::analyzer::SampleBuffer::new(1024, 8000);
+        buf.push(
+            &(0..1024)
+                .map(|i| [(i as f32 * 0.3).sin(), 0.0])
+                .collect::<Vec<_>>(),
+        );
+
+        let analyze = |channels| {
+            let mut analyzer = FourierBuilder::new()
+                .rate(8000)
+                .length(512)
+                .window(window::nuttall)
+                .downsample(2)
+                .channels(channels)
+                .drop_dc(false)
+                .plan();
+            analyzer.analyze(&buf);
+            analyzer
+        };
+
+        let both = analyze(Channels::Both);
+        assert_eq!(both.transforms(), 2);
+        assert!(both.left().max() > 0.0);
+        assert_eq!(both.right().max(), 0.0);
+
+        let left = analyze(Channels::Left);
+        assert_eq!(left.transforms(), 1);
+        assert!(left.left().iter().eq(both.left().iter()));
+        assert!(left.right().iter().eq(both.left().iter()));
+
+        let right = analyze(Channels::Right);
+        assert_eq!(right.transforms(), 1);
+        assert_eq!(right.left().max(), 0.0);
+        assert_eq!(right.right().max(), 0.0);
+
+        let mono = analyze(Channels::MonoMix);
+        assert_eq!(mono.transforms(), 1);
+        assert!(mono.left().max() > 0.0);
+        assert!(mono.left().iter().eq(mono.right().iter()));
+    }
+
+    #[test]
+    fn test_backend() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Debug, Default)]
+        struct CountingBackend(AtomicUsize);
+
+        impl FftBackend for CountingBackend {
+            fn process(&self, buffer: &mut [rustfft::num_complex::Complex<Sample>]) {
+                assert_eq!(buffer.len(), 64);
+                self.0.fetch_add(1, Ordering::SeqCst);
+                for (i, c) in buffer.iter_mut().enumerate() {
+                    *c = rustfft::num_complex::Complex::new(i as f32, 0.0);
+                }
+            }
+        }
+
+        let backend = std::sync::Arc::new(CountingBackend::default());
+        let mut analyzer = FourierBuilder::new()
+            .rate(8000)
+            .length(64)
+            .window(window::none)
+            .downsample(1)
+            .channels(Channels::Both)
+            .drop_dc(false)
+            .backend(backend.clone())
+            .plan();
+
+        let buf = crate::analyzer::SampleBuffer::new(64, 8000);
+        buf.push(&[[1.0; 2]; 64]);
+
+        let [left, _] = analyzer.analyze(&buf);
+        assert_eq!(left[3], 9.0);
+        assert_eq!(backend.0.load(Ordering::SeqCst), 2);
+    }
 }
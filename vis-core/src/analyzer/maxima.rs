@@ -0,0 +1,146 @@
+//! Maxima Tracking
+use crate::analyzer;
+
+/// Smooths the maxima of consecutive spectra over time
+///
+/// [`Spectrum::find_maxima`](../spectrum/struct.Spectrum.html#method.find_maxima) snaps to
+/// bucket frequencies and knows nothing about the previous frame, so note-reactive visuals
+/// jitter.  The tracker associates each new maximum with the nearest maximum of the last
+/// frame and smooths position and amplitude, so a slowly bending note moves smoothly instead.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let mut spectrum = analyzer::Spectrum::new(vec![0.0; 400], 220.0, 660.0);
+/// spectrum[200] = 20.0;
+///
+/// let mut tracker = analyzer::MaximaTracker::new(0.8).max_distance(20.0);
+///
+/// let mut buf = [(0.0, 0.0); 10];
+/// let maxima = tracker.update(spectrum.find_maxima(&mut buf));
+/// # assert_eq!(maxima.len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MaximaTracker {
+    smoothing: f32,
+    max_distance: analyzer::Frequency,
+
+    tracked: Vec<(analyzer::Frequency, analyzer::SignalStrength)>,
+    next: Vec<(analyzer::Frequency, analyzer::SignalStrength)>,
+}
+
+impl MaximaTracker {
+    /// Create a new tracker
+    ///
+    /// `smoothing` is the weight of the previous frame in `[0, 1)`.  `0.0` disables smoothing,
+    /// the higher it is the slower tracked maxima follow the input.
+    pub fn new(smoothing: f32) -> MaximaTracker {
+        assert!((0.0..1.0).contains(&smoothing), "Smoothing out of range!");
+
+        MaximaTracker {
+            smoothing,
+            max_distance: 50.0,
+
+            tracked: Vec::new(),
+            next: Vec::new(),
+        }
+    }
+
+    /// Set the largest distance in Hz over which maxima are associated between frames
+    ///
+    /// A maximum further away from all tracked maxima starts a new track.  Defaults to `50.0`.
+    pub fn max_distance(mut self, max_distance: analyzer::Frequency) -> MaximaTracker {
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Feed the maxima of the current frame and return the smoothed maxima
+    ///
+    /// `maxima` are `(frequency, amplitude)` pairs as returned by `find_maxima`.  Each tracked
+    /// maximum is matched at most once, biggest maxima first.  Tracked maxima without a match
+    /// are dropped.  The result is sorted like the input, starting with the biggest.
+    pub fn update(
+        &mut self,
+        maxima: &[(analyzer::Frequency, analyzer::SignalStrength)],
+    ) -> &[(analyzer::Frequency, analyzer::SignalStrength)] {
+        let mut order = (0..maxima.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| maxima[*b].1.partial_cmp(&maxima[*a].1).unwrap());
+
+        self.next.clear();
+        for (freq, ampl) in order.into_iter().map(|i| maxima[i]) {
+            let nearest = self
+                .tracked
+                .iter()
+                .enumerate()
+                .map(|(i, (f, _))| (i, (f - freq).abs()))
+                .filter(|(_, d)| *d <= self.max_distance)
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+
+            match nearest {
+                Some((i, _)) => {
+                    let (f, a) = self.tracked.swap_remove(i);
+                    self.next.push((
+                        f * self.smoothing + freq * (1.0 - self.smoothing),
+                        a * self.smoothing + ampl * (1.0 - self.smoothing),
+                    ));
+                }
+                None => self.next.push((freq, ampl)),
+            }
+        }
+
+        self.next
+            .sort_by(|(_, a1), (_, a2)| a2.partial_cmp(a1).unwrap());
+        std::mem::swap(&mut self.tracked, &mut self.next);
+        &self.tracked
+    }
+
+    /// Return the smoothed maxima of the last update
+    pub fn maxima(&self) -> &[(analyzer::Frequency, analyzer::SignalStrength)] {
+        &self.tracked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bending_note() {
+        let mut tracker = MaximaTracker::new(0.8).max_distance(30.0);
+        let mut buf = [(0.0, 0.0); 10];
+
+        let mut last = None;
+        for i in 0..100 {
+            // 10 Hz per bucket, the note bends up by one bucket every 5 frames
+            let mut spectrum = analyzer::Spectrum::new(vec![0.0; 401], 0.0, 4000.0);
+            spectrum[40 + i / 5] = 1.0;
+            spectrum[100] = 0.5;
+
+            let maxima = tracker.update(spectrum.find_maxima(&mut buf));
+            assert_eq!(maxima.len(), 2);
+            assert_eq!(maxima[1], (1000.0, 0.5));
+
+            let (freq, ampl) = maxima[0];
+            assert_eq!(ampl, 1.0);
+            if let Some(last) = last {
+                // Moves on every frame, but never snaps a whole bucket
+                let step = freq - last;
+                assert!((0.0..5.0).contains(&step), "Jump from {} to {}", last, freq);
+            }
+            last = Some(freq);
+        }
+
+        assert!((last.unwrap() - 590.0).abs() < 10.0);
+    }
+
+    #[test]
+    fn test_new_track() {
+        let mut tracker = MaximaTracker::new(0.5).max_distance(10.0);
+
+        tracker.update(&[(100.0, 1.0)]);
+        assert_eq!(tracker.update(&[(104.0, 0.0)]), [(102.0, 0.5)]);
+        // Too far away, starts a new track without smoothing
+        assert_eq!(tracker.update(&[(200.0, 1.0)]), [(200.0, 1.0)]);
+        assert_eq!(tracker.maxima(), [(200.0, 1.0)]);
+    }
+}
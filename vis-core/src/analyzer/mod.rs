@@ -1,13 +1,58 @@
+pub mod bands;
 pub mod beat;
+pub mod calibrate;
+pub mod chroma;
+pub mod contrast;
+pub mod events;
+pub mod filter;
 pub mod fourier;
+pub mod maxima;
+pub mod onset;
+pub mod preset;
+pub mod quantile;
 pub mod samples;
+pub mod silence;
+pub mod spectrogram;
 pub mod spectrum;
+pub mod tempo;
 
 #[doc(inline)]
-pub use self::beat::{BeatBuilder, BeatDetector};
+pub use self::bands::LogBands;
 #[doc(inline)]
-pub use self::fourier::{window, FourierAnalyzer, FourierBuilder};
+pub use self::beat::{BeatBuilder, BeatDetector, MultiBandBeat};
 #[doc(inline)]
-pub use self::samples::{Sample, SampleBuffer};
+pub use self::calibrate::{Calibration, Calibrator};
 #[doc(inline)]
-pub use self::spectrum::{average_spectrum, Frequency, SignalStrength, Spectrum};
+pub use self::chroma::ChromaVector;
+#[doc(inline)]
+pub use self::contrast::SpectralContrast;
+#[doc(inline)]
+pub use self::events::{Event, EventKind, Events};
+#[doc(inline)]
+pub use self::filter::{
+    BandSmoother, DereverbLite, FluxSpectrum, PeakHold, RunningAverage, SpectrumSmoother,
+    SpectrumTrail, Whitener,
+};
+#[doc(inline)]
+pub use self::fourier::{window, AnalyzeError, FourierAnalyzer, FourierBuilder};
+#[doc(inline)]
+pub use self::maxima::MaximaTracker;
+#[doc(inline)]
+pub use self::onset::{OnsetBuilder, OnsetDetector};
+#[doc(inline)]
+pub use self::preset::Preset;
+#[doc(inline)]
+pub use self::quantile::QuantileTracker;
+#[doc(inline)]
+pub use self::samples::{recommended_buffer_size, MonoView, Sample, SampleBuffer};
+#[doc(inline)]
+pub use self::silence::{SilenceBuilder, SilenceDetector};
+#[doc(inline)]
+pub use self::spectrogram::Spectrogram;
+#[doc(inline)]
+pub use self::spectrum::{
+    average_spectrum, try_average_spectrum, ComplexSpectrum, Frequency, ReversedView,
+    SignalStrength, Spectrum, SpectrumMismatch,
+};
+#[doc(inline)]
+pub use self::tempo::{TempoBuilder, TempoTracker};
@@ -0,0 +1,270 @@
+//! Onset Detection
+use crate::analyzer;
+use std::collections;
+
+/// Builder for OnsetDetector
+#[derive(Debug, Default)]
+pub struct OnsetBuilder {
+    /// Number of recent flux values the adaptive threshold is the median of
+    ///
+    /// Longer windows adapt slower to changes in the music.  Defaults to `20`, can also be set
+    /// from config as `"audio.onset.window"`.
+    pub window: Option<usize>,
+
+    /// How far the flux has to rise above the median of the window to count as an onset
+    ///
+    /// Defaults to `1.5`, can also be set from config as `"audio.onset.factor"`.
+    pub factor: Option<analyzer::SignalStrength>,
+
+    /// Lowest threshold, regardless of the median
+    ///
+    /// Steady input has almost no flux, so without a floor, rounding noise would trigger
+    /// onsets.  Defaults to `1.0`, can also be set from config as `"audio.onset.min_flux"`.
+    pub min_flux: Option<analyzer::SignalStrength>,
+}
+
+impl OnsetBuilder {
+    /// Create new OnsetBuilder
+    pub fn new() -> OnsetBuilder {
+        Default::default()
+    }
+
+    /// Set the length of the threshold window
+    pub fn window(&mut self, n: usize) -> &mut OnsetBuilder {
+        self.window = Some(n);
+        self
+    }
+
+    /// Set the threshold factor
+    pub fn factor(&mut self, factor: analyzer::SignalStrength) -> &mut OnsetBuilder {
+        self.factor = Some(factor);
+        self
+    }
+
+    /// Set the lowest threshold
+    pub fn min_flux(&mut self, flux: analyzer::SignalStrength) -> &mut OnsetBuilder {
+        self.min_flux = Some(flux);
+        self
+    }
+
+    /// Build the detector
+    pub fn build(&mut self) -> OnsetDetector {
+        OnsetDetector::from_builder(self)
+    }
+}
+
+/// An onset detector based on spectral flux
+///
+/// The flux is the sum of the increases of all buckets between two successive spectra, see
+/// [`FluxSpectrum`](../filter/struct.FluxSpectrum.html).  It jumps whenever a new sound
+/// starts, no matter where in the spectrum, so this also catches percussion which the
+/// [`BeatDetector`](../beat/struct.BeatDetector.html) misses because it only looks at the
+/// bass.
+///
+/// An onset is detected when the flux rises above the median of the recent flux times the
+/// [`factor`](struct.OnsetBuilder.html#structfield.factor), but at least
+/// [`min_flux`](struct.OnsetBuilder.html#structfield.min_flux).  It has to fall below again
+/// before the next onset can be detected.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// # let spectrum = analyzer::Spectrum::new(vec![0.0; 400], 0.0, 4000.0);
+/// let mut onset = analyzer::OnsetBuilder::new()
+///     .window(20)
+///     .factor(1.5)
+///     .min_flux(1.0)
+///     .build();
+///
+/// // Every analyzer cycle
+/// if onset.detect(&spectrum) {
+///     println!("Onset!");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OnsetDetector {
+    window: usize,
+    factor: analyzer::SignalStrength,
+    min_flux: analyzer::SignalStrength,
+
+    flux: analyzer::FluxSpectrum,
+    history: collections::VecDeque<analyzer::SignalStrength>,
+    sorted: Vec<analyzer::SignalStrength>,
+
+    last_flux: analyzer::SignalStrength,
+    threshold: analyzer::SignalStrength,
+    above: bool,
+}
+
+impl OnsetDetector {
+    fn from_builder(build: &OnsetBuilder) -> OnsetDetector {
+        let window = build
+            .window
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.onset.window", 20));
+        let factor = build
+            .factor
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.onset.factor", 1.5));
+        let min_flux = build
+            .min_flux
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.onset.min_flux", 1.0));
+
+        assert!(window > 0, "Onset window must not be empty!");
+        assert!(factor > 0.0, "Onset factor must be positive!");
+
+        log::debug!("OnsetDetector:");
+        log::debug!("    Window      = {:8}", window);
+        log::debug!("    Factor      = {:8}", factor);
+        log::debug!("    Min. Flux   = {:8}", min_flux);
+
+        OnsetDetector {
+            window,
+            factor,
+            min_flux,
+
+            flux: analyzer::FluxSpectrum::new(),
+            history: collections::VecDeque::with_capacity(window + 1),
+            sorted: Vec::with_capacity(window),
+
+            last_flux: 0.0,
+            threshold: 0.0,
+            above: false,
+        }
+    }
+
+    /// Get the flux computed during the last detection cycle
+    pub fn last_flux(&self) -> analyzer::SignalStrength {
+        self.last_flux
+    }
+
+    /// Get the threshold the last flux was compared to
+    pub fn threshold(&self) -> analyzer::SignalStrength {
+        self.threshold
+    }
+
+    /// Detect an onset
+    ///
+    /// Call this with every new spectrum.  Returns true if an onset started with this
+    /// spectrum and false otherwise.
+    pub fn detect<S: analyzer::spectrum::Storage>(
+        &mut self,
+        spectrum: &analyzer::Spectrum<S>,
+    ) -> bool {
+        let flux = self
+            .flux
+            .update(spectrum)
+            .iter()
+            .sum::<analyzer::SignalStrength>();
+
+        self.sorted.clear();
+        self.sorted.extend(self.history.iter().copied());
+        let median = if self.sorted.is_empty() {
+            0.0
+        } else {
+            let mid = self.sorted.len() / 2;
+            *self
+                .sorted
+                .select_nth_unstable_by(mid, |a, b| a.partial_cmp(b).expect("NaN in flux"))
+                .1
+        };
+        self.threshold = (median * self.factor).max(self.min_flux);
+
+        if self.history.len() == self.window {
+            self.history.pop_front();
+        }
+        self.history.push_back(flux);
+        self.last_flux = flux;
+
+        let was_above = self.above;
+        self.above = flux > self.threshold;
+        self.above && !was_above
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+
+    #[test]
+    fn test_threshold() {
+        let mut onset = OnsetBuilder::new()
+            .window(4)
+            .factor(2.0)
+            .min_flux(0.5)
+            .build();
+        let mut spectrum = analyzer::Spectrum::new(vec![0.0; 4], 0.0, 100.0);
+
+        assert!(!onset.detect(&spectrum));
+        // Flux of 1 every cycle
+        for i in 1..=4 {
+            spectrum[0] = i as f32;
+            onset.detect(&spectrum);
+        }
+        assert_eq!(onset.threshold(), 2.0);
+
+        // Flux of 3 is above twice the median
+        spectrum[0] += 1.0;
+        spectrum[3] += 2.0;
+        assert!(onset.detect(&spectrum));
+        assert_eq!(onset.last_flux(), 3.0);
+
+        // Staying above does not trigger again
+        spectrum[1] += 3.0;
+        assert!(!onset.detect(&spectrum));
+
+        // Falling back and rising again does
+        spectrum[0] += 1.0;
+        assert!(!onset.detect(&spectrum));
+        spectrum[2] += 8.0;
+        assert!(onset.detect(&spectrum));
+
+        // Nothing below the floor counts, even after a quiet phase
+        for _ in 0..4 {
+            onset.detect(&spectrum);
+        }
+        assert_eq!(onset.threshold(), 0.5);
+        spectrum[3] += 0.4;
+        assert!(!onset.detect(&spectrum));
+    }
+
+    #[test]
+    fn test_clicks() {
+        let mut rec = crate::recorder::synth::SynthBuilder::new()
+            .rate(8000)
+            .buffer_size(8000)
+            .sine(1000.0, 0.2)
+            .sine(1500.0, 0.1)
+            .clicks(120.0)
+            .channel_gains([1.0, 1.0])
+            .create();
+
+        let mut analyzer = analyzer::FourierBuilder::new()
+            .rate(8000)
+            .length(128)
+            .window(analyzer::window::nuttall)
+            .downsample(1)
+            .channels(analyzer::fourier::Channels::Both)
+            .drop_dc(false)
+            .plan();
+        let mut onset = OnsetBuilder::new()
+            .window(20)
+            .factor(1.5)
+            .min_flux(1.0)
+            .build();
+
+        let mut onsets = Vec::new();
+        for i in 1..=1000 {
+            rec.sync(i as f32 * 0.01);
+            analyzer.analyze(rec.sample_buffer());
+            if onset.detect(&analyzer.average()) {
+                onsets.push(i);
+            }
+        }
+
+        // 10 seconds at 120 BPM, one onset right after every click
+        assert!((19..=21).contains(&onsets.len()), "{:?}", onsets);
+        for i in onsets.iter().skip(1) {
+            assert!(i % 50 <= 5, "{:?}", onsets);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Latency vs. Smoothness Presets
+use crate::analyzer;
+
+/// Bundled analyzer settings trading latency for smoothness
+///
+/// | Preset       | Fourier length | Fourier downsample | Beat decay | Beat downsample | Smoothing |
+/// | ------------ | -------------- | ------------------ | ---------- | --------------- | --------- |
+/// | `Responsive` | 256            | 4                  | 1000.0     | 8               | 0.5       |
+/// | `Balanced`   | 512            | 5                  | 2000.0     | 10              | 0.8       |
+/// | `Smooth`     | 1024           | 6                  | 4000.0     | 12              | 0.95      |
+///
+/// `Balanced` matches the builder defaults.  Settings explicitly set on a builder are
+/// overwritten when applying a preset, so set them afterwards if you want to tweak them.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let mut fourier = analyzer::FourierBuilder::new();
+/// let mut beat = analyzer::BeatBuilder::new();
+///
+/// let preset = analyzer::Preset::Responsive;
+/// preset.apply(&mut fourier, &mut beat);
+///
+/// let mut rolling = 0.0;
+/// # let volume = 1.0;
+/// // In the render loop
+/// rolling = rolling * preset.smoothing() + volume * (1.0 - preset.smoothing());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Low latency, jittery
+    Responsive,
+    /// The defaults
+    Balanced,
+    /// High latency, calm
+    Smooth,
+}
+
+impl Preset {
+    /// Parse a preset name, eg. from config
+    pub fn from_name(name: &str) -> Option<Preset> {
+        match name {
+            "responsive" => Some(Preset::Responsive),
+            "balanced" => Some(Preset::Balanced),
+            "smooth" => Some(Preset::Smooth),
+            _ => None,
+        }
+    }
+
+    /// Configure fourier analyzer and beat detector for this preset
+    pub fn apply(&self, fourier: &mut analyzer::FourierBuilder, beat: &mut analyzer::BeatBuilder) {
+        let (length, downsample, decay, beat_downsample) = match self {
+            Preset::Responsive => (256, 4, 1000.0, 8),
+            Preset::Balanced => (512, 5, 2000.0, 10),
+            Preset::Smooth => (1024, 6, 4000.0, 12),
+        };
+
+        fourier.length(length).downsample(downsample);
+        beat.decay(decay).downsample(beat_downsample);
+    }
+
+    /// Factor for smoothing values over time as `old * factor + new * (1 - factor)`
+    ///
+    /// Can be used with [`BandSmoother`](struct.BandSmoother.html) or for smoothing volume.
+    pub fn smoothing(&self) -> analyzer::SignalStrength {
+        match self {
+            Preset::Responsive => 0.5,
+            Preset::Balanced => 0.8,
+            Preset::Smooth => 0.95,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        for (preset, length, downsample, decay, beat_downsample, smoothing) in [
+            (Preset::Responsive, 256, 4, 1000.0, 8, 0.5),
+            (Preset::Balanced, 512, 5, 2000.0, 10, 0.8),
+            (Preset::Smooth, 1024, 6, 4000.0, 12, 0.95),
+        ] {
+            let mut fourier = analyzer::FourierBuilder::new();
+            let mut beat = analyzer::BeatBuilder::new();
+            preset.apply(&mut fourier, &mut beat);
+
+            assert_eq!(fourier.length, Some(length));
+            assert_eq!(fourier.downsample, Some(downsample));
+            assert_eq!(beat.decay, Some(decay));
+            assert_eq!(beat.downsample, Some(beat_downsample));
+            assert_eq!(preset.smoothing(), smoothing);
+        }
+
+        assert_eq!(Preset::from_name("smooth"), Some(Preset::Smooth));
+        assert_eq!(Preset::from_name("fast"), None);
+    }
+}
@@ -0,0 +1,189 @@
+//! Streaming Quantiles
+
+/// Online estimate of a quantile of a stream of values
+///
+/// Uses the P² algorithm by Jain and Chlamtac, which keeps five markers instead of the whole
+/// history, so updating is constant in time and memory.  This makes it a good fit for adaptive
+/// thresholds, eg. triggering on volumes above the 90th percentile of what was heard so far.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let mut tracker = analyzer::QuantileTracker::new(0.9);
+/// for i in 0..100 {
+///     tracker.update(i as f32);
+/// }
+/// let threshold = tracker.quantile();
+/// # assert!((threshold - 89.0).abs() < 2.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct QuantileTracker {
+    p: f32,
+    count: usize,
+    /// Marker heights
+    heights: [f32; 5],
+    /// Actual marker positions
+    positions: [f64; 5],
+    /// Desired marker positions
+    desired: [f64; 5],
+    /// Increments of the desired marker positions
+    increments: [f64; 5],
+}
+
+impl QuantileTracker {
+    /// Create a new tracker for the quantile `p`
+    ///
+    /// `p` has to be in `[0, 1]`, eg. `0.9` for the 90th percentile.
+    pub fn new(p: f32) -> QuantileTracker {
+        assert!((0.0..=1.0).contains(&p), "Quantile out of range!");
+
+        let pf = p as f64;
+        QuantileTracker {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [0.0, 1.0, 2.0, 3.0, 4.0],
+            desired: [0.0, 2.0 * pf, 4.0 * pf, 2.0 + 2.0 * pf, 4.0],
+            increments: [0.0, pf / 2.0, pf, (1.0 + pf) / 2.0, 1.0],
+        }
+    }
+
+    /// Add a value to the stream
+    pub fn update(&mut self, x: f32) {
+        if self.count < 5 {
+            self.heights[self.count] = x;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights
+                    .sort_by(|a, b| a.partial_cmp(b).expect("NaN in quantile tracker"));
+            }
+            return;
+        }
+        self.count += 1;
+
+        // Find the cell containing x, extending the extremes if necessary
+        let k = if x < self.heights[0] {
+            self.heights[0] = x;
+            0
+        } else if x >= self.heights[4] {
+            self.heights[4] = x;
+            3
+        } else {
+            (0..4)
+                .find(|&i| x < self.heights[i + 1])
+                .expect("Value is within the markers")
+        };
+
+        for n in self.positions[k + 1..].iter_mut() {
+            *n += 1.0;
+        }
+        for (d, inc) in self.desired.iter_mut().zip(self.increments.iter()) {
+            *d += inc;
+        }
+
+        // Move the middle markers towards their desired positions
+        for i in 1..4 {
+            let d = self.desired[i] - self.positions[i];
+            let n = &self.positions;
+            if (d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0) {
+                let d = d.signum();
+                let parabolic = self.parabolic(i, d);
+                self.heights[i] =
+                    if self.heights[i - 1] < parabolic && parabolic < self.heights[i + 1] {
+                        parabolic
+                    } else {
+                        self.linear(i, d)
+                    };
+                self.positions[i] += d;
+            }
+        }
+    }
+
+    fn parabolic(&self, i: usize, d: f64) -> f32 {
+        let q = &self.heights;
+        let n = &self.positions;
+        let (q0, q1, q2) = (q[i - 1] as f64, q[i] as f64, q[i + 1] as f64);
+
+        (q1 + d / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + d) * (q2 - q1) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - d) * (q1 - q0) / (n[i] - n[i - 1]))) as f32
+    }
+
+    fn linear(&self, i: usize, d: f64) -> f32 {
+        let j = if d > 0.0 { i + 1 } else { i - 1 };
+        let q = &self.heights;
+        let n = &self.positions;
+
+        (q[i] as f64 + d * (q[j] - q[i]) as f64 / (n[j] - n[i])) as f32
+    }
+
+    /// Return the current estimate of the quantile
+    ///
+    /// Before five values were seen, the nearest of the values so far is returned.  Without any
+    /// values, this is `0`.
+    pub fn quantile(&self) -> f32 {
+        if self.count >= 5 {
+            return self.heights[2];
+        }
+        if self.count == 0 {
+            return 0.0;
+        }
+
+        let mut values = self.heights[..self.count].to_vec();
+        values.sort_by(|a, b| a.partial_cmp(b).expect("NaN in quantile tracker"));
+        values[((self.count - 1) as f32 * self.p).round() as usize]
+    }
+
+    /// Return the tracked quantile
+    pub fn p(&self) -> f32 {
+        self.p
+    }
+
+    /// Return the number of values seen so far
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic, uniformly distributed values in `[0, 1)`
+    fn uniform(n: usize) -> impl Iterator<Item = f32> {
+        let mut state = 0x2545f491u32;
+        (0..n).map(move |_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state >> 8) as f32 / (1 << 24) as f32
+        })
+    }
+
+    #[test]
+    fn test_uniform() {
+        for &p in &[0.1, 0.5, 0.9] {
+            let mut tracker = QuantileTracker::new(p);
+            for x in uniform(10000) {
+                tracker.update(x);
+            }
+            assert_eq!(tracker.count(), 10000);
+            assert!(
+                (tracker.quantile() - p).abs() < 0.02,
+                "Quantile {} estimated as {}",
+                p,
+                tracker.quantile()
+            );
+        }
+    }
+
+    #[test]
+    fn test_few_values() {
+        let mut tracker = QuantileTracker::new(0.5);
+        assert_eq!(tracker.quantile(), 0.0);
+        for &x in &[3.0, 1.0, 2.0] {
+            tracker.update(x);
+        }
+        assert_eq!(tracker.quantile(), 2.0);
+    }
+}
@@ -1,12 +1,47 @@
 //! Sample Buffer
 use std::collections;
 use std::sync;
+use std::time;
 
 /// Type Alias for Samples
 pub type Sample = f32;
 
 type _SampleBuf = sync::Arc<parking_lot::Mutex<collections::VecDeque<[Sample; 2]>>>;
 
+type _Tap = Box<dyn FnMut(&[[Sample; 2]]) + Send>;
+
+/// Callbacks receiving every pushed chunk
+#[derive(Default)]
+struct Taps(Vec<_Tap>);
+
+impl std::fmt::Debug for Taps {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Taps({})", self.0.len())
+    }
+}
+
+/// Number of samples pushed so far, used to wait for new data
+#[derive(Debug, Default)]
+struct Generation {
+    count: parking_lot::Mutex<u64>,
+    cond: parking_lot::Condvar,
+}
+
+/// Return a safe sample buffer size for an analyzer window
+///
+/// An analyzer looks at the last `length * downsample` samples; a smaller buffer panics.
+/// The recommendation leaves room for samples pushed while the analyzer is running.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let size = analyzer::recommended_buffer_size(512, 5);
+/// let buffer = analyzer::SampleBuffer::new(size, 8000);
+/// ```
+pub fn recommended_buffer_size(length: usize, downsample: usize) -> usize {
+    length * downsample * 2
+}
+
 /// A Sample Buffer
 ///
 /// The sample buffer is a synchronized ring-buffer.  During analyzation, it will
@@ -23,6 +58,8 @@ type _SampleBuf = sync::Arc<parking_lot::Mutex<collections::VecDeque<[Sample; 2]
 /// #     .fourier_length(16)
 /// #     .downsample(10)
 /// #     .rate(8000)
+/// #     .volume_smoothing(8.0)
+/// #     .history(8)
 /// #     .build();
 /// let buffer = analyzer::SampleBuffer::new(32000, 8000);
 ///
@@ -39,16 +76,32 @@ type _SampleBuf = sync::Arc<parking_lot::Mutex<collections::VecDeque<[Sample; 2]
 pub struct SampleBuffer {
     buf: _SampleBuf,
     rate: usize,
+    generation: sync::Arc<Generation>,
+    delay: _SampleBuf,
+    taps: sync::Arc<parking_lot::Mutex<Taps>>,
 }
 
 impl SampleBuffer {
     /// Create a new sample buffer given a size and a record rate
     pub fn new(size: usize, rate: usize) -> SampleBuffer {
+        SampleBuffer::with_delay(size, rate, 0)
+    }
+
+    /// Create a new sample buffer which delays pushed samples by `delay` samples
+    ///
+    /// Useful if the audio is captured with a lag relative to its source, for example with
+    /// a microphone far away from the speakers.  This trades latency for alignment: the
+    /// visuals will lag the captured audio by `delay` samples.
+    pub fn with_delay(size: usize, rate: usize, delay: usize) -> SampleBuffer {
         let buf = collections::VecDeque::from(vec![[0.0; 2]; size]);
+        let delay = collections::VecDeque::from(vec![[0.0; 2]; delay]);
 
         SampleBuffer {
             buf: sync::Arc::new(parking_lot::Mutex::new(buf)),
             rate,
+            generation: Default::default(),
+            delay: sync::Arc::new(parking_lot::Mutex::new(delay)),
+            taps: Default::default(),
         }
     }
 
@@ -57,8 +110,19 @@ impl SampleBuffer {
         self.rate
     }
 
+    /// Return the number of samples held by this buffer
+    pub fn size(&self) -> usize {
+        self.buf.lock().len()
+    }
+
+    /// Return the number of samples pushed samples are delayed by
+    pub fn delay(&self) -> usize {
+        self.delay.lock().len()
+    }
+
     /// Push a slice of interleaved samples to the buffer
     pub fn push(&self, new: &[[Sample; 2]]) {
+        let mut delay = self.delay.lock();
         let mut lock = self.buf.lock();
 
         #[cfg(debug_assertions)]
@@ -66,11 +130,97 @@ impl SampleBuffer {
 
         for sample in new.iter() {
             lock.pop_front().expect("Failed to pop sample!");
-            lock.push_back(*sample);
+            if delay.is_empty() {
+                lock.push_back(*sample);
+            } else {
+                delay.push_back(*sample);
+                lock.push_back(delay.pop_front().unwrap());
+            }
         }
 
         #[cfg(debug_assertions)]
         assert_eq!(debug_size, lock.len(), "Sample buffer size differs!");
+
+        // Count while still holding the buffer, so readers see the samples and the
+        // generation change together
+        *self.generation.count.lock() += new.len() as u64;
+        drop(lock);
+        drop(delay);
+
+        for tap in self.taps.lock().0.iter_mut() {
+            tap(new);
+        }
+
+        self.generation.cond.notify_all();
+    }
+
+    /// Register a callback which receives every chunk of samples pushed to this buffer
+    ///
+    /// The callback is called from the recorder thread with the raw (undelayed) samples.
+    /// Keep it fast to not stall the recorder.
+    pub fn add_tap<F>(&self, tap: F)
+    where
+        F: FnMut(&[[Sample; 2]]) + Send + 'static,
+    {
+        self.taps.lock().0.push(Box::new(tap));
+    }
+
+    /// Return the generation of this buffer
+    ///
+    /// The generation is the total number of samples pushed so far.
+    pub fn generation(&self) -> u64 {
+        *self.generation.count.lock()
+    }
+
+    /// Return the number of samples pushed since `generation`
+    ///
+    /// Lets an analyzer skip recomputation if not enough new data arrived:
+    ///
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let buffer = analyzer::SampleBuffer::new(32000, 8000);
+    /// let mut last = buffer.generation();
+    ///
+    /// // In analyzer
+    /// if buffer.samples_since(last) >= 128 {
+    ///     last = buffer.generation();
+    ///     // Recompute ...
+    /// }
+    /// ```
+    pub fn samples_since(&self, generation: u64) -> usize {
+        self.generation().saturating_sub(generation) as usize
+    }
+
+    /// Replace the contents of `out` with the whole buffer, oldest first
+    ///
+    /// Returns the generation the copy was taken at.  No samples can be pushed in between.
+    pub(crate) fn copy_all(&self, out: &mut Vec<[Sample; 2]>) -> u64 {
+        let lock = self.buf.lock();
+
+        out.clear();
+        out.extend(lock.iter());
+        self.generation()
+    }
+
+    /// Block until the generation advances past `generation`
+    ///
+    /// Returns the new generation or `None` if `timeout` elapsed first.
+    pub fn wait_newer(&self, generation: u64, timeout: time::Duration) -> Option<u64> {
+        let deadline = time::Instant::now() + timeout;
+        let mut count = self.generation.count.lock();
+
+        while *count <= generation {
+            if self
+                .generation
+                .cond
+                .wait_until(&mut count, deadline)
+                .timed_out()
+            {
+                return None;
+            }
+        }
+
+        Some(*count)
     }
 
     /// Lock the buffer and iterate over the last `size` samples (with downsampling)
@@ -86,27 +236,240 @@ impl SampleBuffer {
         }
     }
 
+    /// Return a view of a single channel of this buffer
+    ///
+    /// `channel` is `0` for left and `1` for right.  The view does not copy any samples, it
+    /// reads them from this buffer when iterated.  Pass it to
+    /// [`FourierAnalyzer::analyze_view`](struct.FourierAnalyzer.html#method.analyze_view) to
+    /// run separate pipelines for each channel.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let buffer = analyzer::SampleBuffer::new(16, 8000);
+    /// buffer.push(&[[1.0, -1.0]; 16]);
+    ///
+    /// let right = buffer.channel_view(1);
+    /// assert!(right.iter(16, 1).all(|s| s == -1.0));
+    /// ```
+    pub fn channel_view(&self, channel: usize) -> MonoView<'_> {
+        assert!(channel < 2, "Invalid channel {}!", channel);
+
+        MonoView {
+            buffer: self,
+            channel,
+        }
+    }
+
+    /// Number of samples making up the last `length` seconds
+    fn volume_window(&self, length: f32) -> usize {
+        assert!(
+            length > 0.0 && length.is_finite(),
+            "Volume length must be positive, not {}!",
+            length
+        );
+
+        self.rate
+            .checked_div((1.0 / length) as usize)
+            // Longer than a second
+            .unwrap_or((length * self.rate as f32) as usize)
+    }
+
     /// Calculate the RMS Volume over the last `length` seconds
     ///
-    /// Keep `length` short to avoid performance issues
+    /// Keep `length` short to avoid performance issues.  If `length` exceeds the buffer, the
+    /// whole buffer is used.
+    ///
+    /// *Note*: Older versions divided by the size of the whole buffer instead of the number of
+    /// samples in the window, so they reported volumes which were too low by a factor of
+    /// `sqrt(window / buffer size)`.  Thresholds and scaling factors tuned against those
+    /// values need to be adjusted.
+    ///
+    /// # Panics
+    /// Panics if `length` is not a positive number.
     pub fn volume(&self, length: f32) -> super::SignalStrength {
         use super::SignalStrength;
 
+        let window = self.volume_window(length);
         let lock = self.buf.lock();
         let len = lock.len();
-
-        let div = (1.0 / length) as usize;
+        let count = window.min(len);
 
         (lock
             .iter()
-            // Only look at the last tenth of a second
-            .skip(len - self.rate / div)
+            // Only look at the last `length` seconds
+            .skip(len - count)
             // RMS
             .map(|s| ((s[0] + s[1]) / 2.0).powi(2) as SignalStrength)
             .sum::<SignalStrength>()
-            / len as SignalStrength)
+            / count.max(1) as SignalStrength)
             .sqrt()
     }
+
+    /// Calculate the RMS Volume of the left and right channel over the last `length` seconds
+    ///
+    /// Uses the same window as [`volume`](#method.volume), but keeps the channels apart
+    /// instead of mixing them down first.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let buffer = analyzer::SampleBuffer::new(8000, 8000);
+    /// buffer.push(&[[0.5, 0.25]; 800]);
+    ///
+    /// let [left, right] = buffer.volume_stereo(0.1);
+    /// # assert!((left - 0.5).abs() < 1e-4 && (right - 0.25).abs() < 1e-4);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if `length` is not a positive number.
+    pub fn volume_stereo(&self, length: f32) -> [super::SignalStrength; 2] {
+        use super::SignalStrength;
+
+        let window = self.volume_window(length);
+        let lock = self.buf.lock();
+        let len = lock.len();
+        let count = window.min(len);
+
+        let mut sums = [0.0 as SignalStrength; 2];
+        for s in lock.iter().skip(len - count) {
+            for (sum, v) in sums.iter_mut().zip(s.iter()) {
+                *sum += v.powi(2) as SignalStrength;
+            }
+        }
+
+        sums.map(|sum| (sum / count.max(1) as SignalStrength).sqrt())
+    }
+
+    /// Calculate the balance between left and right over the last `length` seconds
+    ///
+    /// `-1.0` means only the left channel is audible, `1.0` only the right one and `0.0`
+    /// either both equally loud or silence.
+    ///
+    /// # Panics
+    /// Panics if `length` is not a positive number.
+    pub fn balance(&self, length: f32) -> f32 {
+        let [left, right] = self.volume_stereo(length);
+        if left + right == 0.0 {
+            0.0
+        } else {
+            (right - left) / (right + left)
+        }
+    }
+
+    /// Calculate the RMS Volume over the last `length` seconds relative to a reference level
+    ///
+    /// `reference_db` is the level in dBFS (RMS) which should map to `1.0`.  Quieter signals
+    /// yield smaller values, louder signals are clamped to `1.0`.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let buffer = analyzer::SampleBuffer::new(8000, 8000);
+    /// // Full volume for signals around -20 dBFS
+    /// let volume = buffer.volume_normalized(0.3, -20.0);
+    /// # assert_eq!(volume, 0.0);
+    /// ```
+    pub fn volume_normalized(&self, length: f32, reference_db: f32) -> super::SignalStrength {
+        let reference = 10.0f32.powf(reference_db / 20.0);
+
+        (self.volume(length) / reference).min(1.0)
+    }
+
+    /// Calculate the ratio of peak to RMS amplitude over the last `length` seconds
+    ///
+    /// Peaky, percussive audio has a high crest factor, while heavily compressed audio
+    /// approaches `1.0`.  A sine yields `√2`.  Silence yields `0.0`.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let buffer = analyzer::SampleBuffer::new(8000, 8000);
+    /// buffer.push(&[[0.5, 0.5]; 800]);
+    ///
+    /// let crest = buffer.crest_factor(0.1);
+    /// # assert_eq!(crest, 1.0);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if `length` is not a positive number.
+    pub fn crest_factor(&self, length: f32) -> super::SignalStrength {
+        use super::SignalStrength;
+
+        let window = self.volume_window(length);
+        let lock = self.buf.lock();
+        let len = lock.len();
+        if len == 0 {
+            return 0.0;
+        }
+        let count = window.min(len);
+
+        let (peak, sum) = lock
+            .iter()
+            .skip(len - count)
+            .map(|s| ((s[0] + s[1]) / 2.0) as SignalStrength)
+            .fold((0.0 as SignalStrength, 0.0), |(peak, sum), s| {
+                (peak.max(s.abs()), sum + s.powi(2))
+            });
+
+        let rms = (sum / count.max(1) as SignalStrength).sqrt();
+        if rms == 0.0 {
+            0.0
+        } else {
+            peak / rms
+        }
+    }
+
+    /// Calculate the RMS Volume of each channel over the whole buffer
+    ///
+    /// Useful during setup to find out which input channels actually carry a signal.  An
+    /// empty buffer yields silence on both channels.
+    pub fn channel_activity(&self) -> Vec<super::SignalStrength> {
+        use super::SignalStrength;
+
+        let lock = self.buf.lock();
+        if lock.is_empty() {
+            return vec![0.0; 2];
+        }
+        let len = lock.len() as SignalStrength;
+
+        let mut sums = [0.0; 2];
+        for s in lock.iter() {
+            for (sum, v) in sums.iter_mut().zip(s.iter()) {
+                *sum += v.powi(2) as SignalStrength;
+            }
+        }
+
+        sums.iter().map(|sum| (sum / len).sqrt()).collect()
+    }
+
+    /// Copy the last `out.len()` stereo pairs (with downsampling) for plotting as X/Y
+    ///
+    /// Pairs are `[left, right]`, oldest first, as needed for a goniometer or Lissajous
+    /// display.  If the buffer holds fewer pairs than requested, the oldest entries of `out`
+    /// are set to silence.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let buffer = analyzer::SampleBuffer::new(8000, 8000);
+    ///
+    /// let mut points = [[0.0; 2]; 256];
+    /// buffer.scatter(&mut points, 2);
+    /// ```
+    pub fn scatter(&self, out: &mut [[Sample; 2]], downsample: usize) {
+        assert!(downsample > 0, "Downsampling factor must be positive!");
+
+        let n = out.len().min(self.size() / downsample);
+        let (silence, recent) = out.split_at_mut(out.len() - n);
+
+        for s in silence.iter_mut() {
+            *s = [0.0; 2];
+        }
+        for (o, s) in recent.iter_mut().zip(self.iter(n, downsample)) {
+            *o = s;
+        }
+    }
 }
 
 pub struct SampleIterator<'a> {
@@ -125,6 +488,50 @@ impl Iterator for SampleIterator<'_> {
     }
 }
 
+/// A single channel of a sample buffer
+///
+/// Created by [`SampleBuffer::channel_view`](struct.SampleBuffer.html#method.channel_view).
+#[derive(Debug, Clone, Copy)]
+pub struct MonoView<'a> {
+    buffer: &'a SampleBuffer,
+    channel: usize,
+}
+
+impl<'a> MonoView<'a> {
+    /// Return the channel of this view, `0` for left and `1` for right
+    pub fn channel(&self) -> usize {
+        self.channel
+    }
+
+    /// Return the buffer this view reads from
+    pub fn buffer(&self) -> &'a SampleBuffer {
+        self.buffer
+    }
+
+    /// Lock the buffer and iterate over the last `size` samples of this channel
+    ///
+    /// Works like [`SampleBuffer::iter`](struct.SampleBuffer.html#method.iter).
+    pub fn iter(&self, size: usize, downsample: usize) -> MonoIterator<'a> {
+        MonoIterator {
+            samples: self.buffer.iter(size, downsample),
+            channel: self.channel,
+        }
+    }
+}
+
+pub struct MonoIterator<'a> {
+    samples: SampleIterator<'a>,
+    channel: usize,
+}
+
+impl Iterator for MonoIterator<'_> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.samples.next().map(|s| s[self.channel])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,4 +586,229 @@ mod tests {
             &[[4.0; 2], [8.0; 2], [12.0; 2], [16.0; 2], [20.0; 2], [24.0; 2], [28.0; 2],]
         );
     }
+
+    #[test]
+    fn test_scatter() {
+        let buf = SampleBuffer::new(16, 8000);
+        buf.push(
+            &(0..16)
+                .map(|i| [i as Sample, -i as Sample])
+                .collect::<Vec<_>>(),
+        );
+
+        let mut out = [[-1.0; 2]; 4];
+        buf.scatter(&mut out, 1);
+        assert_eq!(
+            out,
+            [[12.0, -12.0], [13.0, -13.0], [14.0, -14.0], [15.0, -15.0]]
+        );
+
+        buf.scatter(&mut out, 2);
+        assert_eq!(
+            out,
+            [[8.0, -8.0], [10.0, -10.0], [12.0, -12.0], [14.0, -14.0]]
+        );
+
+        // More pairs than the buffer holds
+        let mut out = [[-1.0; 2]; 10];
+        buf.scatter(&mut out, 2);
+        assert_eq!(out[..2], [[0.0; 2]; 2]);
+        assert_eq!(out[3], [2.0, -2.0]);
+        assert_eq!(out[9], [14.0, -14.0]);
+    }
+
+    #[test]
+    fn test_delay() {
+        let buf = SampleBuffer::with_delay(16, 8000, 4);
+
+        buf.push(
+            &(0..20)
+                .map(|i| [i as Sample, i as Sample])
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(
+            buf.iter(16, 1).collect::<Vec<_>>(),
+            (0..16)
+                .map(|i| [i as Sample, i as Sample])
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_tap() {
+        let buf = SampleBuffer::with_delay(16, 8000, 4);
+
+        let tapped = sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+        {
+            let tapped = tapped.clone();
+            buf.add_tap(move |chunk| tapped.lock().extend_from_slice(chunk));
+        }
+
+        buf.push(&[[1.0; 2], [2.0; 2]]);
+        buf.push(&[[3.0; 2]]);
+
+        assert_eq!(*tapped.lock(), [[1.0; 2], [2.0; 2], [3.0; 2]]);
+    }
+
+    #[test]
+    fn test_generation() {
+        let buf = SampleBuffer::new(16, 8000);
+        assert_eq!(buf.generation(), 0);
+
+        buf.push(&[[1.0; 2]; 8]);
+        buf.push(&[[1.0; 2]; 4]);
+        assert_eq!(buf.generation(), 12);
+
+        assert_eq!(buf.samples_since(0), 12);
+        assert_eq!(buf.samples_since(8), 4);
+        assert_eq!(buf.samples_since(12), 0);
+        assert_eq!(buf.samples_since(100), 0);
+
+        assert_eq!(buf.wait_newer(12, time::Duration::from_millis(10)), None);
+
+        let pusher = buf.clone();
+        let handle = std::thread::spawn(move || pusher.push(&[[1.0; 2]; 4]));
+        assert_eq!(buf.wait_newer(12, time::Duration::from_secs(5)), Some(16));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_volume_length() {
+        let buf = SampleBuffer::new(8000, 8000);
+        buf.push(&[[0.5; 2]; 8000]);
+
+        // Longer than a second and longer than the buffer
+        let volume = buf.volume(2.0);
+        assert!((volume - 0.5).abs() < 1e-4);
+        assert!((buf.volume(1.5) - volume).abs() < 1e-4);
+        assert!((buf.volume(0.1) - volume).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_volume_stereo() {
+        let buf = SampleBuffer::new(8000, 8000);
+        assert_eq!(buf.balance(0.1), 0.0);
+
+        buf.push(&[[0.0, 0.0]; 7200]);
+        buf.push(&[[0.2, -0.6], [-0.2, 0.6]].repeat(400));
+
+        let [left, right] = buf.volume_stereo(0.1);
+        assert!((left - 0.2).abs() < 1e-4, "{}", left);
+        assert!((right - 0.6).abs() < 1e-4, "{}", right);
+        assert!((buf.balance(0.1) - 0.5).abs() < 1e-4);
+
+        // The silence before counts for longer windows
+        let [left, _] = buf.volume_stereo(1.0);
+        assert!((left - 0.2 * 0.1f32.sqrt()).abs() < 1e-4, "{}", left);
+
+        buf.push(&[[0.3, 0.0]; 800]);
+        assert_eq!(buf.balance(0.1), -1.0);
+    }
+
+    #[test]
+    fn test_volume_buffer_size() {
+        // The volume of a constant signal is that constant, no matter the buffer size
+        for size in [800, 4000, 16000, 48000] {
+            let buf = SampleBuffer::new(size, 8000);
+            buf.push(&vec![[0.25; 2]; size]);
+
+            for length in [0.01, 0.1, 0.3] {
+                let volume = buf.volume(length);
+                assert!(
+                    (volume - 0.25).abs() < 1e-4,
+                    "Size {}, length {}: {}",
+                    size,
+                    length,
+                    volume
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Volume length must be positive")]
+    fn test_volume_invalid_length() {
+        SampleBuffer::new(8000, 8000).volume(0.0);
+    }
+
+    #[test]
+    fn test_crest_factor() {
+        let buf = SampleBuffer::new(8000, 8000);
+        assert_eq!(buf.crest_factor(0.5), 0.0);
+
+        // 100 Hz sine, a whole number of periods in the window
+        let sine = (0..8000)
+            .map(|i| {
+                let s = (i as f32 / 80.0 * 2.0 * std::f32::consts::PI).sin();
+                [s, s]
+            })
+            .collect::<Vec<_>>();
+        buf.push(&sine);
+        let crest = buf.crest_factor(0.5);
+        assert!((crest - std::f32::consts::SQRT_2).abs() < 1e-3, "{}", crest);
+
+        // 100 Hz square
+        let square = (0..8000)
+            .map(|i| if i % 80 < 40 { [0.5; 2] } else { [-0.5; 2] })
+            .collect::<Vec<_>>();
+        buf.push(&square);
+        let crest = buf.crest_factor(0.5);
+        assert!((crest - 1.0).abs() < 1e-3, "{}", crest);
+
+        // Same window as the volume, a single click is the peak and the whole RMS
+        buf.push(&[[0.0; 2]; 8000]);
+        buf.push(&[[1.0; 2]]);
+        buf.push(&[[0.0; 2]; 2500]);
+        let crest = buf.crest_factor(0.3);
+        assert!((crest * buf.volume(0.3) - 1.0).abs() < 1e-4, "{}", crest);
+
+        assert_eq!(SampleBuffer::new(0, 8000).crest_factor(0.5), 0.0);
+    }
+
+    #[test]
+    fn test_volume_normalized() {
+        let buf = SampleBuffer::new(800, 8000);
+        buf.push(&[[0.5; 2]; 800]);
+
+        let reference = 20.0 * 0.5f32.log10();
+        assert!((buf.volume_normalized(0.1, reference) - 1.0).abs() < 1e-3);
+        assert!((buf.volume_normalized(0.1, reference + 20.0) - 0.1).abs() < 1e-3);
+        assert_eq!(buf.volume_normalized(0.1, reference - 20.0), 1.0);
+    }
+
+    #[test]
+    fn test_channel_activity() {
+        let buf = SampleBuffer::new(32, 8000);
+
+        buf.push(&[[0.0, 0.5], [0.0, -0.5]].repeat(16));
+
+        let activity = buf.channel_activity();
+        assert_eq!(activity.len(), 2);
+        assert_eq!(activity[0], 0.0);
+        assert!((activity[1] - 0.5).abs() < 1e-6);
+
+        assert_eq!(SampleBuffer::new(0, 8000).channel_activity(), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_channel_view() {
+        let buf = SampleBuffer::new(32, 8000);
+        buf.push(
+            &(0..32)
+                .map(|i| [i as Sample, -i as Sample])
+                .collect::<Vec<_>>(),
+        );
+
+        let left = buf.channel_view(0);
+        assert_eq!(left.channel(), 0);
+        assert_eq!(
+            left.iter(16, 2).collect::<Vec<_>>(),
+            buf.iter(16, 2).map(|[l, _]| l).collect::<Vec<_>>(),
+        );
+        assert!(buf
+            .channel_view(1)
+            .iter(32, 1)
+            .eq((0..32).map(|i| -i as Sample)));
+    }
 }
@@ -0,0 +1,167 @@
+//! Silence Detection
+use crate::analyzer;
+
+/// Builder for SilenceDetector
+#[derive(Debug, Default)]
+pub struct SilenceBuilder {
+    /// Volume below which the input counts as silent
+    ///
+    /// Defaults to `0.01`, can also be set from config as `"audio.silence.threshold"`.
+    pub threshold: Option<analyzer::SignalStrength>,
+
+    /// Minimum length of a silence gap in seconds
+    ///
+    /// Shorter gaps, eg. breaks within a song, are ignored.  Defaults to `1.5`, can also be
+    /// set from config as `"audio.silence.duration"`.
+    pub duration: Option<f32>,
+}
+
+impl SilenceBuilder {
+    /// Create new SilenceBuilder
+    pub fn new() -> SilenceBuilder {
+        Default::default()
+    }
+
+    /// Set the silence threshold
+    pub fn threshold(&mut self, threshold: analyzer::SignalStrength) -> &mut SilenceBuilder {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// Set the minimum gap length in seconds
+    pub fn duration(&mut self, duration: f32) -> &mut SilenceBuilder {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Build the detector
+    pub fn build(&mut self) -> SilenceDetector {
+        SilenceDetector::from_builder(self)
+    }
+}
+
+/// A detector for silence gaps between tracks
+///
+/// Reports when the volume stayed below a threshold for longer than a minimum duration and
+/// fires a track boundary when the signal returns after such a gap.  Visualizers can use the
+/// boundary to reset beat state or gain for the next song.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// # let samples = analyzer::SampleBuffer::new(32000, 8000);
+/// # let time = 0.0;
+/// let mut silence = analyzer::SilenceBuilder::new()
+///     .threshold(0.01)
+///     .duration(1.5)
+///     .build();
+///
+/// if silence.update(samples.volume(0.1), time) {
+///     // A new track started
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SilenceDetector {
+    threshold: analyzer::SignalStrength,
+    duration: f32,
+
+    silent_since: Option<f32>,
+    silent: bool,
+    boundaries: usize,
+}
+
+impl SilenceDetector {
+    /// Create a SilenceDetector from a builder config
+    pub fn from_builder(build: &SilenceBuilder) -> SilenceDetector {
+        let threshold = build
+            .threshold
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.silence.threshold", 0.01));
+        let duration = build
+            .duration
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.silence.duration", 1.5));
+
+        log::debug!("SilenceDetector:");
+        log::debug!("    Threshold   = {:8}", threshold);
+        log::debug!("    Duration    = {:8}s", duration);
+
+        SilenceDetector {
+            threshold,
+            duration,
+
+            silent_since: None,
+            silent: false,
+            boundaries: 0,
+        }
+    }
+
+    /// Feed the current volume at `time` seconds
+    ///
+    /// Returns true on a track boundary, that is when the signal returns after a silence gap
+    /// of at least the configured duration.
+    pub fn update(&mut self, volume: analyzer::SignalStrength, time: f32) -> bool {
+        if volume < self.threshold {
+            let since = *self.silent_since.get_or_insert(time);
+            if !self.silent && time - since >= self.duration {
+                log::debug!("Silence since {}s", since);
+                self.silent = true;
+            }
+            return false;
+        }
+
+        self.silent_since = None;
+        if self.silent {
+            self.silent = false;
+            self.boundaries += 1;
+            log::debug!("Track boundary at {}s", time);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Return whether the input is in a silence gap right now
+    pub fn is_silent(&self) -> bool {
+        self.silent
+    }
+
+    /// Return the number of track boundaries seen so far
+    pub fn boundaries(&self) -> usize {
+        self.boundaries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boundary() {
+        let mut silence = SilenceBuilder::new().threshold(0.1).duration(1.0).build();
+
+        let mut events = Vec::new();
+        let mut feed = |silence: &mut SilenceDetector, volume, from: usize, to: usize| {
+            for i in from..to {
+                let time = i as f32 * 0.1;
+                if silence.update(volume, time) {
+                    events.push(time);
+                }
+            }
+        };
+
+        // Signal, a short break which is ignored, more signal
+        feed(&mut silence, 0.5, 0, 20);
+        feed(&mut silence, 0.0, 20, 25);
+        feed(&mut silence, 0.5, 25, 40);
+        assert!(!silence.is_silent());
+
+        // A real gap between tracks
+        feed(&mut silence, 0.0, 40, 70);
+        assert!(silence.is_silent());
+        feed(&mut silence, 0.5, 70, 100);
+        assert!(!silence.is_silent());
+
+        assert_eq!(silence.boundaries(), 1);
+        assert_eq!(events.len(), 1);
+        assert!((events[0] - 7.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,249 @@
+//! Spectrogram / Spectrum History
+use crate::analyzer;
+use crate::analyzer::spectrum::Storage;
+use crate::helpers;
+
+/// A fixed size history of spectra
+///
+/// Every pushed spectrum is merged into a row of `buckets` buckets.  Once `rows` spectra
+/// were pushed, the oldest row is evicted for each new one.  Rows are indexed from the
+/// oldest (`0`) to the newest (`len() - 1`), which is the order a scrolling display draws
+/// them in.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let spectrum = analyzer::Spectrum::new(vec![1.0; 256], 0.0, 4000.0);
+/// let mut spectrogram = analyzer::Spectrogram::new(100, 64);
+///
+/// spectrogram.push(&spectrum);
+///
+/// for row in spectrogram.iter() {
+///     assert_eq!(row.len(), 64);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Spectrogram {
+    scroll: helpers::ScrollBuffer<analyzer::SignalStrength>,
+
+    lowest: analyzer::Frequency,
+    highest: analyzer::Frequency,
+}
+
+impl Spectrogram {
+    /// Create a new spectrogram with `rows` rows of `buckets` buckets each
+    pub fn new(rows: usize, buckets: usize) -> Spectrogram {
+        assert!(rows > 0, "A spectrogram needs at least one row!");
+
+        Spectrogram {
+            scroll: helpers::ScrollBuffer::new(rows, buckets, 0.0),
+
+            lowest: 0.0,
+            highest: 1.0,
+        }
+    }
+
+    /// Push a new spectrum, evicting the oldest row if the spectrogram is full
+    ///
+    /// Will merge adjacent buckets to fit the spectrum into a row.  The span of the
+    /// spectrogram is taken from the latest spectrum.
+    pub fn push<S: Storage>(&mut self, spectrum: &analyzer::Spectrum<S>) {
+        let (lowest, highest) = (&mut self.lowest, &mut self.highest);
+        self.scroll.push_with(|slot| {
+            let row = spectrum.fill_buckets(slot);
+            *lowest = row.lowest();
+            *highest = row.highest();
+        });
+    }
+
+    /// Return the number of rows currently stored
+    pub fn len(&self) -> usize {
+        self.scroll.len()
+    }
+
+    /// Return whether no rows are stored yet
+    pub fn is_empty(&self) -> bool {
+        self.scroll.is_empty()
+    }
+
+    /// Return the maximum number of rows
+    pub fn rows(&self) -> usize {
+        self.scroll.rows()
+    }
+
+    /// Return the number of buckets per row
+    pub fn buckets(&self) -> usize {
+        self.scroll.width()
+    }
+
+    /// Return the frequency of the lowest bucket
+    #[inline]
+    pub fn lowest(&self) -> analyzer::Frequency {
+        self.lowest
+    }
+
+    /// Return the frequency of the highest bucket
+    #[inline]
+    pub fn highest(&self) -> analyzer::Frequency {
+        self.highest
+    }
+
+    /// Return row `i`, counting from the oldest row
+    pub fn row(&self, i: usize) -> analyzer::Spectrum<&[analyzer::SignalStrength]> {
+        analyzer::Spectrum::new(self.scroll.row(i), self.lowest, self.highest)
+    }
+
+    /// Iterate over all rows, starting with the oldest
+    pub fn iter(&self) -> impl Iterator<Item = analyzer::Spectrum<&[analyzer::SignalStrength]>> {
+        (0..self.len()).map(move |i| self.row(i))
+    }
+
+    /// Render this spectrogram into an RGBA8 buffer
+    ///
+    /// The buffer is `buckets()` pixels wide and `rows()` pixels high, one row per spectrum
+    /// starting with the oldest.  Rows which were not pushed yet are padded in front and
+    /// colored like silence, so the newest spectrum is always the last row.  `colormap` maps
+    /// each bucket to a pixel.
+    ///
+    /// The buffer can be uploaded directly, eg. with glium's
+    /// `RawImage2d::from_raw_rgba(buf, (buckets, rows))`.  As GL textures start at the
+    /// bottom, the newest row then ends up at the top.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let spectrum = analyzer::Spectrum::new(vec![0.5; 256], 0.0, 4000.0);
+    /// let mut spectrogram = analyzer::Spectrogram::new(100, 64);
+    /// spectrogram.push(&spectrum);
+    ///
+    /// let rgba = spectrogram.as_rgba(|v| {
+    ///     let l = (v.min(1.0) * 255.0) as u8;
+    ///     [l, l, l, 255]
+    /// });
+    /// assert_eq!(rgba.len(), 64 * 100 * 4);
+    /// ```
+    pub fn as_rgba<F>(&self, mut colormap: F) -> Vec<u8>
+    where
+        F: FnMut(analyzer::SignalStrength) -> [u8; 4],
+    {
+        let mut buf = Vec::with_capacity(self.rows() * self.buckets() * 4);
+
+        let silence = colormap(0.0);
+        for _ in 0..(self.rows() - self.len()) * self.buckets() {
+            buf.extend_from_slice(&silence);
+        }
+
+        for row in self.iter() {
+            for v in row.iter() {
+                buf.extend_from_slice(&colormap(*v));
+            }
+        }
+
+        buf
+    }
+
+    /// Write this spectrogram as a CSV matrix
+    ///
+    /// The header contains the frequency of each bucket, followed by one row per spectrum,
+    /// starting with the oldest.
+    pub fn write_csv<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let span = analyzer::Spectrum::new(vec![0.0; self.buckets()], self.lowest, self.highest);
+        let header = (0..self.buckets())
+            .map(|i| span.id_to_freq(i).to_string())
+            .collect::<Vec<_>>();
+        writeln!(writer, "{}", header.join(","))?;
+
+        for row in self.iter() {
+            let values = row.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+            writeln!(writer, "{}", values.join(","))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spectrum(v: f32) -> analyzer::Spectrum<Vec<f32>> {
+        analyzer::Spectrum::new(vec![v; 8], 100.0, 800.0)
+    }
+
+    #[test]
+    fn test_empty() {
+        let spectrogram = Spectrogram::new(4, 8);
+
+        assert!(spectrogram.is_empty());
+        assert_eq!(spectrogram.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_order() {
+        let mut spectrogram = Spectrogram::new(4, 8);
+
+        for i in 0..3 {
+            spectrogram.push(&spectrum(i as f32));
+        }
+        assert_eq!(spectrogram.len(), 3);
+        assert_eq!(
+            spectrogram.iter().map(|r| r[0]).collect::<Vec<_>>(),
+            [0.0, 1.0, 2.0]
+        );
+
+        // Overflow, oldest rows get evicted
+        for i in 3..10 {
+            spectrogram.push(&spectrum(i as f32));
+        }
+        assert_eq!(spectrogram.len(), 4);
+        assert_eq!(
+            spectrogram.iter().map(|r| r[0]).collect::<Vec<_>>(),
+            [6.0, 7.0, 8.0, 9.0]
+        );
+        assert_eq!(spectrogram.row(3).lowest(), 100.0);
+        assert_eq!(spectrogram.row(3).highest(), 800.0);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut spectrogram = Spectrogram::new(2, 4);
+        spectrogram.push(&spectrum(1.0));
+
+        assert_eq!(spectrogram.row(0).iter().sum::<f32>(), 8.0);
+    }
+
+    #[test]
+    fn test_as_rgba() {
+        let mut spectrogram = Spectrogram::new(4, 8);
+        spectrogram.push(&spectrum(1.0));
+        spectrogram.push(&spectrum(3.0));
+
+        let rgba = spectrogram.as_rgba(|v| [v as u8, 0, 0, 255]);
+        assert_eq!(rgba.len(), 8 * 4 * 4);
+
+        // Two padding rows, then the pushed ones
+        let pixel = |x: usize, y: usize| &rgba[(y * 8 + x) * 4..(y * 8 + x + 1) * 4];
+        assert_eq!(pixel(0, 0), [0, 0, 0, 255]);
+        assert_eq!(pixel(7, 1), [0, 0, 0, 255]);
+        assert_eq!(pixel(0, 2), [1, 0, 0, 255]);
+        assert_eq!(pixel(5, 3), [3, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut spectrogram = Spectrogram::new(4, 8);
+        for i in 0..6 {
+            spectrogram.push(&spectrum(i as f32));
+        }
+
+        let mut buf = Vec::new();
+        spectrogram.write_csv(&mut buf).unwrap();
+        let csv = String::from_utf8(buf).unwrap();
+
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "100,200,300,400,500,600,700,800");
+        assert_eq!(lines[1], "2,2,2,2,2,2,2,2");
+        assert_eq!(lines[4], "5,5,5,5,5,5,5,5");
+    }
+}
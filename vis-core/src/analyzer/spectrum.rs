@@ -6,6 +6,9 @@ pub type Frequency = f32;
 /// Type Alias for Signal Strengths
 pub type SignalStrength = f32;
 
+/// Lowest level in dB produced by [`Spectrum::map_db`](struct.Spectrum.html#method.map_db)
+pub const DB_FLOOR: SignalStrength = -120.0;
+
 /// Trait for types that can be used as storage for a spectrum
 pub trait Storage: std::ops::Deref<Target = [SignalStrength]> {}
 
@@ -55,12 +58,33 @@ impl<S: StorageMut> std::ops::IndexMut<Frequency> for Spectrum<S> {
 
 impl Default for Spectrum<Vec<SignalStrength>> {
     fn default() -> Self {
-        Spectrum {
-            buckets: vec![0.0],
-            width: 1.0,
-            lowest: 0.0,
-            highest: 0.0,
+        Spectrum::new(vec![0.0], 0.0, 0.0)
+    }
+}
+
+/// Interpolates each bucket; the span is taken from `other`.  If the number of buckets
+/// differs, `other` is returned unchanged.
+impl crate::helpers::anim::Lerp for Spectrum<Vec<SignalStrength>> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        if self.len() != other.len() {
+            return other.clone();
         }
+
+        let buckets = self
+            .iter()
+            .zip(other.iter())
+            .map(|(a, b)| a + (b - a) * t)
+            .collect();
+        Spectrum::new(buckets, other.lowest, other.highest)
+    }
+}
+
+/// Width of a bucket, `0` if there are fewer than two buckets
+fn bucket_width(len: usize, low: Frequency, high: Frequency) -> Frequency {
+    if len < 2 {
+        0.0
+    } else {
+        (high - low) / (len as Frequency - 1.0)
     }
 }
 
@@ -71,6 +95,10 @@ impl<S: Storage> Spectrum<S> {
     /// the frequency associated with the lowest bucket and the frequency associated
     /// with the highest bucket.
     ///
+    /// Spectra with a single bucket or no buckets at all are allowed.  If there is a single
+    /// bucket or `low` equals `high`, all frequencies map to the first bucket.  An empty
+    /// spectrum has no bucket to look up.
+    ///
     /// # Example
     /// ```
     /// # use vis_core::analyzer;
@@ -79,7 +107,7 @@ impl<S: Storage> Spectrum<S> {
     /// ```
     pub fn new(data: S, low: Frequency, high: Frequency) -> Spectrum<S> {
         Spectrum {
-            width: (high - low) / (data.len() as Frequency - 1.0),
+            width: bucket_width(data.len(), low, high),
             lowest: low,
             highest: high,
 
@@ -99,15 +127,30 @@ impl<S: Storage> Spectrum<S> {
         self.highest
     }
 
+    /// Return the distance between two buckets in Hz
+    ///
+    /// `0` if there are fewer than two buckets.
+    #[inline]
+    pub fn bin_width(&self) -> Frequency {
+        self.width
+    }
+
     /// Respan this spectrum.  Use with care!
     fn respan(&mut self, low: Frequency, high: Frequency) {
-        self.width = (high - low) / (self.buckets.len() as Frequency - 1.0);
+        self.width = bucket_width(self.buckets.len(), low, high);
         self.lowest = low;
         self.highest = high;
     }
 
     /// Return the index of the bucket associated with a frequency
+    ///
+    /// Panics if the spectrum is empty.
     pub fn freq_to_id(&self, f: Frequency) -> usize {
+        assert!(!self.buckets.is_empty(), "Spectrum is empty!");
+        if self.width == 0.0 {
+            return 0;
+        }
+
         let x = (f - self.lowest) / self.width;
 
         assert!(x >= 0.0);
@@ -133,6 +176,11 @@ impl<S: Storage> Spectrum<S> {
         self.buckets.len()
     }
 
+    /// Return whether this spectrum has no buckets
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
     /// Return a borrowed spectrum
     pub fn as_ref<'a>(&'a self) -> Spectrum<&'a [SignalStrength]> {
         Spectrum {
@@ -143,20 +191,136 @@ impl<S: Storage> Spectrum<S> {
         }
     }
 
-    /// Return the highest signal strengh in this spectrum
+    /// Return the highest signal strengh in this spectrum or `0` if it is empty
     pub fn max(&self) -> SignalStrength {
-        *self
-            .buckets
+        self.buckets
             .iter()
             .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap()
+            .cloned()
+            .unwrap_or(0.0)
     }
 
-    /// Return the average signal strengh in this spectrum
+    /// Return the index of the bucket with the highest signal strength
+    ///
+    /// NaN buckets are ignored.  If all buckets are NaN, `0` is returned.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let mut spectrum = analyzer::Spectrum::new(vec![0.0; 400], 220.0, 660.0);
+    /// spectrum[100] = 10.0;
+    ///
+    /// assert_eq!(spectrum.argmax(), 100);
+    /// assert_eq!(spectrum.argmax_freq(), spectrum.id_to_freq(100));
+    /// ```
+    pub fn argmax(&self) -> usize {
+        argmax(&self.buckets)
+    }
+
+    /// Return the frequency of the bucket with the highest signal strength
+    pub fn argmax_freq(&self) -> Frequency {
+        self.id_to_freq(self.argmax())
+    }
+
+    /// Return the index of the bucket with the highest signal strength between two frequencies
+    ///
+    /// The index refers to this spectrum, not to the range.
+    pub fn argmax_in_range(&self, low: Frequency, high: Frequency) -> usize {
+        let start = self.freq_to_id(low);
+        let end = self.freq_to_id(high);
+
+        start + argmax(&self.buckets[start..end + 1])
+    }
+
+    /// Return the frequency of the bucket with the highest signal strength between two frequencies
+    pub fn argmax_freq_in_range(&self, low: Frequency, high: Frequency) -> Frequency {
+        self.id_to_freq(self.argmax_in_range(low, high))
+    }
+
+    /// Return the average signal strengh in this spectrum or `0` if it is empty
     pub fn mean(&self) -> SignalStrength {
+        if self.is_empty() {
+            return 0.0;
+        }
+
         self.buckets.iter().sum::<SignalStrength>() / self.len() as f32
     }
 
+    /// Return the total signal strengh in this spectrum
+    pub fn sum(&self) -> SignalStrength {
+        self.buckets.iter().sum()
+    }
+
+    /// Return the ratio of the total signal strength between two frequency bands
+    ///
+    /// Returns infinity or NaN if the second band is silent.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let spectrum = analyzer::Spectrum::new(vec![1.0; 400], 0.0, 4000.0);
+    /// let bass_treble = spectrum.band_ratio(50.0, 250.0, 2000.0, 4000.0);
+    /// ```
+    pub fn band_ratio(
+        &self,
+        low1: Frequency,
+        high1: Frequency,
+        low2: Frequency,
+        high2: Frequency,
+    ) -> SignalStrength {
+        self.slice(low1, high1).sum() / self.slice(low2, high2).sum()
+    }
+
+    /// Return the average signal strength weighted by a function of frequency
+    ///
+    /// Computes `sum(v * weights(f)) / sum(weights(f))` over all buckets, or `0` if all
+    /// weights are zero.  With a weight of `1` inside a band and `0` outside, this equals
+    /// `slice(low, high).mean()`; smooth weights avoid jumps when energy crosses a band edge.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let spectrum = analyzer::Spectrum::new(vec![1.0; 400], 0.0, 4000.0);
+    ///
+    /// // Falls off smoothly above 100 Hz
+    /// let bass = spectrum.weighted_energy(|f| 1.0 / (1.0 + (f / 100.0).powi(4)));
+    /// # assert!((bass - 1.0).abs() < 1e-4);
+    /// ```
+    pub fn weighted_energy(&self, weights: impl Fn(Frequency) -> f32) -> SignalStrength {
+        let (energy, total) = self.buckets.iter().enumerate().fold(
+            (0.0 as SignalStrength, 0.0),
+            |(energy, total), (i, v)| {
+                let w = weights(self.id_to_freq(i));
+                (energy + v * w, total + w)
+            },
+        );
+
+        if total == 0.0 {
+            0.0
+        } else {
+            energy / total
+        }
+    }
+
+    /// Write this spectrum as CSV with `frequency,magnitude` rows
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let spectrum = analyzer::Spectrum::new(vec![0.0; 400], 220.0, 660.0);
+    ///
+    /// let mut file = std::io::sink(); // eg. std::fs::File::create("spectrum.csv").unwrap()
+    /// spectrum.write_csv(&mut file).unwrap();
+    /// ```
+    pub fn write_csv<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writeln!(writer, "frequency,magnitude")?;
+        for (i, v) in self.buckets.iter().enumerate() {
+            writeln!(writer, "{},{}", self.id_to_freq(i), v)?;
+        }
+
+        Ok(())
+    }
+
     /// Return a spectrum with the buckets between the specified frequencies
     ///
     /// Requires **no** allocation!  Please note that the returned spectrum might be slightly
@@ -204,13 +368,111 @@ impl<S: Storage> Spectrum<S> {
             buf[i] = 0.0;
         }
 
-        for (i, v) in self.buckets.iter().enumerate() {
-            let bucket = i * buf.len() / self.buckets.len();
-            buf[bucket] += v;
+        if !buf.is_empty() {
+            for (i, v) in self.buckets.iter().enumerate() {
+                let bucket = i * buf.len() / self.buckets.len();
+                buf[bucket] += v;
+            }
         }
 
         Spectrum {
-            width: (self.highest - self.lowest) / (buf.len() as f32 - 1.0),
+            width: bucket_width(buf.len(), self.lowest, self.highest),
+            lowest: self.lowest,
+            highest: self.highest,
+
+            buckets: buf,
+        }
+    }
+
+    /// Fill a given buffer with data from this spectrum, using a logarithmic frequency axis
+    ///
+    /// Like [`fill_buckets`](#method.fill_buckets), but bucket `j` of `buf` collects the
+    /// energy between `lowest * r^j` and `lowest * r^(j + 1)` with `r = (highest /
+    /// lowest)^(1 / buf.len())`.  Low frequencies thus get many more buckets than high ones,
+    /// which matches how we hear.  The total energy is preserved.
+    ///
+    /// *Note*: The returned spectrum is still linearly indexed, only its data follows a log
+    /// axis.  `lowest()` and `highest()` are kept, but [`freq_to_id`](#method.freq_to_id) and
+    /// [`id_to_freq`](#method.id_to_freq) do **not** match the layout.  The frequency of
+    /// bucket `j` starts at `lowest * (highest / lowest).powf(j / len)`.
+    ///
+    /// Panics if `lowest()` is not positive.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let spectrum = analyzer::Spectrum::new(vec![1.0; 400], 20.0, 20000.0);
+    /// let bars = spectrum.fill_buckets_log(vec![0.0; 10]);
+    /// # assert_eq!(bars.len(), 10);
+    ///
+    /// // The lowest bar only covers a few buckets, the highest a lot of them
+    /// assert!(bars[0] < bars[9]);
+    /// ```
+    pub fn fill_buckets_log<S2: StorageMut>(&self, mut buf: S2) -> Spectrum<S2> {
+        assert!(
+            self.lowest > 0.0,
+            "Logarithmic axis needs a positive lowest frequency!"
+        );
+
+        for i in 0..buf.len() {
+            buf[i] = 0.0;
+        }
+
+        if !buf.is_empty() {
+            let range = (self.highest / self.lowest).ln();
+            for (i, v) in self.buckets.iter().enumerate() {
+                let bucket = if range > 0.0 {
+                    let x = (self.id_to_freq(i) / self.lowest).ln() / range;
+                    ((x * buf.len() as Frequency) as usize).min(buf.len() - 1)
+                } else {
+                    0
+                };
+                buf[bucket] += v;
+            }
+        }
+
+        Spectrum {
+            width: bucket_width(buf.len(), self.lowest, self.highest),
+            lowest: self.lowest,
+            highest: self.highest,
+
+            buckets: buf,
+        }
+    }
+
+    /// Fill a given buffer with the maxima of adjacent buckets from this spectrum
+    ///
+    /// Like [`fill_buckets`](#method.fill_buckets), but keeps the strongest bucket of each
+    /// group instead of summing them.  This preserves peaks, which often looks better for bar
+    /// displays.  Buckets of `buf` which no bucket maps to are set to `0`.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let spectrum = analyzer::Spectrum::new(vec![0.0; 400], 220.0, 660.0);
+    /// let bars = spectrum.fill_buckets_max(vec![0.0; 20]);
+    /// # assert_eq!(bars.len(), 20);
+    /// ```
+    pub fn fill_buckets_max<S2: StorageMut>(&self, mut buf: S2) -> Spectrum<S2> {
+        for i in 0..buf.len() {
+            buf[i] = SignalStrength::NEG_INFINITY;
+        }
+
+        if !buf.is_empty() {
+            for (i, v) in self.buckets.iter().enumerate() {
+                let bucket = i * buf.len() / self.buckets.len();
+                buf[bucket] = buf[bucket].max(*v);
+            }
+        }
+
+        for i in 0..buf.len() {
+            if buf[i] == SignalStrength::NEG_INFINITY {
+                buf[i] = 0.0;
+            }
+        }
+
+        Spectrum {
+            width: bucket_width(buf.len(), self.lowest, self.highest),
             lowest: self.lowest,
             highest: self.highest,
 
@@ -250,6 +512,151 @@ impl<S: Storage> Spectrum<S> {
         other
     }
 
+    /// Apply a function to each bucket and write the results into another spectrum
+    ///
+    /// `out` must have the same number of buckets as this spectrum and will be respanned
+    /// to match it.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let spectrum = analyzer::Spectrum::new(vec![4.0; 20], 220.0, 660.0);
+    /// let mut out = analyzer::Spectrum::new(vec![0.0; 20], 0.0, 1.0);
+    ///
+    /// spectrum.map_into(&mut out, |v| v.sqrt());
+    ///
+    /// assert_eq!(out[0], 2.0);
+    /// assert_eq!(out.lowest(), 220.0);
+    /// ```
+    pub fn map_into<'a, S2: StorageMut, F: Fn(SignalStrength) -> SignalStrength>(
+        &self,
+        out: &'a mut Spectrum<S2>,
+        f: F,
+    ) -> &'a mut Spectrum<S2> {
+        assert_eq!(
+            self.buckets.len(),
+            out.buckets.len(),
+            "Spectrum sizes differ!"
+        );
+
+        for (o, v) in out.buckets.iter_mut().zip(self.buckets.iter()) {
+            *o = f(*v);
+        }
+
+        out.respan(self.lowest, self.highest);
+
+        out
+    }
+
+    /// Convert a power spectrum into an amplitude spectrum
+    ///
+    /// Writes the square root of each bucket into `out`, which must have the same number of
+    /// buckets.  [`FourierAnalyzer`](struct.FourierAnalyzer.html) produces power spectra.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let power = analyzer::Spectrum::new(vec![4.0; 20], 220.0, 660.0);
+    /// let mut amplitude = analyzer::Spectrum::new(vec![0.0; 20], 0.0, 1.0);
+    ///
+    /// power.sqrt_into(&mut amplitude);
+    /// # assert_eq!(amplitude[0], 2.0);
+    /// ```
+    pub fn sqrt_into<'a, S2: StorageMut>(&self, out: &'a mut Spectrum<S2>) -> &'a mut Spectrum<S2> {
+        self.map_into(out, |v| v.sqrt())
+    }
+
+    /// Convert an amplitude spectrum into a power spectrum
+    ///
+    /// Writes the square of each bucket into `out`, which must have the same number of
+    /// buckets.
+    pub fn square_into<'a, S2: StorageMut>(
+        &self,
+        out: &'a mut Spectrum<S2>,
+    ) -> &'a mut Spectrum<S2> {
+        self.map_into(out, |v| v * v)
+    }
+
+    /// Compress the dynamic range of this spectrum and write it into `out`
+    ///
+    /// Works per bucket in the log magnitude domain: Buckets above `threshold` have their
+    /// distance to it (in dB) divided by `ratio`, buckets below are left unchanged.  A `ratio`
+    /// above `1` compresses loud buckets so quiet passages stay visible, a `ratio` below `1`
+    /// expands them.  `out` must have the same number of buckets.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let spectrum = analyzer::Spectrum::new(vec![0.01, 100.0], 220.0, 660.0);
+    /// let mut out = analyzer::Spectrum::new(vec![0.0; 2], 0.0, 1.0);
+    ///
+    /// spectrum.compress_into(&mut out, 1.0, 2.0);
+    /// assert_eq!(out[0], 0.01);
+    /// assert_eq!(out[1], 10.0);
+    /// ```
+    pub fn compress_into<'a, S2: StorageMut>(
+        &self,
+        out: &'a mut Spectrum<S2>,
+        threshold: SignalStrength,
+        ratio: SignalStrength,
+    ) -> &'a mut Spectrum<S2> {
+        assert!(threshold > 0.0, "Threshold must be positive!");
+        assert!(ratio > 0.0, "Ratio must be positive!");
+
+        self.map_into(out, |v| {
+            if v > threshold {
+                threshold * (v / threshold).powf(1.0 / ratio)
+            } else {
+                v
+            }
+        })
+    }
+
+    /// Scale this spectrum so its buckets sum up to `target_total` and write it into `out`
+    ///
+    /// Unlike normalizing to the maximum, this preserves the total energy, which makes spectra
+    /// of different loudness comparable.  The ratios between buckets are kept.  `out` must
+    /// have the same number of buckets.  A silent spectrum can't be scaled and stays silent.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let spectrum = analyzer::Spectrum::new(vec![1.0, 3.0], 220.0, 660.0);
+    /// let mut out = analyzer::Spectrum::new(vec![0.0; 2], 0.0, 1.0);
+    ///
+    /// spectrum.normalize_energy_into(&mut out, 1.0);
+    /// assert_eq!(out[1], 0.75);
+    /// ```
+    pub fn normalize_energy_into<'a, S2: StorageMut>(
+        &self,
+        out: &'a mut Spectrum<S2>,
+        target_total: SignalStrength,
+    ) -> &'a mut Spectrum<S2> {
+        let sum = self.sum();
+        let scale = if sum == 0.0 { 0.0 } else { target_total / sum };
+
+        self.map_into(out, |v| v * scale)
+    }
+
+    /// Allocate a copy of this spectrum converted to dB
+    ///
+    /// See [`map_db`](#method.map_db).
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let power = analyzer::Spectrum::new(vec![0.0, 1.0, 100.0], 220.0, 660.0);
+    ///
+    /// let db = power.to_db(1.0);
+    /// assert_eq!(db[0], analyzer::spectrum::DB_FLOOR);
+    /// assert_eq!(db[2], 20.0);
+    /// ```
+    pub fn to_db(&self, reference: SignalStrength) -> Spectrum<Vec<SignalStrength>> {
+        let mut db = Spectrum::new(self.buckets.to_vec(), self.lowest, self.highest);
+        db.map_db(reference);
+        db
+    }
+
     /// Find all maxima in this spectrum and allocate a buffer containing them
     pub fn find_maxima_alloc(&self) -> Vec<(f32, f32)> {
         let derivative = self
@@ -334,6 +741,24 @@ impl<S: Storage> Spectrum<S> {
 
         &buffer[..num]
     }
+
+    /// Return a view of this spectrum with the buckets ordered from high to low frequencies
+    ///
+    /// Useful for displays or LED strips which are wired in reverse.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::analyzer;
+    /// let spectrum = analyzer::Spectrum::new(vec![0.0; 400], 220.0, 660.0);
+    /// let reversed = spectrum.reversed_view();
+    ///
+    /// assert_eq!(reversed.id_to_freq(0), 660.0);
+    /// ```
+    pub fn reversed_view<'a>(&'a self) -> ReversedView<'a> {
+        ReversedView {
+            spectrum: self.as_ref(),
+        }
+    }
 }
 
 impl<S: StorageMut> Spectrum<S> {
@@ -342,6 +767,30 @@ impl<S: StorageMut> Spectrum<S> {
         self.buckets.iter_mut()
     }
 
+    /// Convert this power spectrum to dB in place
+    ///
+    /// Each bucket becomes `10 * log10(bucket / reference)`, so a bucket equal to `reference`
+    /// maps to `0 dB`.  Levels below [`DB_FLOOR`](constant.DB_FLOOR.html), including silent
+    /// buckets, are clamped to it.
+    pub fn map_db(&mut self, reference: SignalStrength) {
+        self.map_db_floor(reference, DB_FLOOR);
+    }
+
+    /// Convert this power spectrum to dB in place, clamping at `floor` dB
+    ///
+    /// Like [`map_db`](#method.map_db) with a custom lowest level.
+    pub fn map_db_floor(&mut self, reference: SignalStrength, floor: SignalStrength) {
+        assert!(reference > 0.0, "Reference must be positive!");
+
+        for v in self.buckets.iter_mut() {
+            *v = if *v > 0.0 {
+                (10.0 * (*v / reference).log10()).max(floor)
+            } else {
+                floor
+            };
+        }
+    }
+
     /// Fill this spectrum with values from another one
     ///
     /// Will merge adjacent buckets to fit data into our buffer.
@@ -352,19 +801,357 @@ impl<S: StorageMut> Spectrum<S> {
     }
 }
 
+/// A view of a spectrum with the buckets ordered from high to low frequencies
+///
+/// Created by [`Spectrum::reversed_view`](struct.Spectrum.html#method.reversed_view).
+/// Bucket `0` is the highest frequency of the underlying spectrum.
+#[derive(Debug, Clone)]
+pub struct ReversedView<'a> {
+    spectrum: Spectrum<&'a [SignalStrength]>,
+}
+
+impl<'a> std::ops::Index<usize> for ReversedView<'a> {
+    type Output = SignalStrength;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.spectrum[self.flip(index)]
+    }
+}
+
+impl<'a> ReversedView<'a> {
+    #[inline]
+    fn flip(&self, i: usize) -> usize {
+        assert!(i < self.len());
+
+        self.len() - 1 - i
+    }
+
+    /// Return the number of buckets in this view
+    pub fn len(&self) -> usize {
+        self.spectrum.len()
+    }
+
+    /// Return whether this view has no buckets
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the index of the bucket associated with a frequency
+    pub fn freq_to_id(&self, f: Frequency) -> usize {
+        self.flip(self.spectrum.freq_to_id(f))
+    }
+
+    /// Return the frequency associated with a bucket
+    pub fn id_to_freq(&self, i: usize) -> Frequency {
+        self.spectrum.id_to_freq(self.flip(i))
+    }
+
+    /// Iterate over the buckets, starting with the highest frequency
+    pub fn iter(&self) -> std::iter::Rev<std::slice::Iter<'a, SignalStrength>> {
+        self.spectrum.buckets.iter().rev()
+    }
+
+    /// Return the underlying spectrum in its original order
+    pub fn spectrum(&self) -> &Spectrum<&'a [SignalStrength]> {
+        &self.spectrum
+    }
+}
+
+/// A spectrum of complex bins, carrying phase alongside magnitude
+///
+/// Parallels [`Spectrum`](struct.Spectrum.html) for visualizers which need the phase, eg. for
+/// reassignment.  Fill it with
+/// [`FourierAnalyzer::fill_complex`](struct.FourierAnalyzer.html#method.fill_complex) and
+/// convert it into a normal spectrum with [`power_into`](#method.power_into) to continue with
+/// the usual flow.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// use rustfft::num_complex::Complex;
+///
+/// let complex = analyzer::ComplexSpectrum::new(vec![Complex::new(3.0, 4.0); 20], 220.0, 660.0);
+/// let mut magnitude = analyzer::Spectrum::new(vec![0.0; 20], 0.0, 1.0);
+///
+/// complex.magnitude_into(&mut magnitude);
+/// assert_eq!(magnitude[0], 5.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ComplexSpectrum {
+    buckets: Vec<rustfft::num_complex::Complex<SignalStrength>>,
+    width: Frequency,
+    lowest: Frequency,
+    highest: Frequency,
+}
+
+impl std::ops::Index<usize> for ComplexSpectrum {
+    type Output = rustfft::num_complex::Complex<SignalStrength>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.buckets[index]
+    }
+}
+
+impl Default for ComplexSpectrum {
+    fn default() -> Self {
+        ComplexSpectrum::new(Vec::new(), 0.0, 0.0)
+    }
+}
+
+impl ComplexSpectrum {
+    /// Create a new complex spectrum
+    ///
+    /// Takes the bins and the frequencies of the lowest and highest bucket, like
+    /// [`Spectrum::new`](struct.Spectrum.html#method.new).
+    pub fn new(
+        data: Vec<rustfft::num_complex::Complex<SignalStrength>>,
+        low: Frequency,
+        high: Frequency,
+    ) -> ComplexSpectrum {
+        ComplexSpectrum {
+            width: bucket_width(data.len(), low, high),
+            lowest: low,
+            highest: high,
+
+            buckets: data,
+        }
+    }
+
+    /// Replace the bins and span of this spectrum, reusing its buffer
+    pub(crate) fn assign(
+        &mut self,
+        data: &[rustfft::num_complex::Complex<SignalStrength>],
+        low: Frequency,
+        high: Frequency,
+    ) {
+        self.buckets.clear();
+        self.buckets.extend_from_slice(data);
+        self.width = bucket_width(data.len(), low, high);
+        self.lowest = low;
+        self.highest = high;
+    }
+
+    /// Return the frequency of the lowest bucket
+    #[inline]
+    pub fn lowest(&self) -> Frequency {
+        self.lowest
+    }
+
+    /// Return the frequency of the highest bucket
+    #[inline]
+    pub fn highest(&self) -> Frequency {
+        self.highest
+    }
+
+    /// Return the frequency associated with a bucket
+    pub fn id_to_freq(&self, i: usize) -> Frequency {
+        assert!(i < self.buckets.len());
+
+        i as Frequency * self.width + self.lowest
+    }
+
+    /// Iterate over the bins of this spectrum
+    pub fn iter(&self) -> std::slice::Iter<'_, rustfft::num_complex::Complex<SignalStrength>> {
+        self.buckets.iter()
+    }
+
+    /// Return the number of buckets in this spectrum
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Return whether this spectrum has no buckets
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Return the phase of a bucket in radians, in `[-pi, pi]`
+    pub fn phase(&self, i: usize) -> SignalStrength {
+        self.buckets[i].arg()
+    }
+
+    /// Apply a function to each bin and write the results into a spectrum
+    ///
+    /// `out` must have the same number of buckets and will be respanned to match.
+    pub fn map_into<
+        'a,
+        S: StorageMut,
+        F: Fn(rustfft::num_complex::Complex<SignalStrength>) -> SignalStrength,
+    >(
+        &self,
+        out: &'a mut Spectrum<S>,
+        f: F,
+    ) -> &'a mut Spectrum<S> {
+        assert_eq!(
+            self.buckets.len(),
+            out.buckets.len(),
+            "Spectrum sizes differ!"
+        );
+
+        for (o, v) in out.buckets.iter_mut().zip(self.buckets.iter()) {
+            *o = f(*v);
+        }
+
+        out.respan(self.lowest, self.highest);
+
+        out
+    }
+
+    /// Write the power (squared magnitude) of each bin into `out`
+    ///
+    /// This is the spectrum [`FourierAnalyzer::analyze`](struct.FourierAnalyzer.html#method.analyze)
+    /// returns.
+    pub fn power_into<'a, S: StorageMut>(&self, out: &'a mut Spectrum<S>) -> &'a mut Spectrum<S> {
+        self.map_into(out, |c| c.norm_sqr())
+    }
+
+    /// Write the magnitude of each bin into `out`
+    pub fn magnitude_into<'a, S: StorageMut>(
+        &self,
+        out: &'a mut Spectrum<S>,
+    ) -> &'a mut Spectrum<S> {
+        self.map_into(out, |c| c.norm())
+    }
+
+    /// Write the phase of each bin into `out`
+    pub fn phase_into<'a, S: StorageMut>(&self, out: &'a mut Spectrum<S>) -> &'a mut Spectrum<S> {
+        self.map_into(out, |c| c.arg())
+    }
+}
+
+/// Index of the biggest non-NaN value
+fn argmax(buckets: &[SignalStrength]) -> usize {
+    buckets
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| !v.is_nan())
+        .fold(
+            None,
+            |max: Option<(usize, SignalStrength)>, (i, v)| match max {
+                Some((_, m)) if m >= *v => max,
+                _ => Some((i, *v)),
+            },
+        )
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+/// Error for spectra which can't be combined
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpectrumMismatch {
+    /// No spectra were given
+    Empty,
+    /// Spectrum `index` has a different number of buckets than the first one
+    Length {
+        index: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// Spectrum `index` spans different frequencies than the first one
+    Span {
+        index: usize,
+        expected: (Frequency, Frequency),
+        found: (Frequency, Frequency),
+    },
+    /// The output buffer has a different number of buckets than the input spectra
+    Output { expected: usize, found: usize },
+}
+
+impl std::fmt::Display for SpectrumMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SpectrumMismatch::Empty => write!(f, "No spectra given"),
+            SpectrumMismatch::Length {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Spectrum {} has {} buckets but spectrum 0 has {}",
+                index, found, expected
+            ),
+            SpectrumMismatch::Span {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Spectrum {} spans {:?} Hz but spectrum 0 spans {:?} Hz",
+                index, found, expected
+            ),
+            SpectrumMismatch::Output { expected, found } => write!(
+                f,
+                "Output has {} buckets but the spectra have {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpectrumMismatch {}
+
 /// Compute the average of multiple spectra
+///
+/// # Panics
+/// Panics if no spectra are given or if the spectra or the output differ in their number of
+/// buckets or their span, eg. because left and right channel were analyzed with different
+/// settings.  Use [`try_average_spectrum`](fn.try_average_spectrum.html) to handle this
+/// instead.
 pub fn average_spectrum<'a, S: Storage, SMut: StorageMut>(
     out: &'a mut Spectrum<SMut>,
     spectra: &[Spectrum<S>],
 ) -> &'a Spectrum<SMut> {
-    let buffer = &mut out.buckets;
+    try_average_spectrum(out, spectra).unwrap_or_else(|e| panic!("Can't average spectra: {}!", e))
+}
 
-    let num = spectra.len() as SignalStrength;
-    debug_assert!(num > 0.0);
+/// Compute the average of multiple spectra, failing if they don't match
+///
+/// All spectra and the output need the same number of buckets and all spectra need the same
+/// span.  `out` is left untouched on error.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// let left = analyzer::Spectrum::new(vec![1.0; 4], 0.0, 100.0);
+/// let right = analyzer::Spectrum::new(vec![1.0; 8], 0.0, 100.0);
+///
+/// let mut out = analyzer::Spectrum::new(vec![0.0; 4], 0.0, 100.0);
+/// assert!(analyzer::try_average_spectrum(&mut out, &[left, right]).is_err());
+/// ```
+pub fn try_average_spectrum<'a, S: Storage, SMut: StorageMut>(
+    out: &'a mut Spectrum<SMut>,
+    spectra: &[Spectrum<S>],
+) -> Result<&'a Spectrum<SMut>, SpectrumMismatch> {
+    let first = spectra.first().ok_or(SpectrumMismatch::Empty)?;
+    let buckets = first.len();
+    let lowest = first.lowest;
+    let highest = first.highest;
+
+    for (index, s) in spectra.iter().enumerate().skip(1) {
+        if s.len() != buckets {
+            return Err(SpectrumMismatch::Length {
+                index,
+                expected: buckets,
+                found: s.len(),
+            });
+        }
+        if s.lowest != lowest || s.highest != highest {
+            return Err(SpectrumMismatch::Span {
+                index,
+                expected: (lowest, highest),
+                found: (s.lowest, s.highest),
+            });
+        }
+    }
+    if out.len() != buckets {
+        return Err(SpectrumMismatch::Output {
+            expected: buckets,
+            found: out.len(),
+        });
+    }
 
-    let buckets = buffer.len();
-    let lowest = spectra[0].lowest;
-    let highest = spectra[0].highest;
+    let num = spectra.len() as SignalStrength;
+    let buffer = &mut out.buckets;
 
     // Clear output
     for b in buffer.iter_mut() {
@@ -372,10 +1159,6 @@ pub fn average_spectrum<'a, S: Storage, SMut: StorageMut>(
     }
 
     for s in spectra.iter() {
-        debug_assert_eq!(s.len(), buckets);
-        debug_assert_eq!(s.lowest, lowest);
-        debug_assert_eq!(s.highest, highest);
-
         for (b, x) in buffer.iter_mut().zip(s.buckets.iter()) {
             *b += x;
         }
@@ -387,14 +1170,25 @@ pub fn average_spectrum<'a, S: Storage, SMut: StorageMut>(
 
     out.respan(lowest, highest);
 
-    out
+    Ok(out)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rustfft::num_complex::Complex;
 
     fn check_integrity<S: Storage>(s: &Spectrum<S>) {
+        // Single buckets keep the width of the spectrum they were sliced from
+        assert!(s.width.is_finite());
+        if s.buckets.len() < 2 {
+            return;
+        }
+        if s.width == 0.0 {
+            assert_eq!(s.lowest, s.highest);
+            return;
+        }
+
         assert_eq!(
             ((s.highest - s.lowest) / s.width).round() as usize,
             s.buckets.len() - 1
@@ -408,6 +1202,60 @@ mod tests {
         check_integrity(&def);
     }
 
+    #[test]
+    fn test_bin_width() {
+        do_tests(|n, l, h, _, _, spectrum| {
+            assert_eq!(spectrum.bin_width(), (h - l) / (n - 1) as f32);
+            assert_eq!(
+                spectrum
+                    .slice(spectrum.lowest(), spectrum.highest())
+                    .bin_width(),
+                spectrum.bin_width()
+            );
+        });
+    }
+
+    #[test]
+    fn test_degenerate() {
+        let empty = Spectrum::new(vec![], 100.0, 200.0);
+        check_integrity(&empty);
+        assert!(empty.is_empty());
+        assert_eq!(empty.mean(), 0.0);
+        assert_eq!(empty.max(), 0.0);
+        assert_eq!(empty.sum(), 0.0);
+        assert!(empty.fill_buckets_alloc(4).iter().all(|v| *v == 0.0));
+
+        let single = Spectrum::new(vec![5.0], 440.0, 440.0);
+        check_integrity(&single);
+        assert_eq!(single.len(), 1);
+        assert_eq!(single.freq_to_id(440.0), 0);
+        assert_eq!(single.freq_to_id(1000.0), 0);
+        assert_eq!(single.id_to_freq(0), 440.0);
+        assert_eq!(single[440.0], 5.0);
+        assert_eq!(single.mean(), 5.0);
+        assert_eq!(single.max(), 5.0);
+        assert_eq!(single.slice(400.0, 500.0).len(), 1);
+
+        let filled = single.fill_buckets_alloc(4);
+        check_integrity(&filled);
+        assert_eq!(filled.sum(), 5.0);
+
+        let mut out = Spectrum::new(vec![0.0], 0.0, 1.0);
+        single.map_into(&mut out, |v| v * 2.0);
+        check_integrity(&out);
+        assert_eq!(out[0], 10.0);
+
+        let downscaled = Spectrum::new(vec![1.0; 10], 0.0, 100.0).fill_buckets_alloc(1);
+        check_integrity(&downscaled);
+        assert_eq!(downscaled[0], 10.0);
+    }
+
+    #[test]
+    fn test_empty_lookup() {
+        let empty = Spectrum::new(vec![], 100.0, 200.0);
+        assert!(std::panic::catch_unwind(|| empty.freq_to_id(150.0)).is_err());
+    }
+
     fn do_tests<F: FnMut(usize, f32, f32, f32, f32, Spectrum<Vec<f32>>)>(mut f: F) {
         for n in [100, 1000, 512, 1337].iter().cloned() {
             for (l, h, low, high) in [
@@ -571,6 +1419,54 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_fill_log() {
+        let mut buf = Some(vec![50.0; 20]);
+        do_tests(|_, l, _, _, _, spectrum| {
+            if l == 0.0 {
+                return;
+            }
+
+            let buckets = spectrum.fill_buckets_log(buf.take().unwrap());
+            check_integrity(&buckets);
+
+            let spec_sum = spectrum.iter().sum::<f32>();
+            let bucket_sum = buckets.iter().sum::<f32>();
+            assert!((spec_sum - bucket_sum).abs() <= spec_sum * 1e-6);
+
+            buf = Some(buckets.buckets);
+        });
+
+        // Edges at 100, 215, 464 and 1000 Hz
+        let spectrum = Spectrum::new(vec![1.0; 10], 100.0, 1000.0);
+        let bands = spectrum.fill_buckets_log(vec![0.0; 3]);
+        assert_eq!(&*bands.buckets, &[2.0, 2.0, 6.0]);
+    }
+
+    #[test]
+    fn test_fill_max() {
+        // One tall bucket in each group of four
+        let spectrum = Spectrum::new(
+            (0..40)
+                .map(|i| if i % 4 == 1 { 10.0 } else { 1.0 })
+                .collect::<Vec<_>>(),
+            0.0,
+            1000.0,
+        );
+
+        let pooled = spectrum.fill_buckets_max(vec![0.0; 10]);
+        check_integrity(&pooled);
+        assert!(pooled.iter().all(|v| *v == 10.0));
+
+        // Summing dilutes the peak with its neighbours
+        let summed = spectrum.fill_buckets_alloc(10);
+        assert!(summed.iter().all(|v| *v == 13.0));
+
+        // More target buckets than source buckets
+        let stretched = spectrum.slice(0.0, 50.0).fill_buckets_max(vec![-1.0; 4]);
+        assert_eq!(stretched.iter().filter(|v| **v == 0.0).count(), 1);
+    }
+
     #[test]
     fn test_self_move() {
         let a = Spectrum::new(vec![1.0; 200], 100.0, 800.0);
@@ -585,4 +1481,280 @@ mod tests {
         assert_eq!(b.lowest(), c.lowest());
         assert_eq!(b.highest(), c.highest());
     }
+
+    #[test]
+    fn test_sum() {
+        do_tests(|_, _, _, _, _, spectrum| {
+            assert_eq!(spectrum.sum(), spectrum.iter().sum::<f32>());
+        })
+    }
+
+    #[test]
+    fn test_band_ratio() {
+        let spectrum = Spectrum::new(
+            (0..400).map(|i| 400.0 - i as f32).collect::<Vec<_>>(),
+            0.0,
+            4000.0,
+        );
+
+        assert!(spectrum.band_ratio(50.0, 250.0, 2000.0, 2200.0) > 1.0);
+        assert!(spectrum.band_ratio(2000.0, 2200.0, 50.0, 250.0) < 1.0);
+    }
+
+    #[test]
+    fn test_weighted_energy() {
+        let bass_weight = |f: Frequency| if f <= 200.0 { 1.0 } else { 0.0 };
+
+        let mut bass = Spectrum::new(vec![0.0; 401], 0.0, 4000.0);
+        for v in bass.iter_mut().skip(5).take(11) {
+            *v = 1.0;
+        }
+        let mut treble = Spectrum::new(vec![0.0; 400], 0.0, 4000.0);
+        for v in treble.iter_mut().skip(100) {
+            *v = 10.0;
+        }
+
+        assert!(bass.weighted_energy(bass_weight) > 0.4);
+        assert_eq!(treble.weighted_energy(bass_weight), 0.0);
+        assert_eq!(treble.weighted_energy(|_| 0.0), 0.0);
+
+        // An indicator weight is the mean of the band
+        let band = |f: Frequency| {
+            if (50.0..=200.0).contains(&f) {
+                1.0
+            } else {
+                0.0
+            }
+        };
+        let expected = bass.slice(50.0, 200.0).mean();
+        assert!((bass.weighted_energy(band) - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_average_mismatch() {
+        let left = Spectrum::new(vec![1.0; 4], 0.0, 100.0);
+        let right = Spectrum::new(vec![3.0; 8], 0.0, 100.0);
+        let shifted = Spectrum::new(vec![3.0; 4], 10.0, 100.0);
+        let mut out = Spectrum::new(vec![0.0; 4], 0.0, 100.0);
+
+        let err = try_average_spectrum(&mut out, &[left.as_ref(), right.as_ref()]).unwrap_err();
+        assert_eq!(
+            err,
+            SpectrumMismatch::Length {
+                index: 1,
+                expected: 4,
+                found: 8
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Spectrum 1 has 8 buckets but spectrum 0 has 4"
+        );
+
+        assert!(matches!(
+            try_average_spectrum(&mut out, &[left.as_ref(), shifted.as_ref()]),
+            Err(SpectrumMismatch::Span { index: 1, .. })
+        ));
+        assert!(matches!(
+            try_average_spectrum(&mut out, &[right.as_ref(), right.as_ref()]),
+            Err(SpectrumMismatch::Output {
+                expected: 8,
+                found: 4
+            })
+        ));
+        assert_eq!(
+            try_average_spectrum::<&[f32], _>(&mut out, &[]).unwrap_err(),
+            SpectrumMismatch::Empty
+        );
+
+        // Nothing was written on errors
+        assert_eq!(out.max(), 0.0);
+
+        let both = [left.clone(), Spectrum::new(vec![3.0; 4], 0.0, 100.0)];
+        let average = try_average_spectrum(&mut out, &both).unwrap();
+        assert!(average.iter().all(|v| *v == 2.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't average spectra")]
+    fn test_average_panic() {
+        let left = Spectrum::new(vec![1.0; 4], 0.0, 100.0);
+        let right = Spectrum::new(vec![1.0; 8], 0.0, 100.0);
+        let mut out = Spectrum::new(vec![0.0; 4], 0.0, 100.0);
+
+        average_spectrum(&mut out, &[left, right]);
+    }
+
+    #[test]
+    fn test_reversed_view() {
+        do_tests(|n, l, h, _, _, spectrum| {
+            let reversed = spectrum.reversed_view();
+
+            assert_eq!(reversed.len(), n);
+            assert_eq!(reversed[0], spectrum[n - 1]);
+            assert_eq!(reversed[n - 1], spectrum[0]);
+            assert_eq!(reversed.id_to_freq(0), spectrum.id_to_freq(n - 1));
+            assert!((reversed.id_to_freq(n - 1) - l).abs() < 1e-3);
+            assert!((reversed.id_to_freq(0) - h).abs() < 1e-2);
+            assert_eq!(reversed.freq_to_id(h), 0);
+            assert!(reversed.iter().eq(spectrum.iter().rev()));
+        })
+    }
+
+    #[test]
+    fn test_argmax() {
+        do_tests(|n, _, _, low, high, mut spectrum| {
+            // Buckets hold their index, so the last one is the biggest
+            assert_eq!(spectrum.argmax(), n - 1);
+            assert_eq!(spectrum.argmax_freq(), spectrum.id_to_freq(n - 1));
+            assert_eq!(
+                spectrum.argmax_in_range(low, high),
+                spectrum.freq_to_id(high)
+            );
+
+            let peak = spectrum.freq_to_id(low);
+            spectrum[peak] = 1e9;
+            spectrum[n - 1] = f32::NAN;
+            assert_eq!(spectrum.argmax(), peak);
+            assert_eq!(spectrum.argmax_freq(), spectrum.id_to_freq(peak));
+            assert_eq!(spectrum.argmax_in_range(low, high), peak);
+            assert_eq!(
+                spectrum.argmax_freq_in_range(low, high),
+                spectrum.id_to_freq(peak)
+            );
+        })
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let spectrum = Spectrum::new(vec![0.5; 5], 100.0, 500.0);
+
+        let mut buf = Vec::new();
+        spectrum.write_csv(&mut buf).unwrap();
+        let csv = String::from_utf8(buf).unwrap();
+
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "frequency,magnitude");
+        assert_eq!(lines[1], "100,0.5");
+        assert_eq!(lines[5], "500,0.5");
+    }
+
+    #[test]
+    fn test_map_into() {
+        do_tests(|n, l, h, _, _, spectrum| {
+            let mut out = Spectrum::new(vec![0.0; n], 0.0, 1.0);
+            spectrum.map_into(&mut out, |v| v * 2.0);
+            check_integrity(&out);
+
+            assert_eq!(out.lowest(), l);
+            assert_eq!(out.highest(), h);
+            assert_eq!(out.width, spectrum.width);
+            for (o, s) in out.iter().zip(spectrum.iter()) {
+                assert_eq!(*o, s * 2.0);
+            }
+        })
+    }
+
+    #[test]
+    fn test_power_amplitude() {
+        do_tests(|n, l, h, _, _, power| {
+            let mut amplitude = Spectrum::new(vec![0.0; n], 0.0, 1.0);
+            let mut roundtrip = Spectrum::new(vec![0.0; n], 0.0, 1.0);
+
+            power.sqrt_into(&mut amplitude).square_into(&mut roundtrip);
+            check_integrity(&roundtrip);
+
+            assert_eq!(amplitude[n - 1], ((n - 1) as f32).sqrt());
+            assert_eq!(roundtrip.lowest(), l);
+            assert_eq!(roundtrip.highest(), h);
+            for (r, p) in roundtrip.iter().zip(power.iter()) {
+                assert!((r - p).abs() <= p * 1e-6, "{} != {}", r, p);
+            }
+        })
+    }
+
+    #[test]
+    fn test_complex() {
+        let complex = ComplexSpectrum::new(
+            vec![
+                Complex::new(1.0, 0.0),
+                Complex::new(0.0, 2.0),
+                Complex::new(-3.0, 4.0),
+            ],
+            100.0,
+            300.0,
+        );
+        assert_eq!(complex.id_to_freq(1), 200.0);
+        assert_eq!(complex.phase(1), std::f32::consts::FRAC_PI_2);
+
+        let mut out = Spectrum::new(vec![0.0; 3], 0.0, 1.0);
+        complex.power_into(&mut out);
+        assert_eq!(out.iter().copied().collect::<Vec<_>>(), [1.0, 4.0, 25.0]);
+        assert_eq!(out.lowest(), 100.0);
+        assert_eq!(out.highest(), 300.0);
+
+        complex.magnitude_into(&mut out);
+        assert_eq!(out.iter().copied().collect::<Vec<_>>(), [1.0, 2.0, 5.0]);
+
+        complex.phase_into(&mut out);
+        assert_eq!(out[0], 0.0);
+    }
+
+    #[test]
+    fn test_normalize_energy() {
+        let spectrum = Spectrum::new(vec![0.5, 2.0, 0.0, 1.5], 100.0, 400.0);
+        let mut out = Spectrum::new(vec![0.0; 4], 0.0, 1.0);
+
+        spectrum.normalize_energy_into(&mut out, 10.0);
+        assert!((out.sum() - 10.0).abs() < 1e-5);
+        assert_eq!(out.lowest(), 100.0);
+        for (o, v) in out.iter().zip(spectrum.iter()) {
+            assert!((o / 10.0 - v / 4.0).abs() < 1e-6);
+        }
+
+        let silent = Spectrum::new(vec![0.0; 4], 100.0, 400.0);
+        silent.normalize_energy_into(&mut out, 10.0);
+        assert_eq!(out.sum(), 0.0);
+    }
+
+    #[test]
+    fn test_compress() {
+        let spectrum = Spectrum::new(vec![0.0, 0.1, 0.5, 2.0, 8.0], 100.0, 500.0);
+        let mut out = Spectrum::new(vec![0.0; 5], 0.0, 1.0);
+
+        spectrum.compress_into(&mut out, 0.5, 4.0);
+        assert_eq!(out.lowest(), 100.0);
+        // Below and at the threshold nothing changes
+        assert_eq!(out[0], 0.0);
+        assert_eq!(out[1], 0.1);
+        assert_eq!(out[2], 0.5);
+        // Above, the level over the threshold is divided by the ratio
+        for i in 3..5 {
+            let db = |v: f32| 20.0 * (v / 0.5).log10();
+            assert!((db(out[i]) - db(spectrum[i]) / 4.0).abs() < 1e-4);
+            assert!(out[i] < spectrum[i]);
+        }
+        assert!(out[3] < out[4]);
+    }
+
+    #[test]
+    fn test_db() {
+        let power = Spectrum::new(vec![0.0, 2.0, 20.0, 2e-14, -1.0], 220.0, 660.0);
+
+        let db = power.to_db(2.0);
+        assert_eq!(db.lowest(), 220.0);
+        assert_eq!(db[0], DB_FLOOR);
+        assert_eq!(db[1], 0.0);
+        assert!((db[2] - 10.0).abs() < 1e-4);
+        assert_eq!(db[3], DB_FLOOR);
+        assert_eq!(db[4], DB_FLOOR);
+        assert!(db.iter().all(|v| v.is_finite()));
+
+        let mut power = power;
+        power.map_db_floor(2.0, -60.0);
+        assert_eq!(power[0], -60.0);
+        assert_eq!(power[1], 0.0);
+        assert_eq!(power[3], -60.0);
+    }
 }
@@ -0,0 +1,216 @@
+//! Tempo Estimation
+use crate::analyzer;
+use std::collections;
+
+/// Builder for TempoTracker
+#[derive(Debug, Default)]
+pub struct TempoBuilder {
+    /// How many volume values are pushed per second
+    ///
+    /// Defaults to `"audio.conversions"` or `300`.
+    pub rate: Option<usize>,
+
+    /// Length of the volume history in seconds
+    ///
+    /// Longer histories give more stable estimates but react slower to tempo changes.
+    /// Defaults to `8.0`, can also be set from config as `"audio.tempo.length"`.
+    pub length: Option<f32>,
+
+    /// Range of tempos to search in, in BPM
+    ///
+    /// Defaults to `60 BPM - 200 BPM`, can also be set from config as `"audio.tempo.low"`
+    /// and `"audio.tempo.high"`.
+    pub range: Option<(f32, f32)>,
+}
+
+impl TempoBuilder {
+    /// Create new TempoBuilder
+    pub fn new() -> TempoBuilder {
+        Default::default()
+    }
+
+    /// Set the rate at which volume values are pushed
+    pub fn rate(&mut self, rate: usize) -> &mut TempoBuilder {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// Set the history length in seconds
+    pub fn length(&mut self, length: f32) -> &mut TempoBuilder {
+        self.length = Some(length);
+        self
+    }
+
+    /// Set the tempo range in BPM
+    pub fn range(&mut self, low: f32, high: f32) -> &mut TempoBuilder {
+        self.range = Some((low, high));
+        self
+    }
+
+    /// Build the tracker
+    pub fn build(&mut self) -> TempoTracker {
+        TempoTracker::from_builder(self)
+    }
+}
+
+/// A tempo tracker
+///
+/// Keeps a history of the volume envelope and finds its dominant periodicity using
+/// autocorrelation.  Unlike counting beats, this works without a beat detector and is
+/// robust against single missed or spurious beats.
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// # let samples = analyzer::SampleBuffer::new(32000, 8000);
+/// let mut tempo = analyzer::TempoBuilder::new()
+///     .rate(300)
+///     .length(8.0)
+///     .range(60.0, 200.0)
+///     .build();
+///
+/// tempo.push(samples.volume(0.01));
+///
+/// if let Some(bpm) = tempo.bpm() {
+///     println!("Tempo: {} BPM", bpm);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TempoTracker {
+    rate: f32,
+    min_lag: usize,
+    max_lag: usize,
+
+    history: collections::VecDeque<analyzer::SignalStrength>,
+    capacity: usize,
+}
+
+impl TempoTracker {
+    /// Create a TempoTracker from a builder config
+    pub fn from_builder(build: &TempoBuilder) -> TempoTracker {
+        let rate = build
+            .rate
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.conversions", 300))
+            as f32;
+        let length = build
+            .length
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.tempo.length", 8.0));
+        let (low, high) = build.range.unwrap_or_else(|| {
+            (
+                crate::CONFIG.get_or("audio.tempo.low", 60.0),
+                crate::CONFIG.get_or("audio.tempo.high", 200.0),
+            )
+        });
+
+        let capacity = (rate * length) as usize;
+        let min_lag = ((rate * 60.0 / high).floor() as usize).max(1);
+        let max_lag = (rate * 60.0 / low).ceil() as usize;
+        assert!(
+            max_lag * 2 <= capacity,
+            "Tempo history is too short for the lowest tempo!"
+        );
+
+        TempoTracker {
+            rate,
+            min_lag,
+            max_lag,
+
+            history: collections::VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Push the latest volume value
+    pub fn push(&mut self, volume: analyzer::SignalStrength) {
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(volume);
+    }
+
+    /// Estimate the current tempo in BPM
+    ///
+    /// Returns `None` until at least two periods of the slowest tempo were recorded or if the
+    /// volume did not change at all.
+    pub fn bpm(&self) -> Option<f32> {
+        let n = self.history.len();
+        if n < self.max_lag * 2 {
+            return None;
+        }
+
+        let mean = self.history.iter().sum::<f32>() / n as f32;
+        let (a, b) = self.history.as_slices();
+        let centered = a
+            .iter()
+            .chain(b.iter())
+            .map(|v| v - mean)
+            .collect::<Vec<_>>();
+
+        // Biased autocorrelation, which favors the shortest period over its multiples
+        let correlation = |lag: usize| {
+            centered
+                .iter()
+                .zip(centered[lag..].iter())
+                .map(|(x, y)| x * y)
+                .sum::<f32>()
+                / n as f32
+        };
+
+        let energy = correlation(0);
+        if energy <= 0.0 {
+            return None;
+        }
+
+        let (lag, value) = (self.min_lag..=self.max_lag)
+            .map(|lag| (lag, correlation(lag)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .unwrap();
+        if value <= 0.0 {
+            return None;
+        }
+
+        // Parabolic interpolation around the peak for sub-lag precision
+        let (prev, next) = (correlation(lag - 1), correlation(lag + 1));
+        let denom = prev - 2.0 * value + next;
+        let offset = if denom != 0.0 {
+            (0.5 * (prev - next) / denom).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+
+        Some(60.0 * self.rate / (lag as f32 + offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty() {
+        let tempo = TempoBuilder::new()
+            .rate(100)
+            .length(8.0)
+            .range(60.0, 200.0)
+            .build();
+
+        assert_eq!(tempo.bpm(), None);
+    }
+
+    #[test]
+    fn test_modulation() {
+        let mut tempo = TempoBuilder::new()
+            .rate(100)
+            .length(8.0)
+            .range(60.0, 200.0)
+            .build();
+
+        for i in 0..800 {
+            let t = i as f32 / 100.0;
+            tempo.push(0.5 + 0.5 * (2.0 * std::f32::consts::PI * 2.0 * t).sin());
+        }
+
+        let bpm = tempo.bpm().unwrap();
+        assert!((bpm - 120.0).abs() < 2.0, "{} != 120", bpm);
+    }
+}
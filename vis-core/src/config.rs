@@ -0,0 +1,330 @@
+//! Config Helpers
+use ezconf::toml;
+
+/// Error for config values which do not have the expected shape
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    /// Toml path of the offending value
+    pub path: String,
+    /// Index of the offending element, if the value is an array
+    pub index: Option<usize>,
+    /// Name of the expected type
+    pub expected: &'static str,
+    /// The value found in the config
+    pub found: String,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Config value {:?}", self.path)?;
+        if let Some(index) = self.index {
+            write!(f, " (element {})", index)?;
+        }
+        write!(f, " should be `{}` but is `{}`", self.expected, self.found)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Parse a config value as an array of `T`
+///
+/// Every element is checked on its own so the error can point at the offending one.
+pub fn parse_vec<T>(path: &str, value: toml::Value) -> Result<Vec<T>, ConfigError>
+where
+    T: for<'de> toml::macros::Deserialize<'de>,
+{
+    let array = match value {
+        toml::Value::Array(array) => array,
+        other => {
+            return Err(ConfigError {
+                path: path.to_string(),
+                index: None,
+                expected: std::any::type_name::<Vec<T>>(),
+                found: other.to_string(),
+            })
+        }
+    };
+
+    array
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| {
+            v.clone().try_into::<T>().map_err(|_| ConfigError {
+                path: path.to_string(),
+                index: Some(i),
+                expected: std::any::type_name::<T>(),
+                found: v.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Write `values` as an array to the dotted toml `path`
+///
+/// The counterpart to [`parse_vec`](fn.parse_vec.html), missing tables are created as in
+/// [`set_path`](fn.set_path.html).  Fails without touching `root` if an element cannot be
+/// represented in toml.
+pub fn set_vec<T>(root: &mut toml::Value, path: &str, values: &[T]) -> Result<(), ConfigError>
+where
+    T: serde::Serialize,
+{
+    let array = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            toml::Value::try_from(v).map_err(|e| ConfigError {
+                path: path.to_string(),
+                index: Some(i),
+                expected: "toml value",
+                found: e.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    set_path(root, path, toml::Value::Array(array));
+    Ok(())
+}
+
+/// Parse a `key=value` override
+///
+/// The value is parsed as a toml value, so `44100` becomes an integer and `[1.0, 0.8]` an
+/// array.  Anything which isn't valid toml, like `pulse`, is taken as a string.  Returns
+/// `None` if there is no `=` or the key is empty.
+pub fn parse_override(arg: &str) -> Option<(String, toml::Value)> {
+    let (key, value) = arg.split_once('=')?;
+    let key = key.trim();
+    if key.is_empty() {
+        return None;
+    }
+
+    let value = format!("v = {}", value)
+        .parse::<toml::Value>()
+        .ok()
+        .and_then(|mut v| v.as_table_mut().and_then(|t| t.remove("v")))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()));
+
+    Some((key.to_string(), value))
+}
+
+/// Collect all `--set key=value` overrides from command line arguments
+///
+/// Both `--set key=value` and `--set=key=value` are accepted, other arguments are ignored.
+/// Panics if an override is malformed.
+pub fn parse_args<I, S>(args: I) -> Vec<(String, toml::Value)>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut overrides = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let arg = arg.as_ref();
+        let set = if arg == "--set" {
+            match args.next() {
+                Some(set) => set.as_ref().to_string(),
+                None => panic!("Missing key=value after --set!"),
+            }
+        } else if let Some(set) = arg.strip_prefix("--set=") {
+            set.to_string()
+        } else {
+            continue;
+        };
+
+        match parse_override(&set) {
+            Some(o) => overrides.push(o),
+            None => panic!("Invalid override {:?}, expected key=value!", set),
+        }
+    }
+
+    overrides
+}
+
+/// Set the value at the dotted toml `path`, creating missing tables on the way
+///
+/// Values which are in the way of a table are replaced.
+pub fn set_path(root: &mut toml::Value, path: &str, value: toml::Value) {
+    let mut keys = path.split('.').map(str::trim).peekable();
+    let mut current = root;
+    while let Some(key) = keys.next() {
+        if !current.is_table() {
+            *current = toml::Value::Table(toml::value::Table::new());
+        }
+        let table = current.as_table_mut().unwrap();
+
+        if keys.peek().is_none() {
+            table.insert(key.to_string(), value);
+            return;
+        }
+        current = table
+            .entry(key.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::value::Table::new()));
+    }
+}
+
+/// Typed accessors for config arrays
+///
+/// # Example
+/// ```rust
+/// use vis_core::ConfigExt;
+///
+/// # vis_core::default_config();
+/// let colors: Vec<[f32; 4]> = vis_core::CONFIG.get_vec(
+///     "foo.colors",
+///     vec![[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]],
+/// );
+/// ```
+pub trait ConfigExt {
+    /// Retrieve an array of `T`
+    ///
+    /// Returns `Ok(None)` if the value does not exist and an error describing the offending
+    /// element if it has the wrong shape.
+    fn try_get_vec<T>(&self, path: &str) -> Result<Option<Vec<T>>, ConfigError>
+    where
+        T: for<'de> toml::macros::Deserialize<'de>;
+
+    /// Retrieve an array of `T` or return a default
+    ///
+    /// Panics with a descriptive message if the value has the wrong shape.
+    fn get_vec<T>(&self, path: &str, def: Vec<T>) -> Vec<T>
+    where
+        T: for<'de> toml::macros::Deserialize<'de>,
+    {
+        match self.try_get_vec(path) {
+            Ok(v) => v.unwrap_or(def),
+            Err(e) => {
+                log::error!("{}", e);
+                panic!("{}", e);
+            }
+        }
+    }
+}
+
+impl ConfigExt for ezconf::Config {
+    fn try_get_vec<T>(&self, path: &str) -> Result<Option<Vec<T>>, ConfigError>
+    where
+        T: for<'de> toml::macros::Deserialize<'de>,
+    {
+        self.get::<toml::Value>(path)
+            .map(|v| parse_vec(path, v))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(s: &str) -> toml::Value {
+        s.parse::<toml::Value>().unwrap()["v"].clone()
+    }
+
+    #[test]
+    fn test_parse_vec() {
+        let colors: Vec<[f32; 4]> = parse_vec(
+            "v",
+            value("v = [[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]]"),
+        )
+        .unwrap();
+
+        assert_eq!(colors, [[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]]);
+    }
+
+    #[test]
+    fn test_parse_override() {
+        let parse = |s| parse_override(s).unwrap();
+
+        assert_eq!(
+            parse("audio.rate=44100"),
+            ("audio.rate".into(), 44100.into())
+        );
+        assert_eq!(parse("noa.fps = 30.5"), ("noa.fps".into(), 30.5.into()));
+        assert_eq!(parse("audio.auto_reconnect=true").1, true.into());
+        assert_eq!(parse("audio.recorder=pulse").1, "pulse".into());
+        assert_eq!(parse("audio.recorder=\"pulse\"").1, "pulse".into());
+        assert_eq!(parse("alsa.device=hw:1,0").1, "hw:1,0".into());
+        assert_eq!(parse("osc.prefix=").1, "".into());
+        assert_eq!(
+            parse("audio.channel_gains=[1.0, 0.8]").1,
+            value("v = [1.0, 0.8]")
+        );
+
+        assert_eq!(parse_override("audio.rate"), None);
+        assert_eq!(parse_override("=1"), None);
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let overrides = parse_args(["noa", "--fullscreen", "--set", "a.b=1", "--set=c=x"]);
+        assert_eq!(
+            overrides,
+            [("a.b".into(), 1.into()), ("c".into(), "x".into())]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid override")]
+    fn test_parse_args_malformed() {
+        parse_args(["--set", "audio.rate"]);
+    }
+
+    #[test]
+    fn test_set_path() {
+        let mut config = "[audio]\nrate = 8000\nrecorder = \"cpal\"\n\n[noa]\nfps = 30\n"
+            .parse::<toml::Value>()
+            .unwrap();
+
+        set_path(&mut config, "audio.rate", 44100.into());
+        set_path(&mut config, "audio.beat.trigger", 0.3.into());
+        set_path(&mut config, "noa.fps.max", 60.into());
+
+        // Survives the round trip through a string
+        let config = config.to_string().parse::<toml::Value>().unwrap();
+        assert_eq!(config["audio"]["rate"], 44100.into());
+        assert_eq!(config["audio"]["recorder"], "cpal".into());
+        assert_eq!(config["audio"]["beat"]["trigger"], 0.3.into());
+        assert_eq!(config["noa"]["fps"]["max"], 60.into());
+    }
+
+    #[test]
+    fn test_parse_vec_malformed() {
+        let err = parse_vec::<[f32; 4]>("v", value("v = [[1.0, 0.0, 0.0, 1.0], [0.0, 1.0]]"))
+            .unwrap_err();
+
+        assert_eq!(err.path, "v");
+        assert_eq!(err.index, Some(1));
+        assert_eq!(err.expected, "[f32; 4]");
+        assert_eq!(
+            err.to_string(),
+            "Config value \"v\" (element 1) should be `[f32; 4]` but is `[0.0, 1.0]`"
+        );
+
+        let err = parse_vec::<[f32; 4]>("v", value("v = 1.0")).unwrap_err();
+        assert_eq!(err.index, None);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Config value \"v\" should be `{}` but is `1.0`",
+                std::any::type_name::<Vec<[f32; 4]>>()
+            )
+        );
+    }
+
+    #[test]
+    fn test_set_vec() {
+        let mut config = toml::Value::Table(toml::value::Table::new());
+        let colors = vec![[1.0f32, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 0.5]];
+        set_vec(&mut config, "noa.cols.colors", &colors).unwrap();
+
+        // Survives the round trip through a string
+        let mut config = config.to_string().parse::<toml::Value>().unwrap();
+        let parsed: Vec<[f32; 4]> =
+            parse_vec("noa.cols.colors", config["noa"]["cols"]["colors"].clone()).unwrap();
+        assert_eq!(parsed, colors);
+
+        // Options have no toml representation
+        let before = config.clone();
+        let err = set_vec(&mut config, "v", &[Some(1), None]).unwrap_err();
+        assert_eq!(err.index, Some(1));
+        assert_eq!(config, before);
+    }
+}
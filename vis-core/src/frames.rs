@@ -1,5 +1,9 @@
 use crate::{analyzer, recorder};
-use std::{cell, rc, time};
+use std::{
+    cell, rc,
+    sync::{self, atomic, mpsc},
+    thread, time,
+};
 
 /// Data for one Frame
 #[derive(Debug)]
@@ -7,19 +11,31 @@ pub struct Frame<R: Send> {
     /// Timestamp since start
     pub time: f32,
 
+    /// Time since the previous frame
+    ///
+    /// Median filtered if [delta smoothing](struct.Frames.html#method.smooth_delta) is
+    /// enabled.
+    pub delta: f32,
+
     /// Frame number
     pub frame: usize,
 
     info: rc::Rc<cell::RefCell<triple_buffer::Output<R>>>,
+    queue: Option<rc::Rc<mpsc::Receiver<R>>>,
+    interpolated: Option<R>,
 }
 
 impl<R: Send> Frame<R> {
     /// Get access to the latest info shared from the analyzer
     ///
+    /// If [interpolation](struct.Frames.html#method.interpolate) is enabled, this is the
+    /// interpolated info for this frame instead.
+    ///
     /// # Example
     /// ```
     /// # vis_core::default_config();
     /// # let mut frames = vis_core::Visualizer::new(0.0, |i, _s| i)
+    /// #     .recorder(vis_core::recorder::null::NullBuilder::new().build())
     /// #     .frames();
     /// for frame in frames.iter() {
     ///     println!("Time: {}", frame.time);
@@ -37,7 +53,36 @@ impl<R: Send> Frame<R> {
     where
         F: FnOnce(&R) -> O,
     {
-        f(self.info.borrow_mut().read())
+        match self.interpolated {
+            Some(ref info) => f(info),
+            None => f(self.info.borrow_mut().read()),
+        }
+    }
+
+    /// Take all results queued since the last call
+    ///
+    /// Only yields anything if the visualizer was configured with
+    /// [`Transport::Queue`](../visualizer/enum.Transport.html#variant.Queue).
+    ///
+    /// # Example
+    /// ```
+    /// # vis_core::default_config();
+    /// # let mut frames = vis_core::Visualizer::new(0.0, |i, _s| i)
+    /// #     .recorder(vis_core::recorder::null::NullBuilder::new().build())
+    /// #     .transport(vis_core::visualizer::Transport::Queue(16))
+    /// #     .frames();
+    /// for frame in frames.iter() {
+    ///     for info in frame.queued() {
+    ///         println!("Info: {:?}", info);
+    ///     }
+    /// #
+    /// #     if frame.time > 0.3 {
+    /// #         break;
+    /// #     }
+    /// }
+    /// ```
+    pub fn queued(&self) -> impl Iterator<Item = R> + '_ {
+        self.queue.iter().flat_map(|q| q.try_iter())
     }
 }
 
@@ -49,8 +94,152 @@ where
     for<'r> A: FnMut(&'r mut R, &analyzer::SampleBuffer) -> &'r mut R + Send + 'static,
 {
     info: rc::Rc<cell::RefCell<triple_buffer::Output<R>>>,
-    analyzer: Option<(A, triple_buffer::Input<R>)>,
+    queue: Option<rc::Rc<mpsc::Receiver<R>>>,
+    analyzer: Option<Publisher<R, A>>,
     recorder: Box<dyn recorder::Recorder>,
+    pace: Option<time::Duration>,
+    delta_window: usize,
+    interpolation: Option<Interpolation<R>>,
+    stop: sync::Arc<atomic::AtomicBool>,
+    analyzer_thread: Option<thread::JoinHandle<()>>,
+    publish_interval: Option<f32>,
+    analysis_window: Option<f32>,
+}
+
+/// Interpolation state between the two latest published results
+#[derive(Debug)]
+struct Interpolation<R> {
+    lerp: fn(&R, &R, f32) -> R,
+    state: Option<InterpolationState<R>>,
+}
+
+#[derive(Debug)]
+struct InterpolationState<R> {
+    from: R,
+    to: R,
+    arrived: time::Instant,
+    interval: time::Duration,
+}
+
+impl<R: Clone + Send> Interpolation<R> {
+    fn new(lerp: fn(&R, &R, f32) -> R) -> Interpolation<R> {
+        Interpolation { lerp, state: None }
+    }
+
+    /// Compute the value to show at `now`
+    ///
+    /// When a new result was published, interpolation restarts from the value shown so far
+    /// towards the new result, taking as long as the time between the last two results.
+    fn step(&mut self, output: &mut triple_buffer::Output<R>, now: time::Instant) -> R {
+        if output.update() || self.state.is_none() {
+            let latest = output.output_buffer().clone();
+            self.state = Some(match self.state.take() {
+                None => InterpolationState {
+                    from: latest.clone(),
+                    to: latest,
+                    arrived: now,
+                    interval: time::Duration::ZERO,
+                },
+                Some(state) => InterpolationState {
+                    from: self.value(&state, now),
+                    to: latest,
+                    arrived: now,
+                    interval: now - state.arrived,
+                },
+            });
+        }
+
+        let state = self.state.as_ref().unwrap();
+        self.value(state, now)
+    }
+
+    fn value(&self, state: &InterpolationState<R>, now: time::Instant) -> R {
+        if state.interval.is_zero() {
+            return state.to.clone();
+        }
+
+        let t = (now - state.arrived).as_secs_f32() / state.interval.as_secs_f32();
+        (self.lerp)(&state.from, &state.to, t.min(1.0))
+    }
+}
+
+/// Analyzer and the input side of the shared buffer
+#[derive(Debug)]
+struct Publisher<R, A>
+where
+    R: Clone + Send + 'static,
+    for<'r> A: FnMut(&'r mut R, &analyzer::SampleBuffer) -> &'r mut R + Send + 'static,
+{
+    analyzer: A,
+    info: triple_buffer::Input<R>,
+    publish_if: Option<fn(&R, &R) -> bool>,
+    last: Option<R>,
+    queue: Option<mpsc::SyncSender<R>>,
+    /// Block on a full queue until this flag is raised
+    block: Option<sync::Arc<atomic::AtomicBool>>,
+}
+
+impl<R, A> Publisher<R, A>
+where
+    R: Clone + Send + 'static,
+    for<'r> A: FnMut(&'r mut R, &analyzer::SampleBuffer) -> &'r mut R + Send + 'static,
+{
+    fn new(
+        analyzer: A,
+        info: triple_buffer::Input<R>,
+        publish_if: Option<fn(&R, &R) -> bool>,
+        queue: Option<mpsc::SyncSender<R>>,
+    ) -> Publisher<R, A> {
+        Publisher {
+            analyzer,
+            info,
+            publish_if,
+            last: None,
+            queue,
+            block: None,
+        }
+    }
+
+    /// Run the analyzer once and publish its result
+    ///
+    /// If a `publish_if` predicate is set, the result is only published when the predicate
+    /// returns true for the previously published and the new value.
+    ///
+    /// If a queue is set, the result is also sent over it.  Sending blocks while the queue
+    /// is full if `block` is set, until its flag is raised.  Otherwise the result is dropped
+    /// from the queue.
+    fn run(&mut self, buffer: &analyzer::SampleBuffer) {
+        let new = (self.analyzer)(self.info.input_buffer(), buffer);
+
+        if let Some(predicate) = self.publish_if {
+            if let Some(ref last) = self.last {
+                if !predicate(last, new) {
+                    log::trace!("Analyzer result unchanged, not publishing");
+                    return;
+                }
+            }
+            self.last = Some(new.clone());
+        }
+
+        if let Some(ref queue) = self.queue {
+            if let Some(ref stop) = self.block {
+                // Polling instead of a blocking send, because frames still alive in the render
+                // loop keep the receiver and a full queue open after a stop
+                let mut value = new.clone();
+                while let Err(mpsc::TrySendError::Full(v)) = queue.try_send(value) {
+                    if stop.load(atomic::Ordering::SeqCst) {
+                        break;
+                    }
+                    value = v;
+                    std::thread::sleep(time::Duration::from_millis(1));
+                }
+            } else if let Err(mpsc::TrySendError::Full(_)) = queue.try_send(new.clone()) {
+                log::warn!("Result queue is full, dropping result");
+            }
+        }
+
+        self.info.publish();
+    }
 }
 
 impl<R, A> Frames<R, A>
@@ -60,12 +249,27 @@ where
 {
     pub fn from_vis(vis: crate::Visualizer<R, A>) -> Frames<R, A> {
         let (inp, outp) = triple_buffer::TripleBuffer::new(&vis.initial).split();
+        let (sender, receiver) = match vis.transport {
+            crate::visualizer::Transport::Latest => (None, None),
+            crate::visualizer::Transport::Queue(n) => {
+                let (sender, receiver) = mpsc::sync_channel(n);
+                (Some(sender), Some(rc::Rc::new(receiver)))
+            }
+        };
         let mut f = Frames {
             info: rc::Rc::new(cell::RefCell::new(outp)),
-            analyzer: Some((vis.analyzer, inp)),
+            queue: receiver,
+            analyzer: Some(Publisher::new(vis.analyzer, inp, vis.publish_if, sender)),
             recorder: vis
                 .recorder
                 .unwrap_or_else(|| recorder::RecorderBuilder::new().build()),
+            pace: None,
+            delta_window: 1,
+            interpolation: None,
+            stop: sync::Arc::new(atomic::AtomicBool::new(false)),
+            analyzer_thread: None,
+            publish_interval: None,
+            analysis_window: None,
         };
 
         if let Some(num) = vis.async_analyzer {
@@ -83,42 +287,177 @@ where
 
     /// Move analyzer to a separate thread
     pub fn detach_analyzer(&mut self, num: usize) {
-        let (mut analyzer, mut info) = self.analyzer.take().unwrap();
+        let mut publisher = self.analyzer.take().unwrap();
+        publisher.block = Some(self.stop.clone());
         let buffer = self.recorder.sample_buffer().clone();
 
         let conv_time = std::time::Duration::new(0, (1000000000 / num) as u32);
         log::debug!("Conversion Time: {:?}", conv_time);
+        self.publish_interval = Some(conv_time.as_secs_f32());
+
+        let stop = self.stop.clone();
 
-        std::thread::Builder::new()
+        let handle = std::thread::Builder::new()
             .name("analyzer".into())
-            .spawn(move || loop {
-                let start = std::time::Instant::now();
-                analyzer(info.input_buffer(), &buffer);
-                info.publish();
-
-                let now = std::time::Instant::now();
-                let duration = now - start;
-                log::trace!("Conversion Time (real): {:?}", duration);
-
-                if duration < conv_time {
-                    let sleep = conv_time - duration;
-                    log::trace!("Sleeping for {:?}", sleep);
-                    std::thread::sleep(sleep);
+            .spawn(move || {
+                while !stop.load(atomic::Ordering::SeqCst) {
+                    let start = std::time::Instant::now();
+                    publisher.run(&buffer);
+
+                    let now = std::time::Instant::now();
+                    let duration = now - start;
+                    log::trace!("Conversion Time (real): {:?}", duration);
+
+                    if duration < conv_time {
+                        let sleep = conv_time - duration;
+                        log::trace!("Sleeping for {:?}", sleep);
+                        std::thread::sleep(sleep);
+                    }
                 }
             })
             .unwrap();
+        self.analyzer_thread = Some(handle);
+    }
+
+    /// Stop the analyzer and the recorder
+    ///
+    /// Signals a [detached](#method.detach_analyzer) analyzer to exit and waits for its
+    /// thread, then [stops](recorder/trait.Recorder.html#method.stop) the recorder.  Afterwards,
+    /// [`iter`](#method.iter) yields no more frames.  Use this to shut down from within the
+    /// render loop, eg. to restart with a new config.  Dropping the frames stops them as well.
+    pub fn stop(&mut self) {
+        if self.stop.swap(true, atomic::Ordering::SeqCst) {
+            return;
+        }
+
+        if let Some(handle) = self.analyzer_thread.take() {
+            if handle.join().is_err() {
+                log::error!("Analyzer thread panicked");
+            }
+        }
+
+        self.recorder.stop();
+    }
+
+    /// Return whether these frames were stopped
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(atomic::Ordering::SeqCst)
+    }
+
+    /// Pace frames to the audio input
+    ///
+    /// Each frame will block until new samples were pushed into the sample buffer, but at most
+    /// for `timeout`.  This keeps rendering from running faster than audio arrives.
+    pub fn pace_to_audio(&mut self, timeout: time::Duration) {
+        self.pace = Some(timeout);
+    }
+
+    /// Set the length of the window the analyzer looks at, in seconds
+    ///
+    /// Only used for the [latency estimate](#method.estimated_latency).  Defaults to the
+    /// window of the fourier transform configured as `"audio.fourier.length"` and
+    /// `"audio.fourier.downsample"`.
+    pub fn analysis_window(&mut self, seconds: f32) {
+        self.analysis_window = Some(seconds);
+    }
+
+    /// Estimate the delay between sound arriving and its analysis being shown, in seconds
+    ///
+    /// Sums up the [recorder latency](recorder/trait.Recorder.html#method.latency), the
+    /// [analysis window](#method.analysis_window) and, for a detached analyzer, one publish
+    /// interval.  With [interpolation](#method.interpolate), another publish interval is added.
+    /// Rendering and display latency are not included.
+    pub fn estimated_latency(&self) -> f32 {
+        let rate = self.recorder.sample_buffer().rate();
+        let window = self.analysis_window.unwrap_or_else(|| {
+            let length: usize = crate::CONFIG.get_or("audio.fourier.length", 512);
+            let downsample: usize = crate::CONFIG.get_or("audio.fourier.downsample", 5);
+            (length * downsample) as f32 / rate as f32
+        });
+        let publish = self.publish_interval.unwrap_or(0.0);
+        let interpolation = if self.interpolation.is_some() {
+            publish
+        } else {
+            0.0
+        };
+
+        self.recorder.latency() + window + publish + interpolation
+    }
+
+    /// Smooth frame deltas with a median filter over the last `window` frames
+    ///
+    /// Scheduling makes [`Frame::delta`](struct.Frame.html#structfield.delta) jitter, which
+    /// shows as stutter in anything moved by it.  See
+    /// [`DeltaFilter`](../helpers/jitter/struct.DeltaFilter.html).  A `window` of `1`
+    /// disables smoothing, which is the default.
+    pub fn smooth_delta(&mut self, window: usize) {
+        assert!(window > 0, "Window must not be empty!");
+        self.delta_window = window;
     }
 
     pub fn iter<'a>(&'a mut self) -> FramesIter<'a, R, A> {
+        let buffer = self.recorder.sample_buffer().clone();
+        let delta_filter = if self.delta_window > 1 {
+            Some(crate::helpers::jitter::DeltaFilter::new(self.delta_window))
+        } else {
+            None
+        };
+
         FramesIter {
-            buffer: self.recorder.sample_buffer().clone(),
+            generation: buffer.generation(),
+            buffer,
             visualizer: self,
             start_time: time::Instant::now(),
+            last_time: 0.0,
+            delta_filter,
             frame: 0,
         }
     }
 }
 
+impl<R, A> Frames<R, A>
+where
+    R: Clone + Send + crate::helpers::anim::Lerp + 'static,
+    for<'r> A: FnMut(&'r mut R, &analyzer::SampleBuffer) -> &'r mut R + Send + 'static,
+{
+    /// Interpolate analyzer results between frames
+    ///
+    /// When rendering faster than the analyzer runs, eg. at 144 Hz with a 60 Hz analyzer,
+    /// [`Frame::info`](struct.Frame.html#method.info) jumps in steps.  With interpolation
+    /// enabled, each frame instead sees a result linearly interpolated from the previously
+    /// shown one towards the latest, based on time.  This delays results by about one
+    /// analyzer period.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # vis_core::default_config();
+    /// let mut frames = vis_core::Visualizer::new(
+    ///     vis_core::analyzer::Spectrum::new(vec![0.0; 64], 0.0, 1000.0),
+    ///     |info, _samples| info,
+    /// )
+    /// .async_analyzer(60)
+    /// .frames();
+    /// frames.interpolate(true);
+    /// ```
+    pub fn interpolate(&mut self, enable: bool) {
+        self.interpolation = if enable {
+            Some(Interpolation::new(|a: &R, b: &R, t| a.lerp(b, t)))
+        } else {
+            None
+        };
+    }
+}
+
+impl<R, A> Drop for Frames<R, A>
+where
+    R: Clone + Send + 'static,
+    for<'r> A: FnMut(&'r mut R, &analyzer::SampleBuffer) -> &'r mut R + Send + 'static,
+{
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
 /// Borrowed Frames Iterator
 #[derive(Debug)]
 pub struct FramesIter<'a, R, A>
@@ -128,7 +467,10 @@ where
 {
     visualizer: &'a mut Frames<R, A>,
     buffer: analyzer::SampleBuffer,
+    generation: u64,
     start_time: time::Instant,
+    last_time: f32,
+    delta_filter: Option<crate::helpers::jitter::DeltaFilter>,
     frame: usize,
 }
 
@@ -140,18 +482,254 @@ where
     type Item = Frame<R>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some((ref mut analyzer, ref mut info)) = self.visualizer.analyzer {
-            analyzer(info.input_buffer(), &self.buffer);
-            info.publish();
+        if self.visualizer.is_stopped() {
+            return None;
+        }
+
+        let time = crate::helpers::time(self.start_time);
+        if !self.visualizer.recorder.sync(time) {
+            log::debug!("Recorder has no more samples at {}s", time);
+            return None;
+        }
+
+        if let Some(timeout) = self.visualizer.pace {
+            match self.buffer.wait_newer(self.generation, timeout) {
+                Some(generation) => self.generation = generation,
+                None => log::trace!("No new samples within {:?}", timeout),
+            }
+        }
+
+        if let Some(ref mut publisher) = self.visualizer.analyzer {
+            publisher.run(&self.buffer);
+        }
+
+        let interpolated = match self.visualizer.interpolation {
+            Some(ref mut interpolation) => Some(
+                interpolation.step(&mut self.visualizer.info.borrow_mut(), time::Instant::now()),
+            ),
+            None => None,
+        };
+
+        let mut delta = time - self.last_time;
+        self.last_time = time;
+        if let Some(ref mut filter) = self.delta_filter {
+            delta = filter.filter(delta);
         }
 
         let frame = self.frame;
         self.frame += 1;
 
         Some(Frame {
-            time: crate::helpers::time(self.start_time),
+            time,
+            delta,
             frame,
             info: self.visualizer.info.clone(),
+            queue: self.visualizer.queue.clone(),
+            interpolated,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::TestRecorder;
+
+    #[test]
+    fn test_pace_to_audio() {
+        let buffer = analyzer::SampleBuffer::new(64, 8000);
+        let mut frames = crate::Visualizer::new(0u64, |i, s| {
+            *i = s.generation();
+            i
+        })
+        .recorder(Box::new(TestRecorder(buffer.clone())))
+        .async_analyzer(0)
+        .frames();
+        frames.pace_to_audio(time::Duration::from_secs(5));
+
+        let pusher = std::thread::spawn(move || {
+            for _ in 0..5 {
+                std::thread::sleep(time::Duration::from_millis(20));
+                buffer.push(&[[0.0; 2]; 8]);
+            }
+        });
+
+        // Every frame has to wait for the next push
+        for (i, frame) in frames.iter().take(5).enumerate() {
+            assert!(frame.info(|g| *g) >= (i as u64 + 1) * 8);
+        }
+        pusher.join().unwrap();
+    }
+
+    #[test]
+    fn test_publish_if() {
+        let buffer = analyzer::SampleBuffer::new(16, 8000);
+        let (inp, mut outp) = triple_buffer::TripleBuffer::new(&0u32).split();
+
+        let mut value = 0;
+        let mut publisher = Publisher::new(
+            move |info: &mut u32, _: &analyzer::SampleBuffer| {
+                value += 1;
+                *info = value / 2;
+                info
+            },
+            inp,
+            Some(|old: &u32, new: &u32| old != new),
+            None,
+        );
+
+        let mut published = Vec::new();
+        for _ in 0..6 {
+            publisher.run(&buffer);
+            if outp.updated() {
+                published.push(*outp.read());
+            }
+        }
+
+        assert_eq!(published, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_queue() {
+        let buffer = analyzer::SampleBuffer::new(16, 8000);
+        let mut count = 0;
+        let mut frames = crate::Visualizer::new(0u32, move |i, _s| {
+            count += 1;
+            *i = count;
+            i
         })
+        .recorder(Box::new(TestRecorder(buffer)))
+        .async_analyzer(1000)
+        .transport(crate::visualizer::Transport::Queue(4))
+        .frames();
+
+        let mut seen = Vec::new();
+        for frame in frames.iter() {
+            seen.extend(frame.queued());
+
+            if seen.len() >= 50 {
+                break;
+            }
+            std::thread::sleep(time::Duration::from_millis(5));
+        }
+
+        // Even though the render loop is slower than the analyzer, no result was dropped
+        assert_eq!(seen[..50], (1..=50).collect::<Vec<_>>()[..]);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        use crate::helpers::anim::Lerp;
+
+        let first = analyzer::Spectrum::new(vec![0.0, 2.0], 100.0, 200.0);
+        let second = analyzer::Spectrum::new(vec![4.0, 6.0], 100.0, 200.0);
+
+        let (mut inp, mut outp) = triple_buffer::TripleBuffer::new(&first).split();
+        let mut interpolation =
+            Interpolation::new(|a: &analyzer::Spectrum<Vec<f32>>, b, t| a.lerp(b, t));
+        let buckets = |s: &analyzer::Spectrum<Vec<f32>>| s.iter().copied().collect::<Vec<_>>();
+        let ms = time::Duration::from_millis;
+        let start = time::Instant::now();
+
+        let value = interpolation.step(&mut outp, start);
+        assert_eq!(buckets(&value), [0.0, 2.0]);
+
+        // The next result arrives after 100ms, so interpolation takes 100ms as well
+        inp.write(second);
+        let value = interpolation.step(&mut outp, start + ms(100));
+        assert_eq!(buckets(&value), [0.0, 2.0]);
+        let value = interpolation.step(&mut outp, start + ms(150));
+        assert_eq!(buckets(&value), [2.0, 4.0]);
+        assert_eq!(value.lowest(), 100.0);
+        assert_eq!(value.highest(), 200.0);
+        let value = interpolation.step(&mut outp, start + ms(300));
+        assert_eq!(buckets(&value), [4.0, 6.0]);
+    }
+
+    #[test]
+    fn test_stop() {
+        #[derive(Debug)]
+        struct StoppableRecorder(analyzer::SampleBuffer, sync::Arc<atomic::AtomicBool>);
+
+        impl recorder::Recorder for StoppableRecorder {
+            fn sample_buffer(&self) -> &analyzer::SampleBuffer {
+                &self.0
+            }
+
+            fn stop(&mut self) {
+                self.1.store(true, atomic::Ordering::SeqCst);
+            }
+        }
+
+        let runs = sync::Arc::new(atomic::AtomicUsize::new(0));
+        let recorder_stopped = sync::Arc::new(atomic::AtomicBool::new(false));
+        let mut frames = {
+            let runs = runs.clone();
+            crate::Visualizer::new(0u32, move |i, _s| {
+                runs.fetch_add(1, atomic::Ordering::SeqCst);
+                i
+            })
+            .recorder(Box::new(StoppableRecorder(
+                analyzer::SampleBuffer::new(16, 8000),
+                recorder_stopped.clone(),
+            )))
+            .async_analyzer(1000)
+            .frames()
+        };
+
+        for frame in frames.iter() {
+            if frame.frame == 10 {
+                break;
+            }
+        }
+        frames.stop();
+        assert!(frames.is_stopped());
+        assert!(recorder_stopped.load(atomic::Ordering::SeqCst));
+        assert!(frames.iter().next().is_none());
+
+        // The analyzer thread has exited
+        let count = runs.load(atomic::Ordering::SeqCst);
+        std::thread::sleep(time::Duration::from_millis(20));
+        assert_eq!(runs.load(atomic::Ordering::SeqCst), count);
+    }
+
+    #[test]
+    fn test_stop_full_queue() {
+        let (done, finished) = mpsc::channel();
+        std::thread::spawn(move || {
+            let buffer = analyzer::SampleBuffer::new(16, 8000);
+            let mut frames = crate::Visualizer::new(0u32, |i, _s| i)
+                .recorder(Box::new(TestRecorder(buffer)))
+                .async_analyzer(1000)
+                .transport(crate::visualizer::Transport::Queue(1))
+                .frames();
+
+            // A frame kept alive holds on to the queue, which fills up in the meantime
+            let frame = frames.iter().next().unwrap();
+            std::thread::sleep(time::Duration::from_millis(20));
+
+            frames.stop();
+            drop(frame);
+            done.send(()).unwrap();
+        });
+
+        assert!(finished.recv_timeout(time::Duration::from_secs(5)).is_ok());
+    }
+
+    #[test]
+    fn test_estimated_latency() {
+        // 800 samples of delay at 8000 Hz
+        let buffer = analyzer::SampleBuffer::with_delay(64, 8000, 800);
+        let mut frames = crate::Visualizer::new(0u32, |i, _s| i)
+            .recorder(Box::new(TestRecorder(buffer)))
+            .async_analyzer(0)
+            .frames();
+        // A 512 sample window, downsampled by 5
+        frames.analysis_window(512.0 * 5.0 / 8000.0);
+        assert!((frames.estimated_latency() - (0.1 + 0.32)).abs() < 1e-5);
+
+        // Publishing 50 times a second
+        frames.detach_analyzer(50);
+        assert!((frames.estimated_latency() - (0.1 + 0.32 + 0.02)).abs() < 1e-5);
     }
 }
@@ -0,0 +1,348 @@
+//! Animation Helpers
+
+/// Shape of a flash fading out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fade {
+    /// Fade out at a constant rate
+    Linear,
+    /// Fade out quickly at first and slowly towards the end
+    Exponential,
+}
+
+/// A flash triggered by beats
+///
+/// Turns the time since the last beat into an intensity which is `1.0` at the beat and
+/// fades out to `0.0` over `decay` seconds.
+///
+/// # Example
+/// ```
+/// # use vis_core::helpers::anim;
+/// let flash = anim::Flash::new(0.3).fade(anim::Fade::Exponential);
+///
+/// # let (time, last_beat) = (10.1, 10.0);
+/// let intensity = flash.intensity(time - last_beat);
+/// # assert!(intensity > 0.0 && intensity < 1.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Flash {
+    decay: f32,
+    fade: Fade,
+}
+
+impl Flash {
+    /// Create a new linear flash which fades out over `decay` seconds
+    pub fn new(decay: f32) -> Flash {
+        assert!(decay > 0.0, "Decay must be positive!");
+
+        Flash {
+            decay,
+            fade: Fade::Linear,
+        }
+    }
+
+    /// Set the shape of the fade
+    pub fn fade(mut self, fade: Fade) -> Flash {
+        self.fade = fade;
+        self
+    }
+
+    /// Return the intensity in `[0, 1]` given the seconds since the last beat
+    pub fn intensity(&self, since_beat: f32) -> f32 {
+        // Steepness of the exponential curve; it is renormalized to reach 0 at `decay`
+        const K: f32 = 5.0;
+
+        let t = (since_beat / self.decay).max(0.0);
+        if t >= 1.0 {
+            return 0.0;
+        }
+
+        match self.fade {
+            Fade::Linear => 1.0 - t,
+            Fade::Exponential => {
+                let end = (-K).exp();
+                ((-K * t).exp() - end) / (1.0 - end)
+            }
+        }
+    }
+}
+
+/// Edge detector for a monotonic beat counter
+///
+/// Analyzer closures usually count beats and publish the count in their info struct.
+/// `BeatEdge` remembers the last count it saw and reports whether new beats occurred.
+///
+/// # Example
+/// ```
+/// # use vis_core::helpers::anim;
+/// let mut edge = anim::BeatEdge::new();
+///
+/// # let beat_num = 1;
+/// // In the render loop
+/// if edge.update(beat_num) {
+///     // React to the beat
+/// }
+/// # assert!(!edge.update(beat_num));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BeatEdge {
+    last: u64,
+}
+
+impl BeatEdge {
+    /// Create a new edge detector, expecting the counter to start at `0`
+    pub fn new() -> BeatEdge {
+        Default::default()
+    }
+
+    /// Return whether `beat` changed since the last call
+    pub fn update(&mut self, beat: u64) -> bool {
+        let edge = beat != self.last;
+        self.last = beat;
+        edge
+    }
+
+    /// Return the last seen counter value
+    pub fn last(&self) -> u64 {
+        self.last
+    }
+}
+
+/// Linear interpolation between two values
+///
+/// Used by [`Frames::interpolate`](../../frames/struct.Frames.html#method.interpolate) to
+/// smooth analyzer results when rendering faster than the analyzer runs.  Implement this for
+/// your analyzer result, interpolating the numeric fields and taking everything else from
+/// `other`.
+///
+/// # Example
+/// ```
+/// # use vis_core::helpers::anim::Lerp;
+/// #[derive(Debug, Clone)]
+/// struct Info {
+///     volume: f32,
+///     beat: u64,
+/// }
+///
+/// impl Lerp for Info {
+///     fn lerp(&self, other: &Info, t: f32) -> Info {
+///         Info {
+///             volume: self.volume.lerp(&other.volume, t),
+///             beat: other.beat,
+///         }
+///     }
+/// }
+/// ```
+pub trait Lerp {
+    /// Interpolate between `self` (`t = 0`) and `other` (`t = 1`)
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+/// Easing function of a [`Tween`](struct.Tween.html)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    /// Constant speed
+    Linear,
+    /// Accelerate in the first half, decelerate in the second half
+    Cubic,
+    /// Overshoot the target and settle with a decaying oscillation
+    Elastic,
+}
+
+impl Easing {
+    /// Map the progress `t` in `[0, 1]` to the eased progress
+    ///
+    /// All easings start at `0.0` and end at `1.0`, `t` outside of `[0, 1]` is clamped.
+    /// `Elastic` temporarily goes beyond `1.0`.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::Cubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (2.0 - 2.0 * t).powi(3) / 2.0
+                }
+            }
+            Easing::Elastic => {
+                if t == 1.0 {
+                    return 1.0;
+                }
+                let c = 2.0 * std::f32::consts::PI / 3.0;
+                2.0f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * c).sin() + 1.0
+            }
+        }
+    }
+}
+
+/// An eased transition between two values, triggered by events like beats
+///
+/// After [`trigger`](#method.trigger), [`value`](#method.value) moves from the current value
+/// towards the target over `duration` seconds.  Triggering again while a transition is running
+/// starts from wherever the tween currently is, so there are no jumps.
+///
+/// # Example
+/// ```
+/// # use vis_core::helpers::anim;
+/// let mut scale = anim::Tween::new(1.0, 0.25).easing(anim::Easing::Elastic);
+///
+/// # let (time, beat) = (10.0, true);
+/// // In the render loop
+/// if beat {
+///     scale.trigger(time, 2.0);
+/// }
+/// let s = scale.value(time);
+/// # assert_eq!(s, 1.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tween<T: Lerp + Clone> {
+    from: T,
+    to: T,
+    start: f32,
+    duration: f32,
+    easing: Easing,
+}
+
+impl<T: Lerp + Clone> Tween<T> {
+    /// Create a linear tween resting at `value`, with transitions taking `duration` seconds
+    pub fn new(value: T, duration: f32) -> Tween<T> {
+        assert!(duration > 0.0, "Duration must be positive!");
+
+        Tween {
+            from: value.clone(),
+            to: value,
+            start: f32::NEG_INFINITY,
+            duration,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Set the easing function
+    pub fn easing(mut self, easing: Easing) -> Tween<T> {
+        self.easing = easing;
+        self
+    }
+
+    /// Start a transition towards `to` at `time` seconds
+    pub fn trigger(&mut self, time: f32, to: T) {
+        self.from = self.value(time);
+        self.to = to;
+        self.start = time;
+    }
+
+    /// Return the value at `time` seconds
+    pub fn value(&self, time: f32) -> T {
+        let t = (time - self.start) / self.duration;
+        if t >= 1.0 {
+            return self.to.clone();
+        }
+
+        self.from.lerp(&self.to, self.easing.apply(t))
+    }
+
+    /// Return whether the transition is complete at `time` seconds
+    pub fn is_done(&self, time: f32) -> bool {
+        time - self.start >= self.duration
+    }
+
+    /// Return the target of the current transition
+    pub fn target(&self) -> &T {
+        &self.to
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flash() {
+        for fade in [Fade::Linear, Fade::Exponential] {
+            let flash = Flash::new(0.5).fade(fade);
+
+            assert_eq!(flash.intensity(0.0), 1.0);
+            assert_eq!(flash.intensity(0.5), 0.0);
+            assert_eq!(flash.intensity(100.0), 0.0);
+
+            let mut last = 1.0;
+            for i in 1..10 {
+                let intensity = flash.intensity(i as f32 * 0.05);
+                assert!(intensity < last && intensity > 0.0, "{:?}", fade);
+                last = intensity;
+            }
+        }
+
+        // The exponential curve drops faster in the beginning
+        assert!(
+            Flash::new(0.5).fade(Fade::Exponential).intensity(0.1) < Flash::new(0.5).intensity(0.1)
+        );
+    }
+
+    #[test]
+    fn test_beat_edge() {
+        let mut edge = BeatEdge::new();
+        let mut edges = 0;
+
+        for beat in [0, 0, 1, 1, 1, 2, 3, 3, 4, 4] {
+            if edge.update(beat) {
+                edges += 1;
+            }
+        }
+
+        assert_eq!(edges, 4);
+        assert_eq!(edge.last(), 4);
+        assert!(!edge.update(4));
+        assert!(edge.update(5));
+    }
+
+    #[test]
+    fn test_easing() {
+        for easing in [Easing::Linear, Easing::Cubic, Easing::Elastic] {
+            assert!(easing.apply(0.0).abs() < 1e-6, "{:?}", easing);
+            assert_eq!(easing.apply(1.0), 1.0, "{:?}", easing);
+            assert_eq!(easing.apply(-1.0), easing.apply(0.0), "{:?}", easing);
+            assert_eq!(easing.apply(2.0), 1.0, "{:?}", easing);
+        }
+
+        for easing in [Easing::Linear, Easing::Cubic] {
+            let mut last = 0.0;
+            for i in 1..=20 {
+                let v = easing.apply(i as f32 / 20.0);
+                assert!(v > last, "{:?}", easing);
+                last = v;
+            }
+        }
+        assert_eq!(Easing::Cubic.apply(0.5), 0.5);
+
+        // Elastic overshoots before settling
+        assert!((1..20).any(|i| Easing::Elastic.apply(i as f32 / 20.0) > 1.0));
+    }
+
+    #[test]
+    fn test_tween() {
+        let mut tween = Tween::new(0.0, 1.0).easing(Easing::Cubic);
+        assert_eq!(tween.value(5.0), 0.0);
+        assert!(tween.is_done(5.0));
+
+        tween.trigger(10.0, 4.0);
+        assert_eq!(tween.value(10.0), 0.0);
+        assert_eq!(tween.value(10.5), 2.0);
+        assert!(!tween.is_done(10.5));
+        assert_eq!(tween.value(11.0), 4.0);
+        assert!(tween.is_done(11.0));
+
+        // Retriggering continues from the current value
+        tween.trigger(20.0, 8.0);
+        tween.trigger(20.5, 0.0);
+        assert_eq!(tween.value(20.5), 6.0);
+        assert_eq!(*tween.target(), 0.0);
+        assert_eq!(tween.value(30.0), 0.0);
+    }
+}
@@ -0,0 +1,204 @@
+//! Beat Timeline Logging
+//!
+//! Captures the beats of a session as `(time, confidence)` pairs, eg. to pre-analyze a track
+//! and edit its beat timeline for a choreographed show.  The log can be written as CSV
+//!
+//! ```text
+//! time,confidence
+//! 0.52,0.8
+//! ```
+//!
+//! or as JSON
+//!
+//! ```text
+//! [{"time":0.52,"confidence":0.8}]
+//! ```
+use crate::analyzer;
+use std::io;
+
+/// Output format of a beat log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A header line followed by one line per beat
+    Csv,
+    /// An array of objects
+    Json,
+}
+
+impl Format {
+    /// Guess the format from a file name, `.json` files are JSON and everything else is CSV
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Format {
+        match path.as_ref().extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Format::Json,
+            _ => Format::Csv,
+        }
+    }
+}
+
+/// A log of beats with their timestamps
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// # use vis_core::helpers::beatlog;
+/// # let samples = analyzer::SampleBuffer::new(32000, 8000);
+/// # let time = 0.0;
+/// # let beat = analyzer::BeatBuilder::new()
+/// #     .decay(2000.0)
+/// #     .trigger(0.4)
+/// #     .range(50.0, 100.0)
+/// #     .fourier_length(16)
+/// #     .downsample(10)
+/// #     .rate(8000)
+/// #     .volume_smoothing(8.0)
+/// #     .history(8)
+/// #     .build();
+/// let mut events = analyzer::Events::new().with(beat);
+/// let mut log = beatlog::BeatLog::new();
+///
+/// // Every analyzer cycle
+/// log.record_events(&events.update(&samples, time));
+///
+/// // At the end of the session
+/// log.write(std::io::stdout(), beatlog::Format::Csv).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BeatLog {
+    beats: Vec<(f32, analyzer::SignalStrength)>,
+}
+
+impl BeatLog {
+    /// Create an empty log
+    pub fn new() -> BeatLog {
+        Default::default()
+    }
+
+    /// Record a beat at `time` seconds
+    pub fn record(&mut self, time: f32, confidence: analyzer::SignalStrength) {
+        self.beats.push((time, confidence));
+    }
+
+    /// Record all beat events, other events are ignored
+    pub fn record_events(&mut self, events: &[analyzer::Event]) {
+        for e in events.iter() {
+            if e.kind == analyzer::EventKind::Beat {
+                self.record(e.time, e.strength);
+            }
+        }
+    }
+
+    /// Return the recorded `(time, confidence)` pairs
+    pub fn beats(&self) -> &[(f32, analyzer::SignalStrength)] {
+        &self.beats
+    }
+
+    /// Write the log in the given format
+    pub fn write<W: io::Write>(&self, mut writer: W, format: Format) -> io::Result<()> {
+        match format {
+            Format::Csv => {
+                writeln!(writer, "time,confidence")?;
+                for (time, confidence) in self.beats.iter() {
+                    writeln!(writer, "{},{}", time, confidence)?;
+                }
+            }
+            Format::Json => {
+                write!(writer, "[")?;
+                for (i, (time, confidence)) in self.beats.iter().enumerate() {
+                    if i > 0 {
+                        write!(writer, ",")?;
+                    }
+                    write!(
+                        writer,
+                        "{{\"time\":{},\"confidence\":{}}}",
+                        time, confidence
+                    )?;
+                }
+                writeln!(writer, "]")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the log to a file, choosing the format from its extension
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> io::Result<()> {
+        let format = Format::from_path(&path);
+        let file = std::fs::File::create(path)?;
+        self.write(io::BufWriter::new(file), format)?;
+
+        log::info!("Saved {} beats", self.beats.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session() {
+        let beat = analyzer::BeatBuilder::new()
+            .decay(2000.0)
+            .trigger(0.4)
+            .range(50.0, 100.0)
+            .fourier_length(16)
+            .downsample(10)
+            .rate(8000)
+            .volume_smoothing(8.0)
+            .history(8)
+            .build();
+        let mut events = analyzer::Events::new()
+            .with(beat)
+            .with(analyzer::events::ClipDetector::new(0.5));
+        let mut log = BeatLog::new();
+
+        // A 75 Hz tone pulsing twice a second for two seconds
+        let buf = analyzer::SampleBuffer::new(1000, 8000);
+        for i in 0..200 {
+            let samples = (0..80)
+                .map(|j| {
+                    let t = (i * 80 + j) as f32 / 8000.0;
+                    let envelope = (-(t % 0.5) * 20.0).exp();
+                    let s = (t * 75.0 * 2.0 * std::f32::consts::PI).sin() * envelope;
+                    [s, s]
+                })
+                .collect::<Vec<_>>();
+            buf.push(&samples);
+
+            log.record_events(&events.update(&buf, i as f32 * 0.01));
+        }
+
+        assert!(!log.beats().is_empty());
+        assert!(log.beats().windows(2).all(|w| w[0].0 < w[1].0));
+
+        let mut csv = Vec::new();
+        log.write(&mut csv, Format::Csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("time,confidence"));
+        for (line, (time, confidence)) in lines.zip(log.beats().iter()) {
+            assert_eq!(line, format!("{},{}", time, confidence));
+        }
+        assert_eq!(csv.lines().count(), log.beats().len() + 1);
+    }
+
+    #[test]
+    fn test_json() {
+        let mut log = BeatLog::new();
+        let mut buf = Vec::new();
+        log.write(&mut buf, Format::Json).unwrap();
+        assert_eq!(buf, b"[]\n");
+
+        log.record(0.5, 0.75);
+        log.record(1.0, 1.0);
+        buf.clear();
+        log.write(&mut buf, Format::Json).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "[{\"time\":0.5,\"confidence\":0.75},{\"time\":1,\"confidence\":1}]\n",
+        );
+
+        assert_eq!(Format::from_path("beats.JSON"), Format::Json);
+        assert_eq!(Format::from_path("beats.csv"), Format::Csv);
+    }
+}
@@ -0,0 +1,231 @@
+//! Camera Matrices
+//!
+//! Computes view and perspective matrices for GL visualizers without depending on a linear
+//! algebra library.  Matrices are column-major `[[f32; 4]; 4]`, which can be passed directly
+//! as uniforms.
+
+/// A column-major 4x4 matrix
+pub type Matrix = [[f32; 4]; 4];
+
+/// Smallest allowed near plane distance
+const MIN_NEAR: f32 = 1e-6;
+/// Allowed field of view range in degrees
+const FOV_RANGE: (f32, f32) = (1.0, 179.0);
+
+/// A camera looking along the y axis with z pointing up
+///
+/// The camera sits at `(0, -1, height)` and looks at `(0, 10, look_height)`.
+///
+/// # Example
+/// ```
+/// # use vis_core::helpers::camera;
+/// # vis_core::default_config();
+/// let camera = camera::Camera::from_config("noa.camera");
+///
+/// let view = camera.view();
+/// let perspective = camera.perspective(16.0 / 9.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    height: f32,
+    look_height: f32,
+    fov: f32,
+    near: f32,
+    far: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera::new(1.0, 0.8, 45.0, 0.001, 100.0)
+    }
+}
+
+impl Camera {
+    /// Create a new camera
+    ///
+    /// `fov` is the vertical field of view in degrees.  Parameters are clamped to sane values:
+    /// The field of view to `[1, 179]` degrees, `near` to be positive and `far` to be beyond
+    /// `near`.  A warning is logged if anything was clamped.
+    pub fn new(height: f32, look_height: f32, fov: f32, near: f32, far: f32) -> Camera {
+        let clamped_fov = fov.clamp(FOV_RANGE.0, FOV_RANGE.1);
+        if clamped_fov != fov {
+            log::warn!("Camera fov {} out of range, using {}", fov, clamped_fov);
+        }
+
+        let clamped_near = near.max(MIN_NEAR);
+        if clamped_near != near {
+            log::warn!(
+                "Camera near plane {} too close, using {}",
+                near,
+                clamped_near
+            );
+        }
+
+        let clamped_far = if far > clamped_near {
+            far
+        } else {
+            clamped_near * 1000.0
+        };
+        if clamped_far != far {
+            log::warn!(
+                "Camera far plane {} not beyond the near plane, using {}",
+                far,
+                clamped_far
+            );
+        }
+
+        Camera {
+            height,
+            look_height,
+            fov: clamped_fov,
+            near: clamped_near,
+            far: clamped_far,
+        }
+    }
+
+    /// Read a camera from config
+    ///
+    /// Reads `height`, `look_height`, `fov` (in degrees), `near` and `far` below `path`.
+    /// Missing keys use the defaults of the noambition camera.
+    pub fn from_config(path: &str) -> Camera {
+        let default = Camera::default();
+        let get =
+            |key: &str, default: f32| crate::CONFIG.get_or(&format!("{}.{}", path, key), default);
+
+        let camera = Camera::new(
+            get("height", default.height),
+            get("look_height", default.look_height),
+            get("fov", default.fov),
+            get("near", default.near),
+            get("far", default.far),
+        );
+
+        log::debug!("Camera ({:?}):", path);
+        log::debug!("    Height      = {}", camera.height);
+        log::debug!("    Look Height = {}", camera.look_height);
+        log::debug!("    FOV         = {}°", camera.fov);
+        log::debug!("    Near        = {}", camera.near);
+        log::debug!("    Far         = {}", camera.far);
+
+        camera
+    }
+
+    /// Return the vertical field of view in degrees
+    pub fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    /// Return the near plane distance
+    pub fn near(&self) -> f32 {
+        self.near
+    }
+
+    /// Return the far plane distance
+    pub fn far(&self) -> f32 {
+        self.far
+    }
+
+    /// Compute the right-handed view matrix
+    pub fn view(&self) -> Matrix {
+        look_at_rh(
+            [0.0, -1.0, self.height],
+            [0.0, 10.0, self.look_height],
+            [0.0, 0.0, 1.0],
+        )
+    }
+
+    /// Compute the perspective matrix for a viewport with the given aspect ratio
+    pub fn perspective(&self, aspect: f32) -> Matrix {
+        let f = 1.0 / (self.fov.to_radians() / 2.0).tan();
+        let depth = self.near - self.far;
+
+        let mut m = [[0.0; 4]; 4];
+        m[0][0] = f / aspect;
+        m[1][1] = f;
+        m[2][2] = (self.far + self.near) / depth;
+        m[2][3] = -1.0;
+        m[3][2] = 2.0 * self.far * self.near / depth;
+        m
+    }
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let len = dot(a, a).sqrt();
+    [a[0] / len, a[1] / len, a[2] / len]
+}
+
+fn look_at_rh(eye: [f32; 3], target: [f32; 3], up: [f32; 3]) -> Matrix {
+    let f = normalize(sub(target, eye));
+    let s = normalize(cross(f, up));
+    let u = cross(s, f);
+
+    [
+        [s[0], u[0], -f[0], 0.0],
+        [s[1], u[1], -f[1], 0.0],
+        [s[2], u[2], -f[2], 0.0],
+        [-dot(s, eye), -dot(u, eye), dot(f, eye), 1.0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_matrix_eq(a: Matrix, b: Matrix) {
+        for (ca, cb) in a.iter().zip(b.iter()) {
+            for (va, vb) in ca.iter().zip(cb.iter()) {
+                assert!((va - vb).abs() < 1e-5, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nalgebra() {
+        for &(height, look, fov, near, far, aspect) in &[
+            (1.0, 0.8, 45.0, 0.001, 100.0, 16.0 / 9.0),
+            (2.5, -0.3, 90.0, 0.1, 50.0, 4.0 / 3.0),
+            (0.0, 0.0, 30.0, 1.0, 2.0, 0.5),
+        ] {
+            let camera = Camera::new(height, look, fov, near, far);
+
+            let view = nalgebra::Matrix4::look_at_rh(
+                &nalgebra::Point3::new(0.0, -1.0, height),
+                &nalgebra::Point3::new(0.0, 10.0, look),
+                &nalgebra::Vector3::new(0.0, 0.0, 1.0),
+            );
+            assert_matrix_eq(camera.view(), view.into());
+
+            let perspective =
+                nalgebra::Matrix4::new_perspective(aspect, fov.to_radians(), near, far);
+            assert_matrix_eq(camera.perspective(aspect), perspective.into());
+        }
+    }
+
+    #[test]
+    fn test_clamp() {
+        let camera = Camera::new(1.0, 0.8, 270.0, -1.0, -5.0);
+        assert_eq!(camera.fov(), 179.0);
+        assert!(camera.near() > 0.0);
+        assert!(camera.far() > camera.near());
+
+        let camera = Camera::new(1.0, 0.8, 0.0, 0.5, 0.5);
+        assert_eq!(camera.fov(), 1.0);
+        assert!(camera.far() > camera.near());
+    }
+}
@@ -0,0 +1,125 @@
+//! Binary Frame Format
+//!
+//! A compact little-endian encoding of analysis results for low-overhead IPC, eg. with a
+//! separate rendering process reading from a pipe.  Each frame is laid out as
+//!
+//! ```text
+//! [time: f32][volume: f32][beat: u8][n: u16][spectrum: f32 * n]
+//! ```
+//!
+//! # Example
+//! ```
+//! # use vis_core::helpers::codec;
+//! let mut pipe = Vec::new();
+//! codec::encode_frame(&mut pipe, 1.5, 0.3, 1, &[0.1, 0.2, 0.3]).unwrap();
+//!
+//! let frame = codec::decode_frame(&mut &pipe[..]).unwrap();
+//! assert_eq!(frame.spectrum, [0.1, 0.2, 0.3]);
+//! ```
+use std::io;
+
+/// A decoded frame
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FrameData {
+    /// Timestamp in seconds
+    pub time: f32,
+    /// Volume
+    pub volume: f32,
+    /// Beat indicator, eg. a flag or the beat counter modulo 256
+    pub beat: u8,
+    /// Spectrum buckets
+    pub spectrum: Vec<f32>,
+}
+
+/// Write one frame to `writer`
+///
+/// Fails with `InvalidInput` if the spectrum has more than `u16::MAX` buckets.
+pub fn encode_frame<W: io::Write>(
+    writer: &mut W,
+    time: f32,
+    volume: f32,
+    beat: u8,
+    spectrum: &[f32],
+) -> io::Result<()> {
+    let n = u16::try_from(spectrum.len()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Spectrum too long for frame ({} buckets)", spectrum.len()),
+        )
+    })?;
+
+    let mut buf = Vec::with_capacity(11 + spectrum.len() * 4);
+    buf.extend_from_slice(&time.to_le_bytes());
+    buf.extend_from_slice(&volume.to_le_bytes());
+    buf.push(beat);
+    buf.extend_from_slice(&n.to_le_bytes());
+    for v in spectrum.iter() {
+        buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    writer.write_all(&buf)
+}
+
+/// Read one frame from `reader`
+///
+/// Fails with `UnexpectedEof` if the frame is truncated.
+pub fn decode_frame<R: io::Read>(reader: &mut R) -> io::Result<FrameData> {
+    let mut header = [0; 11];
+    reader.read_exact(&mut header)?;
+
+    let f32_at = |bytes: &[u8]| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let n = u16::from_le_bytes([header[9], header[10]]) as usize;
+
+    let mut body = vec![0; n * 4];
+    reader.read_exact(&mut body)?;
+
+    Ok(FrameData {
+        time: f32_at(&header[0..4]),
+        volume: f32_at(&header[4..8]),
+        beat: header[8],
+        spectrum: body.chunks_exact(4).map(f32_at).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let frames = [
+            FrameData {
+                time: 0.25,
+                volume: 0.5,
+                beat: 3,
+                spectrum: vec![1.0, -2.5, f32::MAX],
+            },
+            FrameData::default(),
+        ];
+
+        let mut buf = Vec::new();
+        for f in frames.iter() {
+            encode_frame(&mut buf, f.time, f.volume, f.beat, &f.spectrum).unwrap();
+        }
+        assert_eq!(buf.len(), 11 + 3 * 4 + 11);
+        assert_eq!(buf[..11], [0, 0, 0x80, 0x3e, 0, 0, 0, 0x3f, 3, 3, 0]);
+
+        let mut reader = &buf[..];
+        for f in frames.iter() {
+            assert_eq!(&decode_frame(&mut reader).unwrap(), f);
+        }
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut buf = Vec::new();
+        let err = encode_frame(&mut buf, 0.0, 0.0, 0, &vec![0.0; 70000]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(buf.is_empty());
+
+        encode_frame(&mut buf, 0.0, 0.0, 0, &[1.0, 2.0]).unwrap();
+        let err = decode_frame(&mut &buf[..buf.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,120 @@
+//! Response Curves
+//!
+//! Shape values like volume before mapping them to visuals or outputs.  All curves map
+//! `[0, 1]` onto `[0, 1]`; inputs outside this range are clamped.
+use ezconf::toml;
+
+/// A named response curve
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+    /// Unchanged
+    Linear,
+    /// Square root, boosts quiet values
+    Sqrt,
+    /// Logarithmic, boosts quiet values more than `Sqrt`
+    Log,
+    /// S-shaped, pushes values away from the middle
+    Sigmoid,
+    /// `x^gamma`, boosts quiet values for `gamma < 1`
+    Gamma(f32),
+}
+
+impl Curve {
+    /// Parse a curve name
+    ///
+    /// Valid names are `"linear"`, `"sqrt"`, `"log"` and `"sigmoid"`.
+    pub fn from_name(name: &str) -> Option<Curve> {
+        match name {
+            "linear" => Some(Curve::Linear),
+            "sqrt" => Some(Curve::Sqrt),
+            "log" => Some(Curve::Log),
+            "sigmoid" => Some(Curve::Sigmoid),
+            _ => None,
+        }
+    }
+
+    /// Read a curve from config or return a default
+    ///
+    /// The value is either a curve name or a number, which is used as the gamma exponent.
+    ///
+    /// # Example
+    /// ```
+    /// # use vis_core::helpers::curve;
+    /// # vis_core::default_config();
+    /// let curve = curve::Curve::from_config("foo.volume_curve", curve::Curve::Gamma(0.6));
+    /// let volume = curve.apply(0.3);
+    /// ```
+    pub fn from_config(path: &str, default: Curve) -> Curve {
+        match crate::CONFIG.get::<toml::Value>(path) {
+            None => default,
+            Some(toml::Value::Float(gamma)) => Curve::Gamma(gamma as f32),
+            Some(toml::Value::Integer(gamma)) => Curve::Gamma(gamma as f32),
+            Some(toml::Value::String(ref name)) => Curve::from_name(name)
+                .unwrap_or_else(|| panic!("Unknown curve {:?} for {:?}!", name, path)),
+            Some(other) => panic!("Invalid curve {} for {:?}!", other, path),
+        }
+    }
+
+    /// Apply this curve to a value
+    pub fn apply(&self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+
+        match *self {
+            Curve::Linear => x,
+            Curve::Sqrt => x.sqrt(),
+            Curve::Log => (1.0 + 9.0 * x).log10(),
+            Curve::Sigmoid => {
+                let s = |x: f32| 1.0 / (1.0 + (-10.0 * (x - 0.5)).exp());
+                (s(x) - s(0.0)) / (s(1.0) - s(0.0))
+            }
+            Curve::Gamma(gamma) => x.powf(gamma),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_curves() {
+        for curve in [
+            Curve::Linear,
+            Curve::Sqrt,
+            Curve::Log,
+            Curve::Sigmoid,
+            Curve::Gamma(0.6),
+            Curve::Gamma(2.2),
+        ] {
+            assert_close(curve.apply(0.0), 0.0);
+            assert_close(curve.apply(1.0), 1.0);
+            assert_close(curve.apply(-1.0), 0.0);
+            assert_close(curve.apply(2.0), 1.0);
+
+            let mut last = 0.0;
+            for i in 1..=10 {
+                let y = curve.apply(i as f32 / 10.0);
+                assert!(y > last, "{:?} is not monotonic", curve);
+                last = y;
+            }
+        }
+
+        assert_close(Curve::Linear.apply(0.3), 0.3);
+        assert_close(Curve::Sqrt.apply(0.25), 0.5);
+        assert!(Curve::Log.apply(0.25) > Curve::Sqrt.apply(0.25));
+        assert_close(Curve::Sigmoid.apply(0.5), 0.5);
+        assert!(Curve::Sigmoid.apply(0.2) < 0.2);
+        assert!(Curve::Sigmoid.apply(0.8) > 0.8);
+        assert_close(Curve::Gamma(2.0).apply(0.5), 0.25);
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Curve::from_name("sqrt"), Some(Curve::Sqrt));
+        assert_eq!(Curve::from_name("cubic"), None);
+    }
+}
@@ -0,0 +1,210 @@
+//! Graphics Helpers
+use notify::Watcher;
+use std::{fs, io, path, sync::mpsc};
+
+/// Live reloading of shader programs
+///
+/// Watches the shader source files and recompiles the program whenever one of them changes.
+/// If compilation fails, the error is logged and the previous program is kept, so a typo
+/// doesn't kill a running visualizer.
+///
+/// The reloader does not depend on a graphics library; compiling is done by a closure which
+/// receives the sources in the order the paths were given.
+///
+/// # Example
+/// ```no_run
+/// # use vis_core::helpers::gl;
+/// # fn compile(_: &str, _: &str) -> Result<(), String> { Ok(()) }
+/// let mut shader = gl::ShaderReloader::new(
+///     &["shaders/pp.vert", "shaders/background.frag"],
+///     |sources| compile(&sources[0], &sources[1]),
+/// )
+/// .expect("Can't load shader");
+///
+/// // In the render loop
+/// shader.update();
+/// let program = shader.program();
+/// ```
+pub struct ShaderReloader<P, F> {
+    paths: Vec<path::PathBuf>,
+    compile: F,
+    program: P,
+    last_error: Option<String>,
+
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl<P, F> std::fmt::Debug for ShaderReloader<P, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ShaderReloader")
+            .field("paths", &self.paths)
+            .field("last_error", &self.last_error)
+            .finish()
+    }
+}
+
+impl<P, E: std::fmt::Display, F: FnMut(&[String]) -> Result<P, E>> ShaderReloader<P, F> {
+    /// Compile the program from `paths` and watch them for changes
+    ///
+    /// Fails if a file can't be read or watched or if the initial compilation fails.
+    pub fn new<Q: AsRef<path::Path>>(paths: &[Q], mut compile: F) -> io::Result<Self> {
+        let paths = paths
+            .iter()
+            .map(|p| fs::canonicalize(p.as_ref()))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let program = compile(&read_sources(&paths)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+        // Watch the directories as editors often replace files instead of writing them
+        for dir in paths.iter().filter_map(|p| p.parent()) {
+            watcher
+                .watch(dir, notify::RecursiveMode::NonRecursive)
+                .map_err(watch_error)?;
+        }
+
+        log::debug!("ShaderReloader:");
+        for p in paths.iter() {
+            log::debug!("    Source      = {:?}", p);
+        }
+
+        Ok(ShaderReloader {
+            paths,
+            compile,
+            program,
+            last_error: None,
+
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    /// Recompile if a source changed since the last call
+    ///
+    /// Returns true if a new program was compiled.
+    pub fn update(&mut self) -> bool {
+        let mut changed = false;
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) => {
+                    changed |= event
+                        .paths
+                        .iter()
+                        .any(|p| self.paths.iter().any(|q| p == q))
+                }
+                Err(e) => log::warn!("Watching shaders failed: {}", e),
+            }
+        }
+
+        changed && self.reload()
+    }
+
+    /// Recompile unconditionally
+    ///
+    /// Returns true if a new program was compiled.
+    pub fn reload(&mut self) -> bool {
+        let result = read_sources(&self.paths)
+            .map_err(|e| e.to_string())
+            .and_then(|sources| (self.compile)(&sources).map_err(|e| e.to_string()));
+
+        match result {
+            Ok(program) => {
+                log::info!("Reloaded shader {:?}", self.paths);
+                self.program = program;
+                self.last_error = None;
+                true
+            }
+            Err(e) => {
+                log::error!("Failed to reload shader {:?}:\n{}", self.paths, e);
+                self.last_error = Some(e);
+                false
+            }
+        }
+    }
+}
+
+impl<P, F> ShaderReloader<P, F> {
+    /// Return the current program
+    pub fn program(&self) -> &P {
+        &self.program
+    }
+
+    /// Return the error of the last reload, if it failed
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+}
+
+fn read_sources(paths: &[path::PathBuf]) -> io::Result<Vec<String>> {
+    paths.iter().map(fs::read_to_string).collect()
+}
+
+fn watch_error(e: notify::Error) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time;
+
+    fn wait_for<P, F>(
+        shader: &mut ShaderReloader<P, F>,
+        mut done: impl FnMut(&ShaderReloader<P, F>) -> bool,
+    ) where
+        F: FnMut(&[String]) -> Result<P, String>,
+    {
+        let start = time::Instant::now();
+        while !done(shader) {
+            assert!(
+                start.elapsed() < time::Duration::from_secs(5),
+                "Shader was not reloaded"
+            );
+            std::thread::sleep(time::Duration::from_millis(5));
+            shader.update();
+        }
+    }
+
+    #[test]
+    fn test_reload() {
+        let dir = std::env::temp_dir().join(format!("vis-core-shader-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("test.frag");
+        fs::write(&file, "v1").unwrap();
+
+        // Writes might be observed half-done, so treat empty sources as broken as well
+        let mut shader = ShaderReloader::new(&[&file], |sources: &[String]| {
+            if sources[0].is_empty() || sources[0].contains("error") {
+                Err(format!("Syntax error in {:?}", sources[0]))
+            } else {
+                Ok(sources[0].clone())
+            }
+        })
+        .unwrap();
+        assert_eq!(shader.program(), "v1");
+        assert!(!shader.update());
+
+        fs::write(&file, "v2").unwrap();
+        wait_for(&mut shader, |s| s.program() == "v2");
+
+        // Keep the old program on errors
+        fs::write(&file, "error").unwrap();
+        wait_for(&mut shader, |s| s.last_error().is_some());
+        assert_eq!(shader.program(), "v2");
+
+        fs::write(&file, "v3").unwrap();
+        wait_for(&mut shader, |s| s.program() == "v3");
+        assert_eq!(shader.last_error(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_initial_error() {
+        let missing = std::env::temp_dir().join("vis-core-shader-missing.frag");
+        assert!(ShaderReloader::new(&[&missing], |_: &[String]| Ok::<_, String>(())).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+//! Hysteresis
+
+/// A gate with separate thresholds for opening and closing (Schmitt trigger)
+///
+/// The gate opens once a value reaches the `on` threshold and only closes again when a value
+/// drops below the lower `off` threshold.  Values dithering between both thresholds keep the
+/// current state instead of rapidly toggling it.
+///
+/// # Example
+/// ```
+/// # use vis_core::helpers::hysteresis;
+/// let mut gate = hysteresis::Gate::new(0.8, 0.6);
+///
+/// assert!(!gate.update(0.7));
+/// assert!(gate.update(0.9));
+/// assert!(gate.update(0.7));
+/// assert!(!gate.update(0.5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Gate {
+    on: f32,
+    off: f32,
+    open: bool,
+}
+
+impl Gate {
+    /// Create a new closed gate
+    ///
+    /// `off` must not be greater than `on`.
+    pub fn new(on: f32, off: f32) -> Gate {
+        assert!(off <= on, "Off threshold is above on threshold!");
+
+        Gate {
+            on,
+            off,
+            open: false,
+        }
+    }
+
+    /// Feed the next value and return whether the gate is open
+    pub fn update(&mut self, value: f32) -> bool {
+        if self.open {
+            self.open = value >= self.off;
+        } else {
+            self.open = value >= self.on;
+        }
+
+        self.open
+    }
+
+    /// Return whether the gate is open
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gate() {
+        let mut gate = Gate::new(0.8, 0.6);
+
+        // Dithering below the on threshold keeps the gate closed
+        for v in [0.5, 0.7, 0.65, 0.79, 0.7] {
+            assert!(!gate.update(v));
+        }
+
+        assert!(gate.update(0.8));
+
+        // Dithering above the off threshold keeps the gate open
+        for v in [0.7, 0.79, 0.6, 0.75, 0.9, 0.61] {
+            assert!(gate.update(v));
+        }
+
+        assert!(!gate.update(0.59));
+        assert!(!gate.is_open());
+    }
+}
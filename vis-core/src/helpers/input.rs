@@ -0,0 +1,263 @@
+//! Key Bindings
+//!
+//! Maps key presses to named actions, so all visualizers share one configurable control
+//! scheme.  Keys are identified by name, independent of the windowing library.  For
+//! libraries with a key enum (like glutin's `VirtualKeyCode` or sfml's `Key`), the name of
+//! the variant is used, so [`KeyMap::key`](struct.KeyMap.html#method.key) can take the key
+//! directly.
+//!
+//! Bindings are read from config as arrays of key names per action:
+//!
+//! ```toml
+//! [input.keys]
+//! quit = ["Escape", "Q"]
+//! pause = ["Space"]
+//! reload = ["R"]
+//! screenshot = ["F12"]
+//! ```
+use crate::config::{self, ConfigError};
+use ezconf::toml;
+
+/// Actions triggered by keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Close the visualizer
+    Quit,
+    /// Freeze the visuals
+    Pause,
+    /// Reload shaders or other resources
+    Reload,
+    /// Save the current frame
+    Screenshot,
+}
+
+impl Action {
+    /// All actions
+    pub const ALL: [Action; 4] = [
+        Action::Quit,
+        Action::Pause,
+        Action::Reload,
+        Action::Screenshot,
+    ];
+
+    /// Return the config name of this action
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Pause => "pause",
+            Action::Reload => "reload",
+            Action::Screenshot => "screenshot",
+        }
+    }
+
+    /// Parse an action name
+    ///
+    /// Valid names are `"quit"`, `"pause"`, `"reload"` and `"screenshot"`.
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|a| a.name() == name)
+    }
+
+    /// Return the keys bound to this action by default
+    ///
+    /// Only quitting is bound by default, as not every visualizer handles the other actions.
+    /// Bind them in config for visualizers which do.
+    pub fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["Escape"],
+            Action::Pause | Action::Reload | Action::Screenshot => &[],
+        }
+    }
+}
+
+/// Mapping from key names to actions
+///
+/// # Example
+/// ```
+/// # use vis_core::helpers::input;
+/// # vis_core::default_config();
+/// let keys = input::KeyMap::from_config("input.keys");
+///
+/// // In the event loop
+/// # #[derive(Debug)]
+/// # enum VirtualKeyCode { Escape }
+/// # let key = VirtualKeyCode::Escape;
+/// if keys.key(&key) == Some(input::Action::Quit) {
+///     // Close the window
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyMap {
+    bindings: Vec<(String, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> KeyMap {
+        let mut map = KeyMap::empty();
+        for action in Action::ALL.iter() {
+            for key in action.default_keys() {
+                map.bind(key, *action);
+            }
+        }
+        map
+    }
+}
+
+impl KeyMap {
+    /// Create a map without any bindings
+    pub fn empty() -> KeyMap {
+        KeyMap {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Read bindings from config
+    ///
+    /// Actions missing from the config keep their default keys.  Panics with a descriptive
+    /// message if the config has the wrong shape or names an unknown action.
+    pub fn from_config(path: &str) -> KeyMap {
+        match crate::CONFIG.get::<toml::Value>(path) {
+            None => KeyMap::default(),
+            Some(value) => KeyMap::from_value(path, value).unwrap_or_else(|e| {
+                log::error!("{}", e);
+                panic!("{}", e);
+            }),
+        }
+    }
+
+    /// Parse bindings from a table of action names to arrays of key names
+    ///
+    /// Actions missing from the table keep their default keys.
+    pub fn from_value(path: &str, value: toml::Value) -> Result<KeyMap, ConfigError> {
+        let table = match value {
+            toml::Value::Table(table) => table,
+            other => {
+                return Err(ConfigError {
+                    path: path.to_string(),
+                    index: None,
+                    expected: "table",
+                    found: other.to_string(),
+                })
+            }
+        };
+
+        let mut map = KeyMap::default();
+        for (name, keys) in table.into_iter() {
+            let key_path = format!("{}.{}", path, name);
+            let action = Action::from_name(&name).ok_or_else(|| ConfigError {
+                path: key_path.clone(),
+                index: None,
+                expected: "action name",
+                found: name.clone(),
+            })?;
+            let keys = config::parse_vec::<String>(&key_path, keys)?;
+
+            map.bindings.retain(|(_, a)| *a != action);
+            for key in keys.iter() {
+                map.bind(key, action);
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Bind `key` to `action`, replacing any previous binding of `key`
+    pub fn bind(&mut self, key: &str, action: Action) {
+        self.bindings.retain(|(k, _)| k != key);
+        self.bindings.push((key.to_string(), action));
+    }
+
+    /// Return the action bound to the key named `name`
+    pub fn action(&self, name: &str) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, a)| *a)
+    }
+
+    /// Return the action bound to a key of a windowing library
+    ///
+    /// The key is named after its `Debug` representation, eg. `"Escape"` for
+    /// `VirtualKeyCode::Escape`.
+    pub fn key<K: std::fmt::Debug>(&self, key: &K) -> Option<Action> {
+        self.action(&format!("{:?}", key))
+    }
+
+    /// Return the names of all keys bound to `action`
+    pub fn keys(&self, action: Action) -> impl Iterator<Item = &str> {
+        self.bindings
+            .iter()
+            .filter(move |(_, a)| *a == action)
+            .map(|(k, _)| k.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(s: &str) -> toml::Value {
+        s.parse::<toml::Value>().unwrap()["keys"].clone()
+    }
+
+    #[derive(Debug)]
+    enum Key {
+        Escape,
+        Q,
+        Space,
+        P,
+    }
+
+    #[test]
+    fn test_from_value() {
+        let keys = KeyMap::from_value(
+            "keys",
+            value("[keys]\nquit = [\"Escape\", \"Q\"]\npause = [\"P\"]"),
+        )
+        .unwrap();
+
+        assert_eq!(keys.key(&Key::Escape), Some(Action::Quit));
+        assert_eq!(keys.key(&Key::Q), Some(Action::Quit));
+        assert_eq!(keys.key(&Key::P), Some(Action::Pause));
+        assert_eq!(keys.key(&Key::Space), None);
+        assert_eq!(keys.keys(Action::Quit).collect::<Vec<_>>(), ["Escape", "Q"]);
+
+        // Actions missing from config keep their defaults
+        let keys = KeyMap::from_value("keys", value("[keys]\npause = [\"Space\"]")).unwrap();
+        assert_eq!(keys.key(&Key::Escape), Some(Action::Quit));
+        assert_eq!(keys.key(&Key::Space), Some(Action::Pause));
+    }
+
+    #[test]
+    fn test_default() {
+        let keys = KeyMap::default();
+        assert_eq!(keys.key(&Key::Escape), Some(Action::Quit));
+
+        // Only actions every visualizer handles are bound
+        for action in [Action::Pause, Action::Reload, Action::Screenshot] {
+            assert_eq!(keys.keys(action).count(), 0);
+        }
+    }
+
+    #[test]
+    fn test_from_value_malformed() {
+        let err = KeyMap::from_value("keys", value("[keys]\nfly = [\"F\"]")).unwrap_err();
+        assert_eq!(err.path, "keys.fly");
+
+        let err = KeyMap::from_value("keys", value("[keys]\nquit = [1]")).unwrap_err();
+        assert_eq!(err.path, "keys.quit");
+        assert_eq!(err.index, Some(0));
+
+        assert!(KeyMap::from_value("keys", value("keys = 1")).is_err());
+    }
+
+    #[test]
+    fn test_bind() {
+        let mut keys = KeyMap::empty();
+        assert_eq!(keys.action("Escape"), None);
+
+        keys.bind("Escape", Action::Quit);
+        keys.bind("Escape", Action::Pause);
+        assert_eq!(keys.action("Escape"), Some(Action::Pause));
+        assert_eq!(keys.keys(Action::Quit).count(), 0);
+    }
+}
@@ -0,0 +1,92 @@
+//! Frame Time Smoothing
+use std::collections;
+
+/// Median filter for frame deltas
+///
+/// Frame times jitter with scheduling, so the delta between two frames is sometimes much
+/// longer or shorter than the actual frame rate.  Animations driven by such deltas stutter.
+/// The filter replaces each delta with the median of the last few, which ignores single
+/// spikes.  Time lost or gained this way is fed back slowly, so the filtered deltas still
+/// add up to the elapsed time.
+///
+/// # Example
+/// ```
+/// # use vis_core::helpers::jitter;
+/// let mut filter = jitter::DeltaFilter::new(5);
+///
+/// for delta in [0.016, 0.016, 0.016, 0.05, 0.016] {
+///     let smooth = filter.filter(delta);
+/// #   assert!(smooth < 0.03);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DeltaFilter {
+    window: usize,
+    deltas: collections::VecDeque<f32>,
+    sorted: Vec<f32>,
+    drift: f32,
+}
+
+impl DeltaFilter {
+    /// Create a filter over the last `window` deltas
+    pub fn new(window: usize) -> DeltaFilter {
+        assert!(window > 0, "Window must not be empty!");
+
+        DeltaFilter {
+            window,
+            deltas: collections::VecDeque::with_capacity(window),
+            sorted: Vec::with_capacity(window),
+            drift: 0.0,
+        }
+    }
+
+    /// Feed the raw delta of a frame and return the filtered one
+    pub fn filter(&mut self, delta: f32) -> f32 {
+        if self.deltas.len() == self.window {
+            self.deltas.pop_front();
+        }
+        self.deltas.push_back(delta);
+
+        self.sorted.clear();
+        self.sorted.extend(self.deltas.iter().copied());
+        self.sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let median = self.sorted[self.sorted.len() / 2];
+
+        // Catch up on the difference over the next frames
+        self.drift += delta - median;
+        let correction = self.drift / self.window as f32;
+        self.drift -= correction;
+
+        median + correction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spikes() {
+        let mut filter = DeltaFilter::new(5);
+
+        // 60 fps with every 7th frame late and the next one early to catch up
+        let raw = (0..700)
+            .map(|i| match i % 7 {
+                5 => 0.016 + 0.012,
+                6 => 0.016 - 0.012,
+                _ => 0.016,
+            })
+            .collect::<Vec<f32>>();
+        let smooth = raw.iter().map(|d| filter.filter(*d)).collect::<Vec<_>>();
+
+        let max_jump = |v: &[f32]| {
+            v.windows(2)
+                .map(|w| (w[1] - w[0]).abs())
+                .fold(0.0f32, f32::max)
+        };
+        assert!(max_jump(&smooth[10..]) < max_jump(&raw[10..]) / 4.0);
+
+        let total = |v: &[f32]| v.iter().sum::<f32>();
+        assert!((total(&smooth) - total(&raw)).abs() < 0.01);
+    }
+}
@@ -0,0 +1,99 @@
+//! Terminal Volume Meter
+//!
+//! A quick sanity check of the audio pipeline without any graphics code.
+use crate::analyzer;
+use crate::helpers::anim;
+use std::io::Write;
+
+#[derive(Debug, Clone, Default)]
+struct MeterInfo {
+    volume: analyzer::SignalStrength,
+    beat: u64,
+}
+
+/// Render one line of the meter
+///
+/// `volume` in `[0, 1]` is shown as a bar of `width` characters, `beat` in `[0, 1]` as an
+/// indicator after it.
+///
+/// # Example
+/// ```
+/// # use vis_core::helpers::meter;
+/// assert_eq!(meter::render(0.5, 1.0, 8), "[####    ] *");
+/// ```
+pub fn render(volume: analyzer::SignalStrength, beat: f32, width: usize) -> String {
+    let filled = (volume.clamp(0.0, 1.0) * width as f32).round() as usize;
+    let indicator = if beat > 0.5 {
+        '*'
+    } else if beat > 0.0 {
+        '.'
+    } else {
+        ' '
+    };
+
+    format!(
+        "[{}{}] {}",
+        "#".repeat(filled),
+        " ".repeat(width - filled),
+        indicator
+    )
+}
+
+/// Capture audio and print a volume meter and beat indicator until interrupted
+///
+/// Uses the default recorder, so the config has to be initialized first.
+pub fn run() {
+    let mut beat = analyzer::BeatBuilder::new().build();
+    let mut beat_num = 0;
+
+    let mut frames = crate::Visualizer::new(MeterInfo::default(), move |info, samples| {
+        info.volume = samples.volume_normalized(0.3, -30.0);
+        if beat.detect(samples) {
+            beat_num += 1;
+        }
+        info.beat = beat_num;
+
+        info
+    })
+    .frames();
+
+    let mut beat_edge = anim::BeatEdge::new();
+    let flash = anim::Flash::new(0.3);
+    let mut last_beat = -100.0;
+
+    let stdout = std::io::stdout();
+    for frame in frames.iter() {
+        let volume = frame.info(|info| {
+            if beat_edge.update(info.beat) {
+                last_beat = frame.time;
+            }
+            info.volume
+        });
+
+        let line = render(volume, flash.intensity(frame.time - last_beat), 60);
+        let mut out = stdout.lock();
+        if write!(out, "\r{}", line).and_then(|_| out.flush()).is_err() {
+            break;
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(30));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        assert_eq!(render(0.0, 0.0, 4), "[    ]  ");
+        assert_eq!(render(1.0, 0.0, 4), "[####]  ");
+        assert_eq!(render(0.5, 0.3, 4), "[##  ] .");
+        assert_eq!(render(2.0, 1.0, 4), "[####] *");
+        assert_eq!(render(-1.0, 0.0, 2), "[  ]  ");
+
+        for volume in 0..=10 {
+            assert_eq!(render(volume as f32 / 10.0, 0.0, 10).len(), 14);
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use std::time;
+
+pub mod anim;
+pub mod beatlog;
+pub mod camera;
+pub mod codec;
+pub mod curve;
+#[cfg(feature = "shader-reload")]
+pub mod gl;
+pub mod hysteresis;
+pub mod input;
+pub mod jitter;
+pub mod meter;
+#[cfg(feature = "osc")]
+pub mod osc;
+pub mod scroll;
+
+#[doc(inline)]
+pub use self::scroll::ScrollBuffer;
+
+pub fn time(start: time::Instant) -> f32 {
+    let elapsed = time::Instant::now() - start;
+
+    elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 * 1e-9
+}
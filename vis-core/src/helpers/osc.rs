@@ -0,0 +1,347 @@
+//! OSC Output
+//!
+//! Sends analyzer results as [OSC](https://opensoundcontrol.stsci.edu/spec-1_0.html) messages
+//! over UDP, eg. to drive TouchDesigner or Resolume.  With the default prefix, the messages
+//! are
+//!
+//! ```text
+//! /vis/volume f        Volume
+//! /vis/beat i          Number of the latest beat
+//! /vis<address> f f …  One float per spectrum bucket
+//! ```
+use crate::analyzer;
+use std::collections;
+use std::io;
+use std::net;
+use std::time;
+
+/// An OSC argument
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Arg {
+    Int(i32),
+    Float(f32),
+}
+
+/// Append `s` as an OSC string, null terminated and padded to a multiple of 4 bytes
+fn push_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(s.as_bytes());
+    buf.resize(buf.len() + 4 - s.len() % 4, 0);
+}
+
+/// Encode a message to `address` into `buf`
+fn encode<I: ExactSizeIterator<Item = Arg> + Clone>(buf: &mut Vec<u8>, address: &str, args: I) {
+    buf.clear();
+    push_str(buf, address);
+
+    let mut tags = String::with_capacity(args.len() + 1);
+    tags.push(',');
+    tags.extend(args.clone().map(|arg| match arg {
+        Arg::Int(_) => 'i',
+        Arg::Float(_) => 'f',
+    }));
+    push_str(buf, &tags);
+
+    for arg in args {
+        match arg {
+            Arg::Int(i) => buf.extend_from_slice(&i.to_be_bytes()),
+            Arg::Float(f) => buf.extend_from_slice(&f.to_be_bytes()),
+        }
+    }
+}
+
+/// Sender for OSC messages
+///
+/// The socket is non-blocking, so sending from inside the frame loop never stalls a frame.
+/// If the network can't keep up or nobody listens, messages are dropped silently: OSC
+/// receivers only care about the latest values anyway.
+///
+/// For receivers which can't handle a message per frame, limit the rate with
+/// [`max_rate`](#method.max_rate) and the size of spectra with [`buckets`](#method.buckets).
+///
+/// # Example
+/// ```
+/// # use vis_core::analyzer;
+/// # use vis_core::helpers::osc;
+/// # let samples = analyzer::SampleBuffer::new(32000, 8000);
+/// # let spectrum = analyzer::Spectrum::new(vec![0.0; 10], 220.0, 660.0);
+/// let mut sender = osc::OscSender::connect("127.0.0.1:9000").unwrap();
+///
+/// // Every frame
+/// sender.send_volume(samples.volume(0.3));
+/// sender.send_beat(12);
+/// sender.send_spectrum(&spectrum, "/notes");
+/// ```
+#[derive(Debug)]
+pub struct OscSender {
+    socket: net::UdpSocket,
+    prefix: String,
+    buf: Vec<u8>,
+
+    /// Minimum time between two messages to the same address
+    interval: Option<time::Duration>,
+    last_sent: collections::HashMap<String, time::Instant>,
+
+    /// Number of buckets spectra are reduced to
+    buckets: Option<usize>,
+    reduced: Vec<analyzer::SignalStrength>,
+}
+
+impl OscSender {
+    /// Create a sender for the receiver listening at `addr`
+    ///
+    /// The prefix defaults to `"/vis"`.
+    pub fn connect<A: net::ToSocketAddrs>(addr: A) -> io::Result<OscSender> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "No address to send OSC to")
+        })?;
+        let local: net::SocketAddr = if addr.is_ipv4() {
+            (net::Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (net::Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+
+        let socket = net::UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        socket.set_nonblocking(true)?;
+
+        log::debug!("OSC:");
+        log::debug!("    Target = {}", addr);
+
+        Ok(OscSender {
+            socket,
+            prefix: "/vis".to_string(),
+            buf: Vec::with_capacity(64),
+
+            interval: None,
+            last_sent: collections::HashMap::new(),
+
+            buckets: None,
+            reduced: Vec::new(),
+        })
+    }
+
+    /// Create a sender as configured
+    ///
+    /// Reads the receiver from `"osc.address"`, default `"127.0.0.1:9000"`, and the prefix
+    /// from `"osc.prefix"`, default `"/vis"`.  A rate limit and bucket count are read from
+    /// `"osc.max_rate"` and `"osc.buckets"`, both unset by default.
+    pub fn from_config() -> io::Result<OscSender> {
+        let addr = crate::CONFIG.get_or("osc.address", "127.0.0.1:9000".to_string());
+        let prefix = crate::CONFIG.get_or("osc.prefix", "/vis".to_string());
+
+        let mut sender = OscSender::connect(addr)?.prefix(prefix);
+        if let Some(rate) = crate::CONFIG.get("osc.max_rate") {
+            sender = sender.max_rate(rate);
+        }
+        if let Some(buckets) = crate::CONFIG.get("osc.buckets") {
+            sender = sender.buckets(buckets);
+        }
+        Ok(sender)
+    }
+
+    /// Set the prefix prepended to all addresses
+    ///
+    /// An empty prefix sends to the bare addresses.
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> OscSender {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Send at most `rate` messages per second to each address
+    ///
+    /// Messages arriving sooner after the last one to the same address are dropped, so
+    /// sending every frame still works.
+    pub fn max_rate(mut self, rate: f32) -> OscSender {
+        assert!(
+            rate > 0.0 && rate.is_finite(),
+            "Rate must be positive, not {}!",
+            rate
+        );
+        self.interval = Some(time::Duration::from_secs_f64(1.0 / rate as f64));
+        self
+    }
+
+    /// Reduce spectra with more than `n` buckets to `n` buckets before sending
+    ///
+    /// Adjacent buckets are summed up using
+    /// [`fill_buckets`](../../analyzer/struct.Spectrum.html#method.fill_buckets).
+    pub fn buckets(mut self, n: usize) -> OscSender {
+        assert!(n > 0, "Spectra need at least one bucket!");
+        self.buckets = Some(n);
+        self
+    }
+
+    /// Send the volume to `<prefix>/volume`
+    pub fn send_volume(&mut self, volume: analyzer::SignalStrength) {
+        self.send("/volume", std::iter::once(Arg::Float(volume)));
+    }
+
+    /// Send the number of the latest beat to `<prefix>/beat`
+    ///
+    /// The number is sent as a 32 bit integer, which wraps after 2³¹ beats.  A receiver
+    /// detects a beat by the number changing.
+    pub fn send_beat(&mut self, beat: u64) {
+        self.send("/beat", std::iter::once(Arg::Int(beat as i32)));
+    }
+
+    /// Send all buckets of `spectrum` to `<prefix><address>` as one float each
+    ///
+    /// Reduce the spectrum to a few bands first, eg. using
+    /// [`fill_spectrum`](../../analyzer/struct.Spectrum.html#method.fill_spectrum) or
+    /// [`buckets`](#method.buckets), as a UDP packet has to fit the whole message.
+    pub fn send_spectrum<S: analyzer::spectrum::Storage>(
+        &mut self,
+        spectrum: &analyzer::Spectrum<S>,
+        address: &str,
+    ) {
+        match self.buckets {
+            Some(n) if n < spectrum.len() => {
+                let mut reduced = std::mem::take(&mut self.reduced);
+                reduced.resize(n, 0.0);
+                spectrum.fill_buckets(&mut reduced[..]);
+                self.send(address, reduced.iter().map(|v| Arg::Float(*v)));
+                self.reduced = reduced;
+            }
+            _ => self.send(address, spectrum.iter().map(|v| Arg::Float(*v))),
+        }
+    }
+
+    /// Return whether a message to `address` has to be dropped to keep the rate limit
+    fn throttled(&mut self, address: &str, now: time::Instant) -> bool {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return false,
+        };
+
+        match self.last_sent.get_mut(address) {
+            Some(last) if now.duration_since(*last) < interval => true,
+            Some(last) => {
+                *last = now;
+                false
+            }
+            None => {
+                self.last_sent.insert(address.to_string(), now);
+                false
+            }
+        }
+    }
+
+    fn send<I: ExactSizeIterator<Item = Arg> + Clone>(&mut self, address: &str, args: I) {
+        let mut full = String::with_capacity(self.prefix.len() + address.len());
+        full.push_str(&self.prefix);
+        full.push_str(address);
+
+        if self.throttled(&full, time::Instant::now()) {
+            log::trace!("Throttled OSC message to {:?}", full);
+            return;
+        }
+        encode(&mut self.buf, &full, args);
+
+        match self.socket.send(&self.buf) {
+            Ok(_) => (),
+            // Receiver not running or not keeping up
+            Err(ref e)
+                if e.kind() == io::ErrorKind::WouldBlock
+                    || e.kind() == io::ErrorKind::ConnectionRefused =>
+            {
+                log::trace!("Dropped OSC message to {:?}: {}", full, e);
+            }
+            Err(e) => log::warn!("Failed to send OSC message to {:?}: {}", full, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let mut buf = Vec::new();
+        encode(
+            &mut buf,
+            "/vis/beat",
+            [Arg::Int(3), Arg::Float(0.5)].iter().copied(),
+        );
+
+        assert_eq!(
+            buf,
+            b"/vis/beat\0\0\0,if\0\0\0\0\x03\x3f\x00\x00\x00".to_vec()
+        );
+
+        // Addresses of a multiple of 4 bytes still need a terminator
+        encode(&mut buf, "/abc", std::iter::empty());
+        assert_eq!(buf, b"/abc\0\0\0\0,\0\0\0".to_vec());
+    }
+
+    #[test]
+    fn test_send() {
+        let receiver = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+
+        let mut sender = OscSender::connect(receiver.local_addr().unwrap())
+            .unwrap()
+            .prefix("/test");
+        let spectrum = analyzer::Spectrum::new(vec![1.0, 2.0], 0.0, 100.0);
+        sender.send_spectrum(&spectrum, "/bands");
+        sender.send_volume(0.25);
+
+        let mut packet = [0; 64];
+        let len = receiver.recv(&mut packet).unwrap();
+        assert_eq!(
+            &packet[..len],
+            b"/test/bands\0,ff\0\x3f\x80\x00\x00\x40\x00\x00\x00"
+        );
+        let len = receiver.recv(&mut packet).unwrap();
+        assert_eq!(
+            &packet[..len],
+            b"/test/volume\0\0\0\0,f\0\0\x3e\x80\x00\x00"
+        );
+    }
+
+    #[test]
+    fn test_max_rate() {
+        let mut sender = OscSender::connect("127.0.0.1:9000").unwrap().max_rate(10.0);
+        let start = time::Instant::now();
+        let at = |ms| start + time::Duration::from_millis(ms);
+
+        // Frames at 60 fps for one second
+        let sent = (0..60)
+            .filter(|i| !sender.throttled("/vis/volume", at(i * 1000 / 60)))
+            .count();
+        assert_eq!(sent, 10);
+
+        // Each address has its own limit
+        assert!(!sender.throttled("/vis/beat", at(990)));
+        assert!(sender.throttled("/vis/beat", at(1080)));
+        assert!(!sender.throttled("/vis/beat", at(1090)));
+    }
+
+    #[test]
+    fn test_buckets() {
+        let receiver = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+
+        let mut sender = OscSender::connect(receiver.local_addr().unwrap())
+            .unwrap()
+            .prefix("")
+            .buckets(2);
+        let spectrum = analyzer::Spectrum::new(vec![1.0, 2.0, 3.0, 4.0], 0.0, 100.0);
+        sender.send_spectrum(&spectrum, "/s");
+        // Smaller spectra are sent as is
+        sender.send_spectrum(&analyzer::Spectrum::new(vec![1.0], 0.0, 100.0), "/s");
+
+        let mut packet = [0; 64];
+        let len = receiver.recv(&mut packet).unwrap();
+        assert_eq!(
+            &packet[..len],
+            b"/s\0\0,ff\0\x40\x40\x00\x00\x40\xe0\x00\x00"
+        );
+        let len = receiver.recv(&mut packet).unwrap();
+        assert_eq!(&packet[..len], b"/s\0\0,f\0\0\x3f\x80\x00\x00");
+    }
+}
@@ -0,0 +1,168 @@
+//! Scrolling Row Buffer
+
+/// A fixed number of rows, cycling through its slots as new rows are pushed
+///
+/// Rows are stored in `rows` physical slots.  Each push overwrites the slot after the
+/// previously written one, wrapping around at the end.  This is useful to keep a scrolling
+/// display (eg. a GPU buffer) in sync without copying all rows on every push.
+///
+/// Logical rows are indexed from the oldest (`0`) to the newest (`len() - 1`).
+///
+/// # Example
+/// ```
+/// # use vis_core::helpers;
+/// let mut scroll = helpers::ScrollBuffer::new(3, 2, 0.0);
+///
+/// let slot = scroll.push_row(&[1.0, 2.0]);
+/// assert_eq!(slot, 0);
+/// assert_eq!(scroll.row(0), &[1.0, 2.0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ScrollBuffer<T> {
+    data: Vec<T>,
+    width: usize,
+    rows: usize,
+
+    head: usize,
+    len: usize,
+}
+
+impl<T: Clone> ScrollBuffer<T> {
+    /// Create a new buffer with `rows` rows of `width` elements, initialized to `fill`
+    pub fn new(rows: usize, width: usize, fill: T) -> ScrollBuffer<T> {
+        assert!(rows > 0, "A scroll buffer needs at least one row!");
+
+        ScrollBuffer {
+            data: vec![fill; rows * width],
+            width,
+            rows,
+
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Start writing at physical slot `slot` instead of `0`
+    pub fn starting_at(mut self, slot: usize) -> ScrollBuffer<T> {
+        assert!(slot < self.rows, "Slot {} does not exist!", slot);
+
+        self.head = slot;
+        self
+    }
+
+    /// Push a new row, evicting the oldest one if the buffer is full
+    ///
+    /// Returns the physical slot the row was written to.
+    pub fn push_row(&mut self, row: &[T]) -> usize {
+        assert_eq!(row.len(), self.width, "Row has the wrong width!");
+
+        self.push_with(|slot| slot.clone_from_slice(row))
+    }
+}
+
+impl<T> ScrollBuffer<T> {
+    /// Push a new row by letting `f` overwrite the slot of the oldest one in place
+    ///
+    /// Until the buffer is full, `f` gets a slot which still holds the initial contents.
+    /// Returns the physical slot the row was written to.
+    pub fn push_with<F: FnOnce(&mut [T])>(&mut self, f: F) -> usize {
+        let slot = self.head;
+        f(&mut self.data[slot * self.width..(slot + 1) * self.width]);
+
+        self.head = (self.head + 1) % self.rows;
+        self.len = (self.len + 1).min(self.rows);
+
+        slot
+    }
+
+    /// Return the number of rows pushed, up to `rows()`
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return whether no rows were pushed yet
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the number of slots
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Return the number of elements per row
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Return the physical slot of row `i`, counting from the oldest row
+    pub fn slot(&self, i: usize) -> usize {
+        assert!(i < self.len, "Row {} does not exist!", i);
+
+        (self.head + self.rows - self.len + i) % self.rows
+    }
+
+    /// Return row `i`, counting from the oldest row
+    pub fn row(&self, i: usize) -> &[T] {
+        let start = self.slot(i) * self.width;
+        &self.data[start..start + self.width]
+    }
+
+    /// Iterate over all rows, starting with the oldest
+    pub fn iter(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.len).map(move |i| self.row(i))
+    }
+
+    /// Return all slots in physical order
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evict() {
+        let mut scroll = ScrollBuffer::new(4, 2, 0);
+        assert!(scroll.is_empty());
+
+        for i in 0..3 {
+            assert_eq!(scroll.push_row(&[i, i]), i as usize);
+        }
+        assert_eq!(scroll.len(), 3);
+        assert_eq!(scroll.iter().map(|r| r[0]).collect::<Vec<_>>(), [0, 1, 2]);
+
+        for i in 3..10 {
+            scroll.push_row(&[i, i]);
+        }
+        assert_eq!(scroll.len(), 4);
+        assert_eq!(
+            scroll.iter().map(|r| r[0]).collect::<Vec<_>>(),
+            [6, 7, 8, 9]
+        );
+        assert_eq!(scroll.as_slice(), &[8, 8, 9, 9, 6, 6, 7, 7]);
+    }
+
+    #[test]
+    fn test_starting_at() {
+        let mut scroll = ScrollBuffer::new(4, 1, 0).starting_at(3);
+
+        assert_eq!(scroll.push_row(&[1]), 3);
+        assert_eq!(scroll.push_row(&[2]), 0);
+        assert_eq!(scroll.slot(0), 3);
+        assert_eq!(scroll.iter().map(|r| r[0]).collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn test_push_with() {
+        let mut scroll = ScrollBuffer::new(2, 2, 0);
+
+        assert_eq!(scroll.push_with(|slot| slot[1] = 1), 0);
+        assert_eq!(scroll.push_with(|slot| slot.fill(2)), 1);
+        // Overwrites the oldest row in place
+        assert_eq!(scroll.push_with(|slot| slot[0] += 3), 0);
+        assert_eq!(scroll.iter().collect::<Vec<_>>(), [&[2, 2], &[3, 1]]);
+    }
+}
@@ -40,7 +40,7 @@
 //!             analyzer.analyze(samples);
 //!
 //!             info.spectrum.fill_from(&analyzer.average());
-//!             info.volume = samples.volume(0.3) * 400.0;
+//!             info.volume = samples.volume_normalized(0.3, -30.0);
 //!             info.beat = info.spectrum.slice(50.0, 100.0).max() * 0.01;
 //!             info
 //!         },
@@ -54,7 +54,7 @@
 //!         // Inside this closure you have access to the latest data from
 //!         // the analyzer
 //!         frame.info(|info| {
-//!             for _ in 0..info.volume as usize {
+//!             for _ in 0..(info.volume * 40.0) as usize {
 //!                 print!("#");
 //!             }
 //!             println!("");
@@ -68,11 +68,14 @@
 //! }
 //! ```
 pub mod analyzer;
+pub mod config;
 pub mod frames;
 pub mod helpers;
 pub mod recorder;
 pub mod visualizer;
 
+#[doc(inline)]
+pub use crate::config::ConfigExt;
 #[doc(inline)]
 pub use crate::frames::Frames;
 #[doc(inline)]
@@ -116,6 +119,54 @@ pub fn default_config() {
         .expect("Can't load config");
 }
 
+/// Initialize config from default sources and command line overrides
+///
+/// Loads the same files as [`default_config`](fn.default_config.html), but every
+/// `--set key=value` argument overrides the value at `key`, eg.
+///
+/// ```text
+/// $ noambition --set audio.rate=44100 --set noa.fps=60 --set audio.recorder=pulse
+/// ```
+///
+/// Values are parsed as toml, falling back to a string, see
+/// [`config::parse_override`](config/fn.parse_override.html).  Other arguments are ignored.
+pub fn config_from_args() {
+    let mut config =
+        ["visualizer.toml", "config/visualizer.toml"]
+            .iter()
+            .find_map(|file| {
+                let content = std::fs::read_to_string(file).ok()?;
+                log::info!("Config loaded from {:?}", file);
+                Some(content.parse::<ezconf::toml::Value>().unwrap_or_else(|e| {
+                    panic!("File {:?} does not contain valid toml: {}", file, e)
+                }))
+            })
+            .unwrap_or_else(|| ezconf::toml::Value::Table(Default::default()));
+
+    for (key, value) in config::parse_args(std::env::args().skip(1)) {
+        log::info!("Config override {} = {}", key, value);
+        config::set_path(&mut config, &key, value);
+    }
+
+    CONFIG
+        .init([ezconf::Source::Memory(&config.to_string())].iter())
+        .expect("Can't load config");
+}
+
+/// Print a volume meter and beat indicator to the terminal
+///
+/// A one-liner to check that audio is captured and analyzed before writing any
+/// visualization code.  Runs until interrupted.  See [`helpers::meter`](helpers/meter/index.html).
+///
+/// # Example
+/// ```no_run
+/// vis_core::default_config();
+/// vis_core::debug_meter();
+/// ```
+pub fn debug_meter() {
+    helpers::meter::run();
+}
+
 /// Initialize logger
 ///
 /// By default, enable debug output in debug-builds.
@@ -0,0 +1,246 @@
+use crate::analyzer;
+use std::sync::{self, atomic};
+use std::thread;
+
+/// Periods in the ALSA ring buffer, more survive longer stalls but add latency
+const PERIODS: usize = 4;
+
+#[derive(Debug, Default)]
+pub struct AlsaBuilder {
+    pub rate: Option<usize>,
+    pub read_size: Option<usize>,
+    pub buffer_size: Option<usize>,
+    pub latency_samples: Option<usize>,
+    pub channel_gains: Option<[analyzer::Sample; 2]>,
+
+    /// Name of the capture PCM, eg. `"hw:1,0"` or `"plughw:CARD=USB"`
+    ///
+    /// Defaults to `"default"`, can also be set from config as `"alsa.device"`.
+    pub device: Option<String>,
+}
+
+impl AlsaBuilder {
+    pub fn new() -> AlsaBuilder {
+        Default::default()
+    }
+
+    pub fn rate(&mut self, rate: usize) -> &mut AlsaBuilder {
+        self.rate = Some(rate);
+        self
+    }
+
+    pub fn read_size(&mut self, size: usize) -> &mut AlsaBuilder {
+        self.read_size = Some(size);
+        self
+    }
+
+    pub fn buffer_size(&mut self, size: usize) -> &mut AlsaBuilder {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    pub fn device<S: Into<String>>(&mut self, dev: S) -> &mut AlsaBuilder {
+        self.device = Some(dev.into());
+        self
+    }
+
+    pub fn latency_samples(&mut self, n: usize) -> &mut AlsaBuilder {
+        self.latency_samples = Some(n);
+        self
+    }
+
+    pub fn channel_gains(&mut self, gains: [analyzer::Sample; 2]) -> &mut AlsaBuilder {
+        self.channel_gains = Some(gains);
+        self
+    }
+
+    pub fn create(&self) -> AlsaRecorder {
+        AlsaRecorder::from_builder(self)
+    }
+
+    pub fn build(&self) -> Box<dyn super::Recorder> {
+        Box::new(self.create())
+    }
+}
+
+/// Recorder capturing from an ALSA PCM directly
+///
+/// Skips the sound server, which saves its latency and makes the device choice explicit.
+/// The PCM must support interleaved stereo `f32` samples.  Hardware devices (`"hw:…"`)
+/// rarely do, use the matching `"plughw:…"` device to let ALSA convert.  If the PCM does not
+/// support the configured rate, the samples are resampled from the nearest one.
+///
+/// Overruns, where the thread did not read in time and samples were lost, are logged and
+/// recovered from.  Any other error stops the recorder.
+#[derive(Debug)]
+pub struct AlsaRecorder {
+    rate: usize,
+    read_size: usize,
+    buffer: analyzer::SampleBuffer,
+    /// Rate of the opened PCM, `0` until the thread opened it
+    native_rate: sync::Arc<atomic::AtomicUsize>,
+    running: sync::Arc<atomic::AtomicBool>,
+    stop: sync::Arc<atomic::AtomicBool>,
+}
+
+/// Open `device` for capturing stereo `f32` samples at `rate`
+fn open(device: &str, rate: usize, read_size: usize) -> alsa::Result<alsa::PCM> {
+    let pcm = alsa::PCM::new(device, alsa::Direction::Capture, false)?;
+    {
+        let hwp = alsa::pcm::HwParams::any(&pcm)?;
+        hwp.set_channels(2)?;
+        hwp.set_rate(rate as u32, alsa::ValueOr::Nearest)?;
+        hwp.set_format(alsa::pcm::Format::float())?;
+        hwp.set_access(alsa::pcm::Access::RWInterleaved)?;
+        let period =
+            hwp.set_period_size_near(read_size as alsa::pcm::Frames, alsa::ValueOr::Nearest)?;
+        hwp.set_buffer_size_near(period * PERIODS as alsa::pcm::Frames)?;
+        pcm.hw_params(&hwp)?;
+    }
+
+    Ok(pcm)
+}
+
+impl AlsaRecorder {
+    fn from_builder(build: &AlsaBuilder) -> AlsaRecorder {
+        let rate = build
+            .rate
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.rate", 8000));
+        let buffer_size = build.buffer_size.unwrap_or_else(super::default_buffer_size);
+        let read_size = build
+            .read_size
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.read_size", 256));
+        let device = build
+            .device
+            .clone()
+            .unwrap_or_else(|| crate::CONFIG.get_or("alsa.device", "default".to_string()));
+
+        let latency_samples = build
+            .latency_samples
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.latency_samples", 0));
+        let gains = build
+            .channel_gains
+            .unwrap_or_else(super::default_channel_gains);
+
+        let buf = analyzer::SampleBuffer::with_delay(buffer_size, rate, latency_samples);
+        let native_rate = sync::Arc::new(atomic::AtomicUsize::new(0));
+        let running = sync::Arc::new(atomic::AtomicBool::new(true));
+        let stop = sync::Arc::new(atomic::AtomicBool::new(false));
+
+        {
+            let buf = buf.clone();
+            let native_rate = native_rate.clone();
+            let running = running.clone();
+            let stop = stop.clone();
+
+            thread::Builder::new()
+                .name("alsa-recorder".into())
+                .spawn(move || {
+                    let _guard = super::RunningGuard(running);
+                    let pcm = match open(&device, rate, read_size) {
+                        Ok(pcm) => pcm,
+                        Err(e) => {
+                            log::error!("Can't open ALSA device \"{}\": {}", device, e);
+                            return;
+                        }
+                    };
+
+                    let pcm_rate = match pcm.hw_params_current().and_then(|h| h.get_rate()) {
+                        Ok(r) => r as usize,
+                        Err(e) => {
+                            log::error!("Can't query rate of ALSA device \"{}\": {}", device, e);
+                            return;
+                        }
+                    };
+                    if pcm_rate != rate {
+                        log::info!(
+                            "ALSA device \"{}\" does not support {} Hz, resampling from {} Hz",
+                            device,
+                            rate,
+                            pcm_rate
+                        );
+                    }
+                    native_rate.store(pcm_rate, atomic::Ordering::SeqCst);
+                    let mut resampler = super::resample::Resampler::new(pcm_rate, rate);
+                    let io = pcm.io_f32().expect("Can't access ALSA device");
+
+                    let mut raw_buf = vec![0.0; read_size * 2];
+                    let mut read_buf = vec![[0.0; 2]; read_size];
+
+                    log::debug!("ALSA:");
+                    log::debug!("    Sample Rate = {:6}", rate);
+                    log::debug!("    Native Rate = {:6}", pcm_rate);
+                    log::debug!("    Read Size   = {:6}", read_size);
+                    log::debug!("    Buffer Size = {:6}", buffer_size);
+                    log::debug!("    Delay       = {:6}", latency_samples);
+                    log::debug!("    Gains       = {:?}", gains);
+                    log::debug!("    Device      = \"{}\"", device);
+
+                    while !stop.load(atomic::Ordering::SeqCst) {
+                        let frames = match io.readi(&mut raw_buf) {
+                            Ok(frames) => frames,
+                            Err(e) => {
+                                // Overruns are routine, eg. after a stall of the system
+                                log::warn!("ALSA read failed, recovering: {}", e);
+                                if let Err(e) = pcm.try_recover(e, true) {
+                                    log::error!("Can't recover ALSA device: {}", e);
+                                    return;
+                                }
+                                continue;
+                            }
+                        };
+
+                        for (s, raw) in read_buf.iter_mut().zip(raw_buf.chunks_exact(2)) {
+                            *s = [raw[0], raw[1]];
+                        }
+                        super::apply_channel_gains(&mut read_buf[..frames], gains);
+
+                        buf.push(resampler.process(&read_buf[..frames]));
+                        log::trace!("Pushed {} samples", frames);
+                    }
+                })
+                .unwrap();
+        }
+
+        AlsaRecorder {
+            rate,
+            read_size,
+            buffer: buf,
+            native_rate,
+            running,
+            stop,
+        }
+    }
+}
+
+impl super::Recorder for AlsaRecorder {
+    fn sample_buffer(&self) -> &analyzer::SampleBuffer {
+        &self.buffer
+    }
+
+    fn is_running(&self) -> bool {
+        self.running.load(atomic::Ordering::SeqCst)
+    }
+
+    fn latency(&self) -> f32 {
+        (self.read_size + self.buffer.delay()) as f32 / self.rate as f32
+    }
+
+    fn native_rate(&self) -> Option<usize> {
+        match self.native_rate.load(atomic::Ordering::SeqCst) {
+            0 => None,
+            rate => Some(rate),
+        }
+    }
+
+    /// Stop recording, the thread exits after its current read
+    fn stop(&mut self) {
+        self.stop.store(true, atomic::Ordering::SeqCst);
+    }
+}
+
+impl Drop for AlsaRecorder {
+    fn drop(&mut self) {
+        super::Recorder::stop(self);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::analyzer;
+use std::sync::{self, atomic};
 use std::thread;
 use cpal::traits::*;
 
@@ -6,7 +7,15 @@ use cpal::traits::*;
 pub struct CPalBuilder {
     pub rate: Option<usize>,
     pub buffer_size: Option<usize>,
+    pub latency_samples: Option<usize>,
     pub read_size: Option<usize>,
+    pub channel_gains: Option<[analyzer::Sample; 2]>,
+
+    /// Name of the input device, eg. a monitor device to capture what is playing
+    ///
+    /// The available names are logged at debug level.  Falls back to the default input
+    /// device if no device has this name.  Can also be set from config as `"cpal.device"`.
+    pub device: Option<String>,
 }
 
 impl CPalBuilder {
@@ -29,6 +38,21 @@ impl CPalBuilder {
         self
     }
 
+    pub fn latency_samples(&mut self, n: usize) -> &mut CPalBuilder {
+        self.latency_samples = Some(n);
+        self
+    }
+
+    pub fn channel_gains(&mut self, gains: [analyzer::Sample; 2]) -> &mut CPalBuilder {
+        self.channel_gains = Some(gains);
+        self
+    }
+
+    pub fn device<S: Into<String>>(&mut self, dev: S) -> &mut CPalBuilder {
+        self.device = Some(dev.into());
+        self
+    }
+
     pub fn create(&self) -> CPalRecorder {
         CPalRecorder::from_builder(self)
     }
@@ -38,11 +62,121 @@ impl CPalBuilder {
     }
 }
 
+/// Find the input device called `name` or the default input device
+fn select_device(host: &cpal::Host, name: Option<&str>) -> Option<cpal::Device> {
+    let devices = match host.input_devices() {
+        Ok(devices) => devices.collect::<Vec<_>>(),
+        Err(e) => {
+            log::warn!("Can't enumerate input devices: {}", e);
+            Vec::new()
+        }
+    };
+
+    log::debug!("Available input devices:");
+    for d in devices.iter() {
+        log::debug!(" - \"{}\"", d.name().as_deref().unwrap_or("unknown"));
+    }
+
+    if let Some(name) = name {
+        if let Some(device) = devices
+            .into_iter()
+            .find(|d| d.name().ok().as_deref() == Some(name))
+        {
+            return Some(device);
+        }
+        log::warn!("Input device \"{}\" not found, using the default device", name);
+    }
+
+    host.default_input_device()
+}
+
+/// Preference of the sample formats which can be converted, lower is better
+fn format_rank(format: cpal::SampleFormat) -> Option<usize> {
+    match format {
+        cpal::SampleFormat::F32 => Some(0),
+        cpal::SampleFormat::I16 => Some(1),
+        cpal::SampleFormat::U16 => Some(2),
+        _ => None,
+    }
+}
+
+/// Preference of channel counts, stereo is used as is while mono lacks the second channel
+fn channels_rank(channels: cpal::ChannelCount) -> usize {
+    match channels {
+        2 => 0,
+        1 => 2,
+        _ => 1,
+    }
+}
+
+/// Pick the supported config closest to `rate`, preferring `f32` samples and stereo
+///
+/// Rates other than `rate` are resampled.  Downsampling keeps everything below the new
+/// Nyquist frequency, while upsampling can't restore what the device never captured.  So the
+/// closest rate at or above `rate` wins and lower rates are only used if no config reaches
+/// `rate`.
+fn choose_config(
+    configs: impl Iterator<Item = cpal::SupportedStreamConfigRange>,
+    rate: u32,
+) -> Option<cpal::SupportedStreamConfig> {
+    configs
+        .filter_map(|c| {
+            let format = format_rank(c.sample_format())?;
+            let closest = rate.clamp(c.min_sample_rate().0, c.max_sample_rate().0);
+            Some((
+                (
+                    closest < rate,
+                    closest.abs_diff(rate),
+                    format,
+                    channels_rank(c.channels()),
+                ),
+                c.with_sample_rate(cpal::SampleRate(closest)),
+            ))
+        })
+        .min_by_key(|(rank, _)| *rank)
+        .map(|(_, c)| c)
+}
+
+/// Convert interleaved frames with `channels` channels to stereo and push them in chunks
+///
+/// Mono is copied to both channels, beyond stereo only the first two channels are used.
+/// Each chunk is resampled to the rate of `buf` before pushing.
+fn push_frames<T>(
+    data: &[T],
+    channels: usize,
+    chunk_buffer: &mut [[analyzer::Sample; 2]],
+    resampler: &mut super::resample::Resampler,
+    gains: [analyzer::Sample; 2],
+    buf: &analyzer::SampleBuffer,
+) where
+    T: cpal::Sample,
+    f32: cpal::FromSample<T>,
+{
+    for chunk in data.chunks(chunk_buffer.len() * channels) {
+        let len = chunk.len() / channels;
+        for (b, frame) in chunk_buffer.iter_mut().zip(chunk.chunks_exact(channels)) {
+            *b = match frame {
+                [m] => [m.to_sample(); 2],
+                [l, r, ..] => [l.to_sample(), r.to_sample()],
+                [] => unreachable!(),
+            };
+        }
+        super::apply_channel_gains(&mut chunk_buffer[..len], gains);
+        buf.push(resampler.process(&chunk_buffer[..len]));
+    }
+}
+
 #[derive(Debug)]
 pub struct CPalRecorder {
-    #[allow(unused)]
     rate: usize,
     buffer: analyzer::SampleBuffer,
+    /// Rate of the opened stream, `0` until the thread opened it
+    native_rate: sync::Arc<atomic::AtomicUsize>,
+    /// Frames per callback of the opened stream, the read size until the thread opened it
+    period: sync::Arc<atomic::AtomicUsize>,
+    running: sync::Arc<atomic::AtomicBool>,
+    stop: sync::Arc<atomic::AtomicBool>,
+    thread: thread::Thread,
 }
 
 impl CPalRecorder {
@@ -50,49 +184,103 @@ impl CPalRecorder {
         let rate = build
             .rate
             .unwrap_or_else(|| crate::CONFIG.get_or("audio.rate", 8000));
-        let buffer_size = build
-            .buffer_size
-            .unwrap_or_else(|| crate::CONFIG.get_or("audio.buffer", 16000));
+        let buffer_size = build.buffer_size.unwrap_or_else(super::default_buffer_size);
         let read_size = build
-            .buffer_size
+            .read_size
             .unwrap_or_else(|| crate::CONFIG.get_or("audio.read_size", 256));
 
-        let buf = analyzer::SampleBuffer::new(buffer_size, rate);
+        let latency_samples = build
+            .latency_samples
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.latency_samples", 0));
+        let gains = build.channel_gains.unwrap_or_else(super::default_channel_gains);
+        let device_name = build
+            .device
+            .clone()
+            .or_else(|| crate::CONFIG.get("cpal.device"));
 
-        {
+        let buf = analyzer::SampleBuffer::with_delay(buffer_size, rate, latency_samples);
+        let native_rate = sync::Arc::new(atomic::AtomicUsize::new(0));
+        let period = sync::Arc::new(atomic::AtomicUsize::new(read_size));
+        let running = sync::Arc::new(atomic::AtomicBool::new(true));
+        let stop = sync::Arc::new(atomic::AtomicBool::new(false));
+
+        let handle = {
             let buf = buf.clone();
+            let native_rate = native_rate.clone();
+            let period = period.clone();
+            let running = running.clone();
+            let stop = stop.clone();
             let mut chunk_buffer = vec![[0.0; 2]; read_size];
 
             thread::Builder::new()
                 .name("cpal-recorder".into())
                 .spawn(move || {
+                    let _guard = super::RunningGuard(running.clone());
                     let host = cpal::default_host();
-                    let device = host.default_input_device().expect("Can't acquire input device");
+                    let device = select_device(&host, device_name.as_deref())
+                        .expect("Can't acquire input device");
+
+                    let supported = device
+                        .supported_input_configs()
+                        .expect("Can't query input configs");
+                    let supported = choose_config(supported, rate as u32)
+                        .expect("Input device offers no usable config");
+                    let stream_rate = supported.sample_rate().0 as usize;
+                    if stream_rate != rate {
+                        log::info!(
+                            "Input device does not support {} Hz, resampling from {} Hz",
+                            rate,
+                            stream_rate
+                        );
+                    }
+                    native_rate.store(stream_rate, atomic::Ordering::SeqCst);
+                    let mut resampler = super::resample::Resampler::new(stream_rate, rate);
 
+                    let format = supported.sample_format();
+                    let channels = supported.channels() as usize;
                     let config = cpal::StreamConfig {
-                        channels: 2,
-                        sample_rate: cpal::SampleRate(rate as u32),
-                        buffer_size: cpal::BufferSize::Fixed(read_size as u32),
+                        channels: supported.channels(),
+                        sample_rate: supported.sample_rate(),
+                        buffer_size: match *supported.buffer_size() {
+                            cpal::SupportedBufferSize::Range { min, max } => {
+                                cpal::BufferSize::Fixed((read_size as u32).clamp(min, max))
+                            }
+                            cpal::SupportedBufferSize::Unknown => cpal::BufferSize::Default,
+                        },
                     };
 
+                    // The default buffer size is unknown, assume one read
+                    if let cpal::BufferSize::Fixed(frames) = config.buffer_size {
+                        period.store(frames as usize, atomic::Ordering::SeqCst);
+                    }
+
+                    log::debug!("CPal Stream:");
+                    log::debug!("    Format      = {:?}", format);
+                    log::debug!("    Channels    = {:6}", channels);
+                    log::debug!("    Sample Rate = {:6}", config.sample_rate.0);
+                    log::debug!("    Buffer Size = {:?}", config.buffer_size);
+
                     let stream = device.build_input_stream_raw(
                         &config,
-                        cpal::SampleFormat::F32,
-                        move |data, _info| {
-                            let slice = data.as_slice::<f32>().expect("Wrong sample buffer data type!");
-                            for chunk in slice.chunks(chunk_buffer.len() * 2) {
-                                let len = chunk.len() / 2;
-                                for p in chunk_buffer.iter_mut().zip(chunk.chunks_exact(2)) {
-                                    match p {
-                                        (b, [l, r]) => *b = [*l, *r],
-                                        _ => unreachable!(),
-                                    }
-                                }
-                                buf.push(&chunk_buffer[..len]);
-                            }
+                        format,
+                        move |data, _info| match data.sample_format() {
+                            cpal::SampleFormat::F32 => push_frames::<f32>(
+                                data.as_slice().unwrap(), channels, &mut chunk_buffer,
+                                &mut resampler, gains, &buf,
+                            ),
+                            cpal::SampleFormat::I16 => push_frames::<i16>(
+                                data.as_slice().unwrap(), channels, &mut chunk_buffer,
+                                &mut resampler, gains, &buf,
+                            ),
+                            cpal::SampleFormat::U16 => push_frames::<u16>(
+                                data.as_slice().unwrap(), channels, &mut chunk_buffer,
+                                &mut resampler, gains, &buf,
+                            ),
+                            f => unreachable!("Unexpected sample format {:?}", f),
                         },
-                        |err| {
-                            panic!("Stream Error: {err:?}");
+                        move |err| {
+                            log::error!("Stream Error: {err:?}");
+                            running.store(false, atomic::Ordering::SeqCst);
                         },
                         None,
                     ).expect("Failed to build input stream");
@@ -101,18 +289,29 @@ impl CPalRecorder {
                     log::debug!("    Sample Rate = {:6}", rate);
                     log::debug!("    Read Size   = {:6}", read_size);
                     log::debug!("    Buffer Size = {:6}", buffer_size);
+                    log::debug!("    Delay       = {:6}", latency_samples);
+                    log::debug!("    Gains       = {:?}", gains);
                     log::debug!("    Device      = \"{}\"", device.name().as_deref().unwrap_or("unknown"));
 
                     stream.play().unwrap();
 
-                    loop {
+                    // The stream records as long as it is alive
+                    while !stop.load(atomic::Ordering::SeqCst) {
                         std::thread::park();
                     }
                 })
-                .unwrap();
-        }
+                .unwrap()
+        };
 
-        CPalRecorder { rate, buffer: buf }
+        CPalRecorder {
+            rate,
+            buffer: buf,
+            native_rate,
+            period,
+            running,
+            stop,
+            thread: handle.thread().clone(),
+        }
     }
 }
 
@@ -120,4 +319,109 @@ impl super::Recorder for CPalRecorder {
     fn sample_buffer<'a>(&'a self) -> &'a analyzer::SampleBuffer {
         &self.buffer
     }
+
+    fn is_running(&self) -> bool {
+        self.running.load(atomic::Ordering::SeqCst)
+    }
+
+    /// The delay of the sample buffer plus one callback of the stream at its native rate
+    fn latency(&self) -> f32 {
+        let period = self.period.load(atomic::Ordering::SeqCst) as f32
+            / super::Recorder::native_rate(self).unwrap_or(self.rate) as f32;
+        self.buffer.delay() as f32 / self.rate as f32 + period
+    }
+
+    fn native_rate(&self) -> Option<usize> {
+        match self.native_rate.load(atomic::Ordering::SeqCst) {
+            0 => None,
+            rate => Some(rate),
+        }
+    }
+
+    /// Stop recording, the thread drops the stream and exits
+    fn stop(&mut self) {
+        self.stop.store(true, atomic::Ordering::SeqCst);
+        self.thread.unpark();
+    }
+}
+
+impl Drop for CPalRecorder {
+    fn drop(&mut self) {
+        super::Recorder::stop(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(
+        channels: cpal::ChannelCount,
+        rates: (u32, u32),
+        format: cpal::SampleFormat,
+    ) -> cpal::SupportedStreamConfigRange {
+        cpal::SupportedStreamConfigRange::new(
+            channels,
+            cpal::SampleRate(rates.0),
+            cpal::SampleRate(rates.1),
+            cpal::SupportedBufferSize::Unknown,
+            format,
+        )
+    }
+
+    #[test]
+    fn test_choose_config() {
+        let choose = |configs: Vec<cpal::SupportedStreamConfigRange>, rate| {
+            let config = choose_config(configs.into_iter(), rate).unwrap();
+            (
+                config.sample_rate().0,
+                config.channels(),
+                config.sample_format(),
+            )
+        };
+
+        // A supported rate is used as is, stereo f32 preferred
+        assert_eq!(
+            choose(
+                vec![
+                    range(1, (8000, 48000), cpal::SampleFormat::F32),
+                    range(2, (8000, 48000), cpal::SampleFormat::I16),
+                    range(2, (8000, 48000), cpal::SampleFormat::F32),
+                ],
+                8000,
+            ),
+            (8000, 2, cpal::SampleFormat::F32)
+        );
+
+        // A higher rate wins over a closer, lower one
+        assert_eq!(
+            choose(
+                vec![
+                    range(2, (7000, 7000), cpal::SampleFormat::F32),
+                    range(2, (48000, 48000), cpal::SampleFormat::F32),
+                    range(2, (44100, 44100), cpal::SampleFormat::F32),
+                ],
+                8000,
+            ),
+            (44100, 2, cpal::SampleFormat::F32)
+        );
+
+        // Lower rates only if nothing reaches the target
+        assert_eq!(
+            choose(
+                vec![
+                    range(2, (4000, 6000), cpal::SampleFormat::F32),
+                    range(2, (2000, 7000), cpal::SampleFormat::I16),
+                ],
+                8000,
+            ),
+            (7000, 2, cpal::SampleFormat::I16)
+        );
+
+        assert!(choose_config(
+            vec![range(2, (8000, 8000), cpal::SampleFormat::I8)].into_iter(),
+            8000
+        )
+        .is_none());
+    }
 }
@@ -0,0 +1,435 @@
+use crate::analyzer;
+use std::{fmt, io, path};
+
+/// Builder for WavRecorder
+#[derive(Debug, Default)]
+pub struct WavBuilder {
+    /// Expected sample rate
+    ///
+    /// Files with a different rate are rejected.  Can also be set from config as
+    /// `"audio.rate"`.
+    pub rate: Option<usize>,
+    pub buffer_size: Option<usize>,
+
+    /// Path of the WAV file
+    ///
+    /// Can also be set from config as `"audio.file.path"`.
+    pub path: Option<path::PathBuf>,
+
+    /// Start over at the end of the file instead of stopping
+    ///
+    /// Defaults to `false`, can also be set from config as `"audio.file.loop"`.
+    pub looping: Option<bool>,
+
+    /// Gains of the left and right channel
+    ///
+    /// Can also be set from config as `"audio.channel_gains"`.
+    pub channel_gains: Option<[analyzer::Sample; 2]>,
+}
+
+impl WavBuilder {
+    pub fn new() -> WavBuilder {
+        Default::default()
+    }
+
+    pub fn rate(&mut self, rate: usize) -> &mut WavBuilder {
+        self.rate = Some(rate);
+        self
+    }
+
+    pub fn buffer_size(&mut self, buffer_size: usize) -> &mut WavBuilder {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+
+    pub fn path<P: Into<path::PathBuf>>(&mut self, path: P) -> &mut WavBuilder {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn looping(&mut self, looping: bool) -> &mut WavBuilder {
+        self.looping = Some(looping);
+        self
+    }
+
+    pub fn channel_gains(&mut self, gains: [analyzer::Sample; 2]) -> &mut WavBuilder {
+        self.channel_gains = Some(gains);
+        self
+    }
+
+    pub fn create(&self) -> Result<WavRecorder, FileError> {
+        WavRecorder::from_builder(self)
+    }
+
+    /// Build the recorder, panicking with a descriptive message if the file can't be used
+    pub fn build(&self) -> Box<dyn super::Recorder> {
+        match self.create() {
+            Ok(rec) => Box::new(rec),
+            Err(e) => {
+                log::error!("{}", e);
+                panic!("{}", e);
+            }
+        }
+    }
+}
+
+/// Error opening an audio file
+#[derive(Debug)]
+pub enum FileError {
+    /// The file could not be read or decoded
+    Wav(hound::Error),
+    /// The sample rate of the file differs from the configured one
+    Rate { file: usize, expected: usize },
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileError::Wav(e) => write!(f, "Can't read audio file: {}", e),
+            FileError::Rate { file, expected } => write!(
+                f,
+                "Audio file has a sample rate of {} Hz but \"audio.rate\" is {} Hz",
+                file, expected,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FileError::Wav(e) => Some(e),
+            FileError::Rate { .. } => None,
+        }
+    }
+}
+
+impl From<hound::Error> for FileError {
+    fn from(e: hound::Error) -> FileError {
+        FileError::Wav(e)
+    }
+}
+
+/// Recorder playing back a WAV file
+///
+/// Instead of capturing live input, samples are read from a file, which makes analysis
+/// reproducible and allows offline rendering.  The file is decoded completely on creation.
+/// Each [`sync`](trait.Recorder.html#method.sync) pushes as many frames into the sample buffer
+/// as correspond to the given time, so the buffer always holds the samples up to
+/// `time * rate`.
+///
+/// Mono files are upmixed to stereo, for files with more channels the first two are used.
+///
+/// At time `0`, the buffer only holds silence.  To analyze the opening of the file from the
+/// first frame on, [`prime`](trait.Recorder.html#method.prime) the recorder.
+///
+/// # Example
+/// ```no_run
+/// # use vis_core::recorder;
+/// # vis_core::default_config();
+/// let mut recorder = recorder::file::WavBuilder::new()
+///     .path("track.wav")
+///     .looping(true)
+///     .build();
+/// recorder.prime();
+/// ```
+pub struct WavRecorder {
+    buffer: analyzer::SampleBuffer,
+    frames: Vec<[analyzer::Sample; 2]>,
+    looping: bool,
+    /// Number of frames pushed so far, counting all loops
+    consumed: usize,
+    /// Number of frames playback runs ahead of the sync time, set by priming
+    lead: usize,
+}
+
+impl fmt::Debug for WavRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WavRecorder")
+            .field("frames", &self.frames.len())
+            .field("looping", &self.looping)
+            .field("consumed", &self.consumed)
+            .field("lead", &self.lead)
+            .finish()
+    }
+}
+
+impl WavRecorder {
+    fn from_builder(build: &WavBuilder) -> Result<WavRecorder, FileError> {
+        let rate = build
+            .rate
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.rate", 8000));
+        let buffer_size = build.buffer_size.unwrap_or_else(super::default_buffer_size);
+        let path = build.path.clone().unwrap_or_else(|| {
+            crate::CONFIG
+                .get::<String>("audio.file.path")
+                .expect("No audio file set!")
+                .into()
+        });
+        let looping = build
+            .looping
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.file.loop", false));
+        let gains = build
+            .channel_gains
+            .unwrap_or_else(super::default_channel_gains);
+
+        let reader = hound::WavReader::open(&path)?;
+        let mut frames = WavRecorder::decode(reader, rate)?;
+        super::apply_channel_gains(&mut frames, gains);
+
+        log::debug!("WavRecorder:");
+        log::debug!("    File        = {:?}", path);
+        log::debug!("    Sample Rate = {:6}", rate);
+        log::debug!("    Buffer Size = {:6}", buffer_size);
+        log::debug!("    Frames      = {:6}", frames.len());
+        log::debug!("    Looping     = {:?}", looping);
+        log::debug!("    Gains       = {:?}", gains);
+
+        Ok(WavRecorder {
+            buffer: analyzer::SampleBuffer::new(buffer_size, rate),
+            frames,
+            looping,
+            consumed: 0,
+            lead: 0,
+        })
+    }
+
+    fn decode<R: io::Read>(
+        mut reader: hound::WavReader<R>,
+        rate: usize,
+    ) -> Result<Vec<[analyzer::Sample; 2]>, FileError> {
+        let spec = reader.spec();
+        if spec.sample_rate as usize != rate {
+            return Err(FileError::Rate {
+                file: spec.sample_rate as usize,
+                expected: rate,
+            });
+        }
+
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1u64 << (spec.bits_per_sample - 1)) as analyzer::Sample;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.map(|s| s as analyzer::Sample / scale))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+
+        let channels = spec.channels as usize;
+        Ok(samples
+            .chunks_exact(channels)
+            .map(|c| {
+                if channels == 1 {
+                    [c[0], c[0]]
+                } else {
+                    [c[0], c[1]]
+                }
+            })
+            .collect())
+    }
+
+    /// Return the number of frames in the file
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Return whether the file contains no frames
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Push the frames up to frame `target`
+    ///
+    /// Returns false once the end of the file was reached, unless looping.
+    fn push_until(&mut self, mut target: usize) -> bool {
+        let len = self.frames.len();
+        if !self.looping {
+            target = target.min(len);
+        }
+
+        if target > self.consumed && len > 0 {
+            // Frames which would be overwritten right away need not be pushed
+            self.consumed = self.consumed.max(target.saturating_sub(self.buffer.size()));
+
+            while self.consumed < target {
+                let start = self.consumed % len;
+                let end = (start + target - self.consumed).min(len);
+                self.buffer.push(&self.frames[start..end]);
+                self.consumed += end - start;
+            }
+        }
+
+        self.looping && len > 0 || self.consumed < len
+    }
+}
+
+impl super::Recorder for WavRecorder {
+    fn sample_buffer(&self) -> &analyzer::SampleBuffer {
+        &self.buffer
+    }
+
+    /// Push the frames up to `time` seconds, plus the primed frames
+    ///
+    /// Returns false once the end of the file was reached, unless looping.
+    fn sync(&mut self, time: f32) -> bool {
+        let target = (time.max(0.0) as f64 * self.buffer.rate() as f64) as usize;
+        self.push_until(target + self.lead)
+    }
+
+    /// Fill the sample buffer with the opening of the file
+    ///
+    /// Playback then runs one buffer length ahead of the time passed to
+    /// [`sync`](#method.sync).  Does nothing once frames were pushed.
+    fn prime(&mut self) {
+        if self.consumed == 0 {
+            self.lead = self.buffer.size();
+            self.push_until(self.lead);
+        }
+    }
+
+    fn wait_ready(&self, _timeout: std::time::Duration) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+
+    fn write_wav(name: &str, channels: u16, rate: u32, samples: &[i16]) -> path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate: rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for s in samples.iter() {
+            writer.write_sample(*s).unwrap();
+        }
+        writer.finalize().unwrap();
+        path
+    }
+
+    fn latest(rec: &WavRecorder) -> Vec<[analyzer::Sample; 2]> {
+        let buf = rec.sample_buffer();
+        buf.iter(buf.samples_since(0).min(buf.size()), 1).collect()
+    }
+
+    #[test]
+    fn test_sync() {
+        let samples = (0..20).map(|i| i * 1000).collect::<Vec<i16>>();
+        let path = write_wav("vis-core-test-file-sync.wav", 2, 10, &samples);
+
+        let mut rec = WavBuilder::new()
+            .path(&path)
+            .rate(10)
+            .buffer_size(16)
+            .channel_gains([1.0, 1.0])
+            .looping(false)
+            .create()
+            .unwrap();
+        assert_eq!(rec.len(), 10);
+
+        assert!(rec.sync(0.35));
+        let frames = latest(&rec);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[2], [4000.0 / 32768.0, 5000.0 / 32768.0]);
+
+        // Going back in time pushes nothing
+        assert!(rec.sync(0.1));
+        assert_eq!(rec.sample_buffer().samples_since(0), 3);
+
+        assert!(!rec.sync(2.0));
+        assert_eq!(rec.sample_buffer().samples_since(0), 10);
+        assert!(!rec.sync(3.0));
+        assert_eq!(rec.sample_buffer().samples_since(0), 10);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_loop_and_upmix() {
+        let samples = (0..4).map(|i| i * 1000).collect::<Vec<i16>>();
+        let path = write_wav("vis-core-test-file-loop.wav", 1, 10, &samples);
+
+        let mut rec = WavBuilder::new()
+            .path(&path)
+            .rate(10)
+            .buffer_size(16)
+            .channel_gains([1.0, 1.0])
+            .looping(true)
+            .create()
+            .unwrap();
+
+        assert!(rec.sync(1.0));
+        let frames = latest(&rec);
+        assert_eq!(frames.len(), 10);
+        for (i, f) in frames.iter().enumerate() {
+            let v = (i % 4) as f32 * 1000.0 / 32768.0;
+            assert_eq!(*f, [v, v]);
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_prime() {
+        let samples = (0..20).map(|i| i * 1000).collect::<Vec<i16>>();
+        let path = write_wav("vis-core-test-file-prime.wav", 2, 10, &samples);
+
+        let mut rec = WavBuilder::new()
+            .path(&path)
+            .rate(10)
+            .buffer_size(4)
+            .channel_gains([1.0, 1.0])
+            .looping(false)
+            .create()
+            .unwrap();
+
+        rec.prime();
+        let frames = latest(&rec);
+        assert_eq!(frames.len(), 4);
+        for (i, f) in frames.iter().enumerate() {
+            let v = i as f32 * 2000.0 / 32768.0;
+            assert_eq!(*f, [v, v + 1000.0 / 32768.0]);
+        }
+
+        // Playback continues after the primed frames
+        rec.prime();
+        assert!(rec.sync(0.0));
+        assert_eq!(rec.sample_buffer().samples_since(0), 4);
+        assert!(rec.sync(0.2));
+        assert_eq!(rec.sample_buffer().samples_since(0), 6);
+        assert_eq!(latest(&rec)[3], [10000.0 / 32768.0, 11000.0 / 32768.0]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rate_mismatch() {
+        let path = write_wav("vis-core-test-file-rate.wav", 2, 44100, &[0, 0]);
+
+        let err = WavBuilder::new()
+            .path(&path)
+            .rate(8000)
+            .buffer_size(16)
+            .channel_gains([1.0, 1.0])
+            .looping(false)
+            .create()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            FileError::Rate {
+                file: 44100,
+                expected: 8000
+            }
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
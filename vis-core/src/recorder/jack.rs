@@ -0,0 +1,323 @@
+use crate::analyzer;
+use std::fmt;
+use std::sync::{self, atomic};
+
+/// Frames copied per push in the process callback
+const CHUNK_SIZE: usize = 256;
+
+/// Builder for JackRecorder
+#[derive(Debug, Default)]
+pub struct JackBuilder {
+    /// Expected sample rate
+    ///
+    /// JACK dictates the rate, so a server running at a different rate is an error.  Can also
+    /// be set from config as `"audio.rate"`.
+    pub rate: Option<usize>,
+    pub buffer_size: Option<usize>,
+    pub latency_samples: Option<usize>,
+    pub channel_gains: Option<[analyzer::Sample; 2]>,
+
+    /// Name of the JACK client
+    ///
+    /// Defaults to `"visualizer2"`.
+    pub name: Option<String>,
+
+    /// Ports to connect to the left and right input
+    ///
+    /// A single port is connected to both inputs.  Without ports, the inputs stay unconnected
+    /// and can be patched manually.  Can also be set from config as `"jack.ports"`, eg.
+    /// `["system:capture_1", "system:capture_2"]`.
+    pub ports: Option<Vec<String>>,
+}
+
+impl JackBuilder {
+    pub fn new() -> JackBuilder {
+        Default::default()
+    }
+
+    pub fn rate(&mut self, rate: usize) -> &mut JackBuilder {
+        self.rate = Some(rate);
+        self
+    }
+
+    pub fn buffer_size(&mut self, size: usize) -> &mut JackBuilder {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    pub fn latency_samples(&mut self, n: usize) -> &mut JackBuilder {
+        self.latency_samples = Some(n);
+        self
+    }
+
+    pub fn channel_gains(&mut self, gains: [analyzer::Sample; 2]) -> &mut JackBuilder {
+        self.channel_gains = Some(gains);
+        self
+    }
+
+    pub fn name<S: Into<String>>(&mut self, name: S) -> &mut JackBuilder {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn ports<S: Into<String>, I: IntoIterator<Item = S>>(
+        &mut self,
+        ports: I,
+    ) -> &mut JackBuilder {
+        self.ports = Some(ports.into_iter().map(|p| p.into()).collect());
+        self
+    }
+
+    pub fn create(&self) -> Result<JackRecorder, JackError> {
+        JackRecorder::from_builder(self)
+    }
+
+    /// Build the recorder, panicking with a descriptive message if JACK can't be used
+    pub fn build(&self) -> Box<dyn super::Recorder> {
+        match self.create() {
+            Ok(rec) => Box::new(rec),
+            Err(e) => {
+                log::error!("{}", e);
+                panic!("{}", e);
+            }
+        }
+    }
+}
+
+/// Error connecting to JACK
+#[derive(Debug)]
+pub enum JackError {
+    /// The JACK server rejected a request
+    Jack(jack::Error),
+    /// The JACK server runs at a different sample rate than the configured one
+    Rate { server: usize, expected: usize },
+}
+
+impl fmt::Display for JackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JackError::Jack(e) => write!(f, "JACK error: {}", e),
+            JackError::Rate { server, expected } => write!(
+                f,
+                "JACK server runs at {} Hz but \"audio.rate\" is {} Hz",
+                server, expected,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for JackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JackError::Jack(e) => Some(e),
+            JackError::Rate { .. } => None,
+        }
+    }
+}
+
+impl From<jack::Error> for JackError {
+    fn from(e: jack::Error) -> JackError {
+        JackError::Jack(e)
+    }
+}
+
+/// Copies the input ports into the sample buffer
+///
+/// Runs on the real-time thread of JACK, so it must not allocate.  Frames are interleaved
+/// into a fixed scratch buffer and pushed chunk by chunk.
+struct Process {
+    ports: [jack::Port<jack::AudioIn>; 2],
+    buffer: analyzer::SampleBuffer,
+    gains: [analyzer::Sample; 2],
+    scratch: [[analyzer::Sample; 2]; CHUNK_SIZE],
+}
+
+impl jack::ProcessHandler for Process {
+    fn process(&mut self, _: &jack::Client, ps: &jack::ProcessScope) -> jack::Control {
+        let left = self.ports[0].as_slice(ps);
+        let right = self.ports[1].as_slice(ps);
+
+        for (left, right) in left.chunks(CHUNK_SIZE).zip(right.chunks(CHUNK_SIZE)) {
+            let chunk = &mut self.scratch[..left.len()];
+            for (s, (l, r)) in chunk.iter_mut().zip(left.iter().zip(right.iter())) {
+                *s = [*l, *r];
+            }
+            super::apply_channel_gains(chunk, self.gains);
+
+            self.buffer.push(chunk);
+        }
+
+        jack::Control::Continue
+    }
+}
+
+/// Clears the running flag when the JACK server shuts down
+struct Notifications(sync::Arc<atomic::AtomicBool>);
+
+impl jack::NotificationHandler for Notifications {
+    fn shutdown(&mut self, _status: jack::ClientStatus, reason: &str) {
+        log::error!("JACK server shut down: {}", reason);
+        self.0.store(false, atomic::Ordering::SeqCst);
+    }
+}
+
+/// Recorder capturing from two JACK input ports
+///
+/// Registers the ports `in_left` and `in_right` and copies their samples into the sample
+/// buffer from the process callback, so no extra latency is added.  The ports can be
+/// connected automatically, see [`JackBuilder::ports`](struct.JackBuilder.html#structfield.ports).
+///
+/// # Example
+/// ```no_run
+/// # use vis_core::recorder;
+/// # vis_core::default_config();
+/// let recorder = recorder::jack::JackBuilder::new()
+///     .ports(vec!["system:capture_1", "system:capture_2"])
+///     .build();
+/// ```
+pub struct JackRecorder {
+    buffer: analyzer::SampleBuffer,
+    period: usize,
+    running: sync::Arc<atomic::AtomicBool>,
+    client: Option<jack::AsyncClient<Notifications, Process>>,
+}
+
+impl fmt::Debug for JackRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("JackRecorder")
+            .field("rate", &self.buffer.rate())
+            .field("running", &self.running)
+            .finish()
+    }
+}
+
+impl JackRecorder {
+    fn from_builder(build: &JackBuilder) -> Result<JackRecorder, JackError> {
+        let rate = build
+            .rate
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.rate", 8000));
+        let buffer_size = build.buffer_size.unwrap_or_else(super::default_buffer_size);
+        let latency_samples = build
+            .latency_samples
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.latency_samples", 0));
+        let gains = build
+            .channel_gains
+            .unwrap_or_else(super::default_channel_gains);
+        let name = build
+            .name
+            .clone()
+            .unwrap_or_else(|| "visualizer2".to_string());
+        let ports = build
+            .ports
+            .clone()
+            .or_else(|| crate::CONFIG.get("jack.ports"))
+            .unwrap_or_default();
+
+        let (client, _status) = jack::Client::new(&name, jack::ClientOptions::NO_START_SERVER)?;
+        if client.sample_rate() != rate {
+            return Err(JackError::Rate {
+                server: client.sample_rate(),
+                expected: rate,
+            });
+        }
+
+        let inputs = [
+            client.register_port("in_left", jack::AudioIn)?,
+            client.register_port("in_right", jack::AudioIn)?,
+        ];
+        let input_names = [inputs[0].name()?, inputs[1].name()?];
+        let period = client.buffer_size() as usize;
+
+        log::debug!("JACK:");
+        log::debug!("    Client      = \"{}\"", client.name());
+        log::debug!("    Sample Rate = {:6}", rate);
+        log::debug!("    Period      = {:6}", period);
+        log::debug!("    Buffer Size = {:6}", buffer_size);
+        log::debug!("    Delay       = {:6}", latency_samples);
+        log::debug!("    Gains       = {:?}", gains);
+        log::debug!("    Ports       = {:?}", ports);
+
+        let buffer = analyzer::SampleBuffer::with_delay(buffer_size, rate, latency_samples);
+        let running = sync::Arc::new(atomic::AtomicBool::new(true));
+
+        let process = Process {
+            ports: inputs,
+            buffer: buffer.clone(),
+            gains,
+            scratch: [[0.0; 2]; CHUNK_SIZE],
+        };
+        let client = client.activate_async(Notifications(running.clone()), process)?;
+
+        for (i, input) in input_names.iter().enumerate() {
+            if let Some(port) = ports.get(i).or_else(|| ports.first()) {
+                client.as_client().connect_ports_by_name(port, input)?;
+            }
+        }
+
+        Ok(JackRecorder {
+            buffer,
+            period,
+            running,
+            client: Some(client),
+        })
+    }
+}
+
+impl super::Recorder for JackRecorder {
+    fn sample_buffer(&self) -> &analyzer::SampleBuffer {
+        &self.buffer
+    }
+
+    fn is_running(&self) -> bool {
+        self.running.load(atomic::Ordering::SeqCst)
+    }
+
+    fn latency(&self) -> f32 {
+        (self.period + self.buffer.delay()) as f32 / self.buffer.rate() as f32
+    }
+
+    /// Deactivate the client and close its ports
+    fn stop(&mut self) {
+        if let Some(client) = self.client.take() {
+            if let Err(e) = client.deactivate() {
+                log::error!("Failed to deactivate JACK client: {}", e);
+            }
+        }
+        self.running.store(false, atomic::Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_rate_error() {
+        let e = JackError::Rate {
+            server: 48000,
+            expected: 8000,
+        };
+
+        assert_eq!(
+            e.to_string(),
+            "JACK server runs at 48000 Hz but \"audio.rate\" is 8000 Hz"
+        );
+        assert!(e.source().is_none());
+    }
+
+    #[test]
+    fn test_jack_error() {
+        let e = JackError::from(jack::Error::PortRegistrationError("in_left".to_string()));
+
+        assert!(matches!(
+            e,
+            JackError::Jack(jack::Error::PortRegistrationError(ref p)) if p == "in_left"
+        ));
+        assert_eq!(
+            e.to_string(),
+            "JACK error: PortRegistrationError(\"in_left\")"
+        );
+        assert!(e.source().is_some());
+    }
+}
@@ -1,9 +1,29 @@
+#[cfg(feature = "alsa")]
+pub mod alsa;
+
 #[cfg(feature = "pulseaudio")]
 pub mod pulse;
 
 #[cfg(feature = "cpalrecord")]
 pub mod cpal;
 
+#[cfg(feature = "wavrecord")]
+pub mod file;
+
+#[cfg(feature = "jack")]
+pub mod jack;
+
+pub mod null;
+
+pub mod reconnect;
+
+pub mod resample;
+
+pub mod synth;
+
+#[cfg(feature = "wavrecord")]
+pub mod tee;
+
 use crate::analyzer;
 
 pub trait Recorder: std::fmt::Debug {
@@ -19,14 +39,125 @@ pub trait Recorder: std::fmt::Debug {
     fn sync(&mut self, _time: f32) -> bool {
         true
     }
+
+    /// Push initial samples before the first frame
+    ///
+    /// Recorders playing back a file fill the sample buffer with its opening, so the analysis
+    /// of the first frame does not only see silence.  Call this before iterating frames.
+    /// Live recorders ignore this call.
+    fn prime(&mut self) {}
+
+    /// Block until the recorder has produced its first samples
+    ///
+    /// Async recorders need a moment to open their device, during which the sample buffer
+    /// only contains silence.  Returns false if `timeout` elapsed first, eg. to show a
+    /// "connecting" state and try again.
+    fn wait_ready(&self, timeout: std::time::Duration) -> bool {
+        self.sample_buffer().wait_newer(0, timeout).is_some()
+    }
+
+    /// Return whether this recorder is still capturing
+    ///
+    /// Becomes false if the recording thread died, eg. because the device was unplugged.
+    fn is_running(&self) -> bool {
+        true
+    }
+
+    /// Return the delay between sound arriving and its samples being pushed, in seconds
+    ///
+    /// The default is the [delay](../analyzer/struct.SampleBuffer.html#method.delay) of the
+    /// sample buffer.  Recorders reading in chunks add the duration of one chunk.
+    fn latency(&self) -> f32 {
+        let buffer = self.sample_buffer();
+        buffer.delay() as f32 / buffer.rate() as f32
+    }
+
+    /// Return the rate the device captures at, in Hz
+    ///
+    /// Recorders [resample](resample/struct.Resampler.html) to the rate of their sample buffer
+    /// if the device does not support it.  Returns `None` while the device is not open yet.
+    /// The default is the rate of the sample buffer.
+    fn native_rate(&self) -> Option<usize> {
+        Some(self.sample_buffer().rate())
+    }
+
+    /// Stop capturing and let all threads of this recorder exit
+    ///
+    /// Afterwards, no more samples are pushed and [`is_running`](#method.is_running) returns
+    /// false.  Recorders without threads don't need to do anything.
+    fn stop(&mut self) {}
 }
 
+/// Clears a running flag when dropped, including when a recording thread panics
+#[cfg(any(feature = "cpalrecord", feature = "pulseaudio", feature = "alsa"))]
+struct RunningGuard(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+#[cfg(any(feature = "cpalrecord", feature = "pulseaudio", feature = "alsa"))]
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.store(false, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/// Per-channel gains if none were set explicitly
+///
+/// Can be set from config as `"audio.channel_gains"`, eg. `[1.0, 0.8]`.  Defaults to unity gain.
+pub fn default_channel_gains() -> [analyzer::Sample; 2] {
+    crate::CONFIG.get_or("audio.channel_gains", [1.0, 1.0])
+}
+
+/// Scale left and right samples of a chunk by their gains
+pub(crate) fn apply_channel_gains(
+    chunk: &mut [[analyzer::Sample; 2]],
+    gains: [analyzer::Sample; 2],
+) {
+    if gains == [1.0, 1.0] {
+        return;
+    }
+
+    for [l, r] in chunk.iter_mut() {
+        *l *= gains[0];
+        *r *= gains[1];
+    }
+}
+
+/// Default sample buffer size if none was set explicitly
+///
+/// Derived from the analysis windows configured as `"audio.fourier.*"` and
+/// `"audio.beat.*"` using [`recommended_buffer_size`](../analyzer/fn.recommended_buffer_size.html),
+/// but at least `16000`.  Set `"audio.buffer"` to override it.
+pub fn default_buffer_size() -> usize {
+    buffer_size_from(&crate::CONFIG)
+}
+
+fn buffer_size_from(config: &ezconf::Config) -> usize {
+    let fourier = analyzer::recommended_buffer_size(
+        config.get_or("audio.fourier.length", 512),
+        config.get_or("audio.fourier.downsample", 5),
+    );
+    let beat = analyzer::recommended_buffer_size(
+        config.get_or("audio.beat.fourier_length", 16),
+        config.get_or("audio.beat.downsample", 10),
+    );
+
+    config
+        .get("audio.buffer")
+        .unwrap_or_else(|| fourier.max(beat).max(16000))
+}
+
+/// Recorders which only push samples from [`Recorder::sync`](trait.Recorder.html#method.sync)
+const SYNC_RECORDERS: &[&str] = &["wav", "synth"];
+
 #[derive(Debug, Clone, Default)]
 pub struct RecorderBuilder {
     pub rate: Option<usize>,
     pub buffer_size: Option<usize>,
     pub read_size: Option<usize>,
+    pub latency_samples: Option<usize>,
+    pub channel_gains: Option<[analyzer::Sample; 2]>,
     pub recorder: Option<String>,
+    pub tee: Option<String>,
+    pub auto_reconnect: Option<bool>,
 }
 
 impl RecorderBuilder {
@@ -49,24 +180,98 @@ impl RecorderBuilder {
         self
     }
 
+    /// Delay captured samples by `n` samples to align visuals with the audio source
+    ///
+    /// This trades latency for alignment.  Can also be set from config as
+    /// `"audio.latency_samples"`.
+    pub fn latency_samples(&mut self, n: usize) -> &mut RecorderBuilder {
+        self.latency_samples = Some(n);
+        self
+    }
+
+    /// Scale the left and right channel independently, eg. to fix an imbalanced input
+    ///
+    /// Applied to the samples as they are captured.  Can also be set from config as
+    /// `"audio.channel_gains"`.
+    pub fn channel_gains(&mut self, gains: [analyzer::Sample; 2]) -> &mut RecorderBuilder {
+        self.channel_gains = Some(gains);
+        self
+    }
+
     pub fn recorder<S: Into<String>>(&mut self, rec: S) -> &mut RecorderBuilder {
         self.recorder = Some(rec.into());
         self
     }
 
+    /// Additionally write all captured samples to the WAV file at `path`
+    ///
+    /// Requires the `wavrecord` feature.  Can also be set from config as `"audio.tee"`.
+    pub fn tee<S: Into<String>>(&mut self, path: S) -> &mut RecorderBuilder {
+        self.tee = Some(path.into());
+        self
+    }
+
+    /// Rebuild the recorder whenever it stops running, eg. after unplugging the device
+    ///
+    /// See [`ReconnectRecorder`](reconnect/struct.ReconnectRecorder.html).  Defaults to
+    /// `false`, can also be set from config as `"audio.auto_reconnect"`.
+    ///
+    /// Recorders driven by [`sync`](trait.Recorder.html#method.sync), like `"wav"` or `"synth"`,
+    /// have no device to lose and are never wrapped, as the supervisor thread can't forward
+    /// `sync`.
+    pub fn auto_reconnect(&mut self, auto_reconnect: bool) -> &mut RecorderBuilder {
+        self.auto_reconnect = Some(auto_reconnect);
+        self
+    }
+
     pub fn build(&mut self) -> Box<dyn Recorder> {
-        let recorder = self
-            .recorder
-            .as_ref()
-            .map(|s| s.clone())
-            .unwrap_or_else(|| crate::CONFIG.get_or("audio.recorder", "cpal".to_string()));
+        let auto_reconnect = self
+            .auto_reconnect
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.auto_reconnect", false));
+        let rec = if auto_reconnect && !SYNC_RECORDERS.contains(&&*self.recorder_name()) {
+            let mut build = self.clone();
+            Box::new(self::reconnect::ReconnectRecorder::new(Box::new(move || {
+                build.build_recorder()
+            })))
+        } else {
+            self.build_recorder()
+        };
+
+        match self
+            .tee
+            .clone()
+            .or_else(|| crate::CONFIG.get::<String>("audio.tee"))
+        {
+            #[cfg(feature = "wavrecord")]
+            Some(path) => Box::new(
+                self::tee::TeeRecorder::new(rec, &path).expect("Can't create capture file"),
+            ),
+
+            #[cfg(not(feature = "wavrecord"))]
+            Some(_) => {
+                log::warn!("Not writing capture file, `wavrecord` feature is disabled");
+                rec
+            }
+
+            None => rec,
+        }
+    }
 
-        match &*recorder {
+    fn recorder_name(&self) -> String {
+        self.recorder
+            .clone()
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.recorder", "cpal".to_string()))
+    }
+
+    fn build_recorder(&mut self) -> Box<dyn Recorder> {
+        match &*self.recorder_name() {
             #[cfg(feature = "cpalrecord")]
             "cpal" => self::cpal::CPalBuilder {
                 rate: self.rate,
                 buffer_size: self.buffer_size,
                 read_size: self.read_size,
+                latency_samples: self.latency_samples,
+                channel_gains: self.channel_gains,
                 ..Default::default()
             }
             .build(),
@@ -76,6 +281,53 @@ impl RecorderBuilder {
                 rate: self.rate,
                 buffer_size: self.buffer_size,
                 read_size: self.read_size,
+                latency_samples: self.latency_samples,
+                channel_gains: self.channel_gains,
+                ..Default::default()
+            }
+            .build(),
+
+            #[cfg(feature = "alsa")]
+            "alsa" => self::alsa::AlsaBuilder {
+                rate: self.rate,
+                buffer_size: self.buffer_size,
+                read_size: self.read_size,
+                latency_samples: self.latency_samples,
+                channel_gains: self.channel_gains,
+                ..Default::default()
+            }
+            .build(),
+
+            #[cfg(feature = "jack")]
+            "jack" => self::jack::JackBuilder {
+                rate: self.rate,
+                buffer_size: self.buffer_size,
+                latency_samples: self.latency_samples,
+                channel_gains: self.channel_gains,
+                ..Default::default()
+            }
+            .build(),
+
+            #[cfg(feature = "wavrecord")]
+            "wav" => self::file::WavBuilder {
+                rate: self.rate,
+                buffer_size: self.buffer_size,
+                channel_gains: self.channel_gains,
+                ..Default::default()
+            }
+            .build(),
+
+            "null" => self::null::NullBuilder {
+                rate: self.rate,
+                buffer_size: self.buffer_size,
+                ..Default::default()
+            }
+            .build(),
+
+            "synth" => self::synth::SynthBuilder {
+                rate: self.rate,
+                buffer_size: self.buffer_size,
+                channel_gains: self.channel_gains,
                 ..Default::default()
             }
             .build(),
@@ -86,3 +338,141 @@ impl RecorderBuilder {
         }
     }
 }
+
+/// Recorder for tests which only hands out a buffer the test pushes to itself
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct TestRecorder(pub(crate) analyzer::SampleBuffer);
+
+#[cfg(test)]
+impl Recorder for TestRecorder {
+    fn sample_buffer(&self) -> &analyzer::SampleBuffer {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time;
+
+    fn test_wav_path() -> std::path::PathBuf {
+        std::env::temp_dir().join("vis-core-recorder-test.wav")
+    }
+
+    /// Initialize the config for tests going through `RecorderBuilder::build`
+    fn test_config() {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| {
+            let config = format!("[audio.file]\npath = {:?}\n", test_wav_path());
+            let config = Box::leak(config.into_boxed_str());
+            crate::CONFIG
+                .init([ezconf::Source::Memory(config)].iter())
+                .expect("Can't load test config");
+        });
+    }
+
+    #[test]
+    fn test_wait_ready() {
+        let recorder = TestRecorder(analyzer::SampleBuffer::new(16, 8000));
+        assert!(!recorder.wait_ready(time::Duration::from_millis(10)));
+
+        let buffer = recorder.sample_buffer().clone();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(time::Duration::from_millis(10));
+            buffer.push(&[[1.0; 2]; 4]);
+        });
+        assert!(recorder.wait_ready(time::Duration::from_secs(5)));
+        handle.join().unwrap();
+
+        // Stays ready once data was pushed
+        assert!(recorder.wait_ready(time::Duration::from_millis(0)));
+    }
+
+    #[test]
+    fn test_default_buffer_size() {
+        let config = |s: &str| {
+            let config = ezconf::INIT;
+            config.init([ezconf::Source::Memory(s)].iter()).unwrap();
+            config
+        };
+
+        // The default windows are small, so the minimum applies
+        assert_eq!(buffer_size_from(&config("")), 16000);
+
+        // The larger of both windows wins
+        let fourier = "[audio.fourier]\nlength = 4096\ndownsample = 4\n";
+        let beat = "[audio.beat]\nfourier_length = 1024\ndownsample = 10\n";
+        assert_eq!(buffer_size_from(&config(fourier)), 4096 * 4 * 2);
+        assert_eq!(buffer_size_from(&config(beat)), 1024 * 10 * 2);
+        let both = format!("{}{}", fourier, beat);
+        assert_eq!(buffer_size_from(&config(&both)), 4096 * 4 * 2);
+
+        // An explicit size is used as is, even if it is smaller
+        let explicit = format!("[audio]\nbuffer = 4000\n{}", fourier);
+        assert_eq!(buffer_size_from(&config(&explicit)), 4000);
+    }
+
+    #[test]
+    fn test_channel_gains() {
+        let mut chunk = (0..800)
+            .map(|i| {
+                let s = (i as f32 * 0.1).sin();
+                [s, s]
+            })
+            .collect::<Vec<_>>();
+        apply_channel_gains(&mut chunk, [1.5, 0.5]);
+
+        let rms = |c: usize| {
+            (chunk.iter().map(|s| s[c] * s[c]).sum::<f32>() / chunk.len() as f32).sqrt()
+        };
+        assert!((rms(0) / rms(1) - 3.0).abs() < 1e-4);
+        assert!((rms(0) - 1.5 / 2.0f32.sqrt()).abs() < 1e-2);
+    }
+
+    #[cfg(feature = "wavrecord")]
+    #[test]
+    fn test_auto_reconnect_wav() {
+        test_config();
+
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(test_wav_path(), spec).unwrap();
+        for _ in 0..800 * 2 {
+            writer.write_sample(16384i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut recorder = RecorderBuilder::new()
+            .recorder("wav")
+            .rate(8000)
+            .buffer_size(1000)
+            .auto_reconnect(true)
+            .build();
+
+        // Samples only arrive if sync reaches the wav recorder
+        assert!(recorder.sync(0.05));
+        let buffer = recorder.sample_buffer();
+        assert_eq!(buffer.samples_since(0), 400);
+        assert_eq!(buffer.iter(1, 1).next(), Some([0.5; 2]));
+    }
+
+    #[test]
+    fn test_auto_reconnect_synth() {
+        test_config();
+
+        let mut recorder = RecorderBuilder::new()
+            .recorder("synth")
+            .rate(8000)
+            .buffer_size(1000)
+            .auto_reconnect(true)
+            .build();
+
+        assert!(recorder.sync(0.05));
+        assert_eq!(recorder.sample_buffer().samples_since(0), 400);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::analyzer;
+
+/// Builder for NullRecorder
+#[derive(Debug, Default)]
+pub struct NullBuilder {
+    /// Sample rate of the buffer
+    ///
+    /// Can also be set from config as `"audio.rate"`.
+    pub rate: Option<usize>,
+    pub buffer_size: Option<usize>,
+
+    /// Samples pushed once on creation
+    ///
+    /// Lets tests analyze known input deterministically.  Without samples, the buffer only
+    /// contains silence.
+    pub samples: Option<Vec<[analyzer::Sample; 2]>>,
+}
+
+impl NullBuilder {
+    pub fn new() -> NullBuilder {
+        Default::default()
+    }
+
+    pub fn rate(&mut self, rate: usize) -> &mut NullBuilder {
+        self.rate = Some(rate);
+        self
+    }
+
+    pub fn buffer_size(&mut self, buffer_size: usize) -> &mut NullBuilder {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+
+    pub fn samples<I: IntoIterator<Item = [analyzer::Sample; 2]>>(
+        &mut self,
+        samples: I,
+    ) -> &mut NullBuilder {
+        self.samples = Some(samples.into_iter().collect());
+        self
+    }
+
+    pub fn create(&self) -> NullRecorder {
+        NullRecorder::from_builder(self)
+    }
+
+    pub fn build(&self) -> Box<dyn super::Recorder> {
+        Box::new(self.create())
+    }
+}
+
+/// Recorder without any audio input
+///
+/// Owns a sample buffer which is never written after creation, so it works on machines
+/// without a sound card, eg. for CI and headless examples.  The buffer holds silence unless
+/// it was [seeded](struct.NullBuilder.html#structfield.samples) with fixed samples.
+///
+/// # Example
+/// ```
+/// # use vis_core::{analyzer, recorder};
+/// # vis_core::default_config();
+/// let recorder = recorder::null::NullBuilder::new()
+///     .rate(8000)
+///     .buffer_size(1024)
+///     .samples(vec![[0.5, 0.5]; 1024])
+///     .build();
+///
+/// assert!(recorder.sample_buffer().volume(0.1) > 0.0);
+/// ```
+#[derive(Debug)]
+pub struct NullRecorder {
+    buffer: analyzer::SampleBuffer,
+}
+
+impl NullRecorder {
+    fn from_builder(build: &NullBuilder) -> NullRecorder {
+        let rate = build
+            .rate
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.rate", 8000));
+        let buffer_size = build.buffer_size.unwrap_or_else(super::default_buffer_size);
+
+        let buffer = analyzer::SampleBuffer::new(buffer_size, rate);
+        if let Some(ref samples) = build.samples {
+            buffer.push(samples);
+        }
+
+        log::debug!("Null:");
+        log::debug!("    Sample Rate = {:6}", rate);
+        log::debug!("    Buffer Size = {:6}", buffer_size);
+        log::debug!(
+            "    Samples     = {:6}",
+            build.samples.as_ref().map(|s| s.len()).unwrap_or(0)
+        );
+
+        NullRecorder { buffer }
+    }
+}
+
+impl super::Recorder for NullRecorder {
+    fn sample_buffer(&self) -> &analyzer::SampleBuffer {
+        &self.buffer
+    }
+
+    /// There is nothing to wait for, so this returns true right away
+    fn wait_ready(&self, _timeout: std::time::Duration) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+
+    #[test]
+    fn test_silence() {
+        let recorder = NullBuilder::new().rate(8000).buffer_size(256).create();
+
+        assert!(recorder.wait_ready(std::time::Duration::from_millis(0)));
+        assert_eq!(recorder.sample_buffer().rate(), 8000);
+        assert_eq!(recorder.sample_buffer().volume(0.01), 0.0);
+    }
+
+    #[test]
+    fn test_samples() {
+        let mut recorder = NullBuilder::new()
+            .rate(8000)
+            .buffer_size(256)
+            .samples((0..256).map(|i| [i as f32, -(i as f32)]))
+            .create();
+
+        assert!(recorder.sync(1.0));
+        let last = recorder.sample_buffer().iter(4, 1).collect::<Vec<_>>();
+        assert_eq!(
+            last,
+            [
+                [252.0, -252.0],
+                [253.0, -253.0],
+                [254.0, -254.0],
+                [255.0, -255.0]
+            ]
+        );
+    }
+}
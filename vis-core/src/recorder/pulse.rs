@@ -1,4 +1,5 @@
 use crate::analyzer;
+use std::sync::{self, atomic};
 use std::thread;
 
 #[derive(Debug, Default)]
@@ -6,6 +7,8 @@ pub struct PulseBuilder {
     pub rate: Option<usize>,
     pub read_size: Option<usize>,
     pub buffer_size: Option<usize>,
+    pub latency_samples: Option<usize>,
+    pub channel_gains: Option<[analyzer::Sample; 2]>,
     pub name: Option<(String, String)>,
     pub device: Option<String>,
 }
@@ -44,6 +47,16 @@ impl PulseBuilder {
         self
     }
 
+    pub fn latency_samples(&mut self, n: usize) -> &mut PulseBuilder {
+        self.latency_samples = Some(n);
+        self
+    }
+
+    pub fn channel_gains(&mut self, gains: [analyzer::Sample; 2]) -> &mut PulseBuilder {
+        self.channel_gains = Some(gains);
+        self
+    }
+
     pub fn create(&self) -> PulseRecorder {
         PulseRecorder::from_builder(self)
     }
@@ -56,7 +69,10 @@ impl PulseBuilder {
 #[derive(Debug)]
 pub struct PulseRecorder {
     rate: usize,
+    read_size: usize,
     buffer: analyzer::SampleBuffer,
+    running: sync::Arc<atomic::AtomicBool>,
+    stop: sync::Arc<atomic::AtomicBool>,
 }
 
 impl PulseRecorder {
@@ -64,9 +80,7 @@ impl PulseRecorder {
         let rate = build
             .rate
             .unwrap_or_else(|| crate::CONFIG.get_or("audio.rate", 8000));
-        let buffer_size = build
-            .buffer_size
-            .unwrap_or_else(|| crate::CONFIG.get_or("audio.buffer", 16000));
+        let buffer_size = build.buffer_size.unwrap_or_else(super::default_buffer_size);
         let read_size = build
             .buffer_size
             .unwrap_or_else(|| crate::CONFIG.get_or("audio.read_size", 32));
@@ -79,14 +93,26 @@ impl PulseRecorder {
             .clone()
             .or_else(|| crate::CONFIG.get("pulse.device"));
 
-        let buf = analyzer::SampleBuffer::new(buffer_size, rate);
+        let latency_samples = build
+            .latency_samples
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.latency_samples", 0));
+        let gains = build
+            .channel_gains
+            .unwrap_or_else(super::default_channel_gains);
+
+        let buf = analyzer::SampleBuffer::with_delay(buffer_size, rate, latency_samples);
+        let running = sync::Arc::new(atomic::AtomicBool::new(true));
+        let stop = sync::Arc::new(atomic::AtomicBool::new(false));
 
         {
             let buf = buf.clone();
+            let running = running.clone();
+            let stop = stop.clone();
 
             thread::Builder::new()
                 .name("pulse-recorder".into())
                 .spawn(move || {
+                    let _guard = super::RunningGuard(running);
                     let rec: pulse_simple::Record<[analyzer::Sample; 2]> =
                         pulse_simple::Record::new(
                             &name,
@@ -101,14 +127,17 @@ impl PulseRecorder {
                     log::debug!("    Sample Rate = {:6}", rate);
                     log::debug!("    Read Size   = {:6}", read_size);
                     log::debug!("    Buffer Size = {:6}", buffer_size);
+                    log::debug!("    Delay       = {:6}", latency_samples);
+                    log::debug!("    Gains       = {:?}", gains);
                     if let Some(ref name) = device {
                         log::debug!("    Device      = \"{}\"", name);
                     } else {
                         log::debug!("    Device      = \"default\"");
                     }
 
-                    loop {
+                    while !stop.load(atomic::Ordering::SeqCst) {
                         rec.read(&mut read_buf);
+                        super::apply_channel_gains(&mut read_buf, gains);
 
                         buf.push(&read_buf);
                         log::trace!("Pushed {} samples", read_size);
@@ -117,7 +146,13 @@ impl PulseRecorder {
                 .unwrap();
         }
 
-        PulseRecorder { rate, buffer: buf }
+        PulseRecorder {
+            rate,
+            read_size,
+            buffer: buf,
+            running,
+            stop,
+        }
     }
 }
 
@@ -125,4 +160,23 @@ impl super::Recorder for PulseRecorder {
     fn sample_buffer<'a>(&'a self) -> &'a analyzer::SampleBuffer {
         &self.buffer
     }
+
+    fn is_running(&self) -> bool {
+        self.running.load(atomic::Ordering::SeqCst)
+    }
+
+    fn latency(&self) -> f32 {
+        (self.read_size + self.buffer.delay()) as f32 / self.rate as f32
+    }
+
+    /// Stop recording, the thread exits after its current read
+    fn stop(&mut self) {
+        self.stop.store(true, atomic::Ordering::SeqCst);
+    }
+}
+
+impl Drop for PulseRecorder {
+    fn drop(&mut self) {
+        super::Recorder::stop(self);
+    }
 }
@@ -0,0 +1,302 @@
+use crate::analyzer;
+use std::{
+    panic,
+    sync::{self, atomic, mpsc},
+    thread, time,
+};
+
+/// Builds a fresh recorder, eg. after the previous one lost its device
+pub type Factory = Box<dyn FnMut() -> Box<dyn super::Recorder> + Send>;
+
+/// Recorder rebuilding its inner recorder when that one stops running
+///
+/// The inner recorder lives on a supervisor thread which checks
+/// [`is_running`](trait.Recorder.html#method.is_running) regularly.  Once the inner recorder
+/// stopped, eg. because its device was unplugged, it is rebuilt using `factory`, backing off
+/// exponentially between failed attempts.
+///
+/// Samples are forwarded into a sample buffer owned by this recorder, so the analyzer can keep
+/// using the same buffer across reconnects.  Size, rate and delay of this buffer are taken from
+/// the first recorder; later recorders with a different rate are rejected.
+///
+/// [`sync`](trait.Recorder.html#method.sync) is not forwarded to the inner recorder, so only
+/// recorders capturing on their own can be supervised.
+///
+/// # Example
+/// ```no_run
+/// # use vis_core::recorder;
+/// # vis_core::default_config();
+/// let recorder = recorder::reconnect::ReconnectRecorder::new(Box::new(|| {
+///     recorder::RecorderBuilder::new().build()
+/// }));
+/// ```
+pub struct ReconnectRecorder {
+    buffer: analyzer::SampleBuffer,
+    /// Latency of the first recorder, rebuilt recorders are assumed to be alike
+    latency: f32,
+    /// Native rate of the current recorder, `0` while it has none
+    native_rate: sync::Arc<atomic::AtomicUsize>,
+    running: sync::Arc<atomic::AtomicBool>,
+    reconnects: sync::Arc<atomic::AtomicUsize>,
+    stop: sync::Arc<atomic::AtomicBool>,
+}
+
+impl std::fmt::Debug for ReconnectRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("ReconnectRecorder")
+            .field("running", &self.running.load(atomic::Ordering::SeqCst))
+            .field("reconnects", &self.reconnects())
+            .finish()
+    }
+}
+
+impl ReconnectRecorder {
+    /// Supervise recorders built by `factory`, backing off from 0.5s up to 10s
+    pub fn new(factory: Factory) -> ReconnectRecorder {
+        ReconnectRecorder::with_backoff(
+            factory,
+            time::Duration::from_millis(500),
+            time::Duration::from_secs(10),
+        )
+    }
+
+    /// Supervise recorders built by `factory` with a custom backoff
+    ///
+    /// The first rebuild is attempted after `initial`.  The delay doubles with every failed
+    /// attempt, up to `max`.
+    ///
+    /// Panics if the first recorder can't be built.
+    pub fn with_backoff(
+        mut factory: Factory,
+        initial: time::Duration,
+        max: time::Duration,
+    ) -> ReconnectRecorder {
+        let running = sync::Arc::new(atomic::AtomicBool::new(true));
+        let reconnects = sync::Arc::new(atomic::AtomicUsize::new(0));
+        let native_rate = sync::Arc::new(atomic::AtomicUsize::new(0));
+        let stop = sync::Arc::new(atomic::AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+
+        {
+            let running = running.clone();
+            let reconnects = reconnects.clone();
+            let native_rate = native_rate.clone();
+            let stop = stop.clone();
+
+            thread::Builder::new()
+                .name("recorder-supervisor".into())
+                .spawn(move || {
+                    let mut recorder = factory();
+                    let buffer = {
+                        let inner = recorder.sample_buffer();
+                        analyzer::SampleBuffer::with_delay(
+                            inner.size(),
+                            inner.rate(),
+                            inner.delay(),
+                        )
+                    };
+                    forward(&*recorder, &buffer);
+                    native_rate.store(
+                        recorder.native_rate().unwrap_or(0),
+                        atomic::Ordering::SeqCst,
+                    );
+                    sender.send((buffer.clone(), recorder.latency())).unwrap();
+
+                    log::debug!("Reconnect:");
+                    log::debug!("    Initial Backoff = {:?}", initial);
+                    log::debug!("    Maximum Backoff = {:?}", max);
+
+                    let mut backoff = initial;
+                    while !stop.load(atomic::Ordering::SeqCst) {
+                        if recorder.is_running() {
+                            // The device may only be opened after a while
+                            native_rate.store(
+                                recorder.native_rate().unwrap_or(0),
+                                atomic::Ordering::SeqCst,
+                            );
+                            thread::sleep(initial.min(time::Duration::from_millis(100)));
+                            continue;
+                        }
+                        running.store(false, atomic::Ordering::SeqCst);
+                        native_rate.store(0, atomic::Ordering::SeqCst);
+
+                        log::warn!("Recorder stopped, reconnecting in {:?}", backoff);
+                        thread::sleep(backoff);
+                        backoff = (backoff * 2).min(max);
+
+                        let new = panic::catch_unwind(panic::AssertUnwindSafe(&mut factory));
+                        let new = match new {
+                            Ok(new) => new,
+                            Err(_) => {
+                                log::error!("Failed to rebuild recorder");
+                                continue;
+                            }
+                        };
+                        if new.sample_buffer().rate() != buffer.rate() {
+                            log::error!(
+                                "Rebuilt recorder has the wrong rate ({} instead of {})",
+                                new.sample_buffer().rate(),
+                                buffer.rate(),
+                            );
+                            continue;
+                        }
+
+                        recorder = new;
+                        forward(&*recorder, &buffer);
+                        backoff = initial;
+                        running.store(true, atomic::Ordering::SeqCst);
+                        reconnects.fetch_add(1, atomic::Ordering::SeqCst);
+                        log::info!("Recorder reconnected");
+                    }
+
+                    recorder.stop();
+                    running.store(false, atomic::Ordering::SeqCst);
+                })
+                .unwrap();
+        }
+
+        let (buffer, latency) = receiver
+            .recv()
+            .expect("Failed to build the initial recorder");
+
+        ReconnectRecorder {
+            buffer,
+            latency,
+            native_rate,
+            running,
+            reconnects,
+            stop,
+        }
+    }
+
+    /// Return how often the inner recorder was rebuilt
+    pub fn reconnects(&self) -> usize {
+        self.reconnects.load(atomic::Ordering::SeqCst)
+    }
+}
+
+/// Forward everything `recorder` captures into `buffer`
+fn forward(recorder: &dyn super::Recorder, buffer: &analyzer::SampleBuffer) {
+    let buffer = buffer.clone();
+    recorder
+        .sample_buffer()
+        .add_tap(move |chunk| buffer.push(chunk));
+}
+
+impl Drop for ReconnectRecorder {
+    fn drop(&mut self) {
+        self.stop.store(true, atomic::Ordering::SeqCst);
+    }
+}
+
+impl super::Recorder for ReconnectRecorder {
+    fn sample_buffer(&self) -> &analyzer::SampleBuffer {
+        &self.buffer
+    }
+
+    fn is_running(&self) -> bool {
+        self.running.load(atomic::Ordering::SeqCst)
+    }
+
+    fn latency(&self) -> f32 {
+        self.latency
+    }
+
+    /// Return the native rate of the current inner recorder
+    ///
+    /// `None` while reconnecting.
+    fn native_rate(&self) -> Option<usize> {
+        match self.native_rate.load(atomic::Ordering::SeqCst) {
+            0 => None,
+            rate => Some(rate),
+        }
+    }
+
+    /// Stop the supervisor thread and the inner recorder
+    ///
+    /// The inner recorder is stopped by the supervisor, which can take until the end of a
+    /// running backoff.
+    fn stop(&mut self) {
+        self.stop.store(true, atomic::Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+
+    #[derive(Debug)]
+    struct FlakyRecorder(analyzer::SampleBuffer, sync::Arc<atomic::AtomicBool>);
+
+    impl Recorder for FlakyRecorder {
+        fn sample_buffer(&self) -> &analyzer::SampleBuffer {
+            &self.0
+        }
+
+        fn is_running(&self) -> bool {
+            self.1.load(atomic::Ordering::SeqCst)
+        }
+    }
+
+    #[derive(Debug)]
+    struct ResampledRecorder(analyzer::SampleBuffer);
+
+    impl Recorder for ResampledRecorder {
+        fn sample_buffer(&self) -> &analyzer::SampleBuffer {
+            &self.0
+        }
+
+        fn native_rate(&self) -> Option<usize> {
+            Some(44100)
+        }
+    }
+
+    #[test]
+    fn test_native_rate() {
+        let recorder = ReconnectRecorder::new(Box::new(|| {
+            Box::new(ResampledRecorder(analyzer::SampleBuffer::new(16, 8000)))
+        }));
+
+        assert_eq!(recorder.sample_buffer().rate(), 8000);
+        assert_eq!(recorder.native_rate(), Some(44100));
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let (sender, receiver) = mpsc::channel();
+        let recorder = ReconnectRecorder::with_backoff(
+            Box::new(move || {
+                let buffer = analyzer::SampleBuffer::new(16, 8000);
+                let running = sync::Arc::new(atomic::AtomicBool::new(true));
+                sender.send((buffer.clone(), running.clone())).unwrap();
+                Box::new(FlakyRecorder(buffer, running))
+            }),
+            time::Duration::from_millis(1),
+            time::Duration::from_millis(10),
+        );
+        let timeout = time::Duration::from_secs(5);
+
+        let (first, first_running) = receiver.recv_timeout(timeout).unwrap();
+        first.push(&[[1.0; 2]; 4]);
+        assert!(recorder.wait_ready(timeout));
+        assert_eq!(recorder.sample_buffer().iter(4, 1).next(), Some([1.0; 2]));
+
+        // Simulate an unplugged device
+        first_running.store(false, atomic::Ordering::SeqCst);
+        let (second, _) = receiver.recv_timeout(timeout).unwrap();
+
+        let start = time::Instant::now();
+        while recorder.reconnects() == 0 {
+            assert!(start.elapsed() < timeout, "Recorder was not rebuilt");
+            thread::sleep(time::Duration::from_millis(1));
+        }
+        assert!(recorder.is_running());
+
+        // The new recorder feeds the same buffer
+        let generation = recorder.sample_buffer().generation();
+        second.push(&[[2.0; 2]; 4]);
+        assert_eq!(recorder.sample_buffer().samples_since(generation), 4);
+        assert_eq!(recorder.sample_buffer().iter(4, 1).next(), Some([2.0; 2]));
+    }
+}
@@ -0,0 +1,223 @@
+use crate::analyzer;
+
+/// Streaming sample rate converter for recorders
+///
+/// Lets a recorder capture at whatever rate the device supports and still push samples at
+/// the configured `"audio.rate"`.  Uses linear interpolation.  When downsampling, the input
+/// is first averaged over one output period.  This dampens aliasing of frequencies well above
+/// the new Nyquist frequency, at the cost of delaying the signal by half an output period.
+///
+/// Chunks can have any size, the output is the same as converting the whole stream at once.
+///
+/// # Example
+/// ```
+/// # use vis_core::{analyzer, recorder};
+/// let buffer = analyzer::SampleBuffer::new(8000, 8000);
+/// let mut resampler = recorder::resample::Resampler::new(48000, 8000);
+///
+/// // In the capture loop
+/// let chunk = vec![[0.0; 2]; 480];
+/// buffer.push(resampler.process(&chunk));
+/// # assert_eq!(buffer.samples_since(0), 80);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    from: usize,
+    to: usize,
+    /// Input frames per output frame
+    step: f64,
+    /// Position of the next output frame between `previous` at 0 and the next input at 1
+    position: f64,
+    previous: [analyzer::Sample; 2],
+
+    /// Last input frames for the moving average, empty when not downsampling
+    history: Vec<[analyzer::Sample; 2]>,
+    history_pos: usize,
+    sum: [f64; 2],
+
+    output: Vec<[analyzer::Sample; 2]>,
+}
+
+impl Resampler {
+    /// Create a converter from `from` Hz to `to` Hz
+    pub fn new(from: usize, to: usize) -> Resampler {
+        assert!(from > 0 && to > 0, "Sample rates must be positive!");
+
+        let step = from as f64 / to as f64;
+        let width = step.round() as usize;
+        Resampler {
+            from,
+            to,
+            step,
+            position: 1.0,
+            previous: [0.0; 2],
+
+            history: if width > 1 {
+                vec![[0.0; 2]; width]
+            } else {
+                Vec::new()
+            },
+            history_pos: 0,
+            sum: [0.0; 2],
+
+            output: Vec::new(),
+        }
+    }
+
+    /// Rate of the input in Hz
+    pub fn from_rate(&self) -> usize {
+        self.from
+    }
+
+    /// Rate of the output in Hz
+    pub fn to_rate(&self) -> usize {
+        self.to
+    }
+
+    /// Whether both rates are the same, so the input is passed through untouched
+    pub fn is_passthrough(&self) -> bool {
+        self.from == self.to
+    }
+
+    /// Convert the next chunk of the stream
+    pub fn process<'a>(
+        &'a mut self,
+        input: &'a [[analyzer::Sample; 2]],
+    ) -> &'a [[analyzer::Sample; 2]] {
+        if self.is_passthrough() {
+            return input;
+        }
+
+        self.output.clear();
+        for frame in input.iter() {
+            let frame = self.average(*frame);
+
+            while self.position <= 1.0 {
+                let t = self.position as analyzer::Sample;
+                self.output.push([
+                    self.previous[0] + (frame[0] - self.previous[0]) * t,
+                    self.previous[1] + (frame[1] - self.previous[1]) * t,
+                ]);
+                self.position += self.step;
+            }
+
+            self.position -= 1.0;
+            self.previous = frame;
+        }
+
+        &self.output
+    }
+
+    /// Feed a frame into the moving average and return the current average
+    fn average(&mut self, frame: [analyzer::Sample; 2]) -> [analyzer::Sample; 2] {
+        if self.history.is_empty() {
+            return frame;
+        }
+
+        let old = std::mem::replace(&mut self.history[self.history_pos], frame);
+        self.history_pos = (self.history_pos + 1) % self.history.len();
+        for c in 0..2 {
+            self.sum[c] += frame[c] as f64 - old[c] as f64;
+        }
+
+        let width = self.history.len() as f64;
+        [
+            (self.sum[0] / width) as analyzer::Sample,
+            (self.sum[1] / width) as analyzer::Sample,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f32, rate: usize, len: usize) -> Vec<[analyzer::Sample; 2]> {
+        (0..len)
+            .map(|i| {
+                let s = (i as f32 / rate as f32 * freq * 2.0 * std::f32::consts::PI).sin();
+                [s, s * 0.5]
+            })
+            .collect()
+    }
+
+    fn peak(samples: &[[analyzer::Sample; 2]]) -> analyzer::Frequency {
+        let buf = analyzer::SampleBuffer::new(samples.len(), 8000);
+        buf.push(samples);
+
+        let mut analyzer = analyzer::FourierBuilder::new()
+            .rate(8000)
+            .length(512)
+            .window(analyzer::window::nuttall)
+            .downsample(1)
+            .channels(analyzer::fourier::Channels::Both)
+            .drop_dc(false)
+            .plan();
+        analyzer.analyze(&buf);
+        analyzer.left().argmax_freq()
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let input = sine(440.0, 8000, 100);
+        let mut resampler = Resampler::new(8000, 8000);
+
+        assert!(resampler.is_passthrough());
+        assert_eq!(resampler.process(&input), &input[..]);
+    }
+
+    #[test]
+    fn test_rates() {
+        for from in [44100, 48000, 4000, 11025] {
+            let input = sine(1000.0, from, from);
+            let mut resampler = Resampler::new(from, 8000);
+            let output = resampler.process(&input).to_vec();
+
+            assert!(
+                (output.len() as isize - 8000).abs() <= 1,
+                "{} Hz: {} samples",
+                from,
+                output.len()
+            );
+            let freq = peak(&output[output.len() - 1024..]);
+            assert!(
+                (freq - 1000.0).abs() <= 8000.0 / 512.0,
+                "{} Hz: {}",
+                from,
+                freq
+            );
+        }
+    }
+
+    #[test]
+    fn test_chunked() {
+        let input = sine(700.0, 44100, 10000);
+
+        let mut whole = Resampler::new(44100, 8000);
+        let expected = whole.process(&input).to_vec();
+
+        let mut chunked = Resampler::new(44100, 8000);
+        let mut output = Vec::new();
+        for chunk in input.chunks(333) {
+            output.extend_from_slice(chunked.process(chunk));
+        }
+
+        assert_eq!(output.len(), expected.len());
+        for (a, b) in output.iter().zip(expected.iter()) {
+            assert!((a[0] - b[0]).abs() < 1e-5 && (a[1] - b[1]).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_aliasing() {
+        // 7 kHz is above the output Nyquist frequency and would alias to 1 kHz
+        let input = sine(7000.0, 48000, 48000);
+        let mut resampler = Resampler::new(48000, 8000);
+        let output = resampler.process(&input);
+
+        let rms = (output[1000..].iter().map(|s| s[0] * s[0]).sum::<f32>()
+            / (output.len() - 1000) as f32)
+            .sqrt();
+        assert!(rms < 0.15, "{}", rms);
+    }
+}
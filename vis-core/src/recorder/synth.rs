@@ -0,0 +1,337 @@
+use crate::analyzer;
+use crate::ConfigExt;
+use std::f64::consts::PI;
+
+/// Frequency of the click sound, in the middle of the default beat range
+const CLICK_FREQUENCY: f64 = 75.0;
+/// Time constant of the exponential decay of a click in seconds
+const CLICK_DECAY: f64 = 0.02;
+/// Clicks are cut off after this many seconds
+const CLICK_LENGTH: f64 = 0.1;
+
+/// Frames generated per push
+const CHUNK_SIZE: usize = 256;
+
+/// Builder for SynthRecorder
+#[derive(Debug, Default)]
+pub struct SynthBuilder {
+    /// Sample rate of the generated audio
+    ///
+    /// Can also be set from config as `"audio.rate"`.
+    pub rate: Option<usize>,
+    pub buffer_size: Option<usize>,
+
+    /// Sine waves as `(frequency, amplitude)` pairs
+    ///
+    /// Can also be set from config as `"audio.synth.sines"`, eg. `[[440.0, 0.5]]`.  Defaults
+    /// to a single 440 Hz sine with an amplitude of `0.5`.
+    pub sines: Option<Vec<(analyzer::Frequency, analyzer::Sample)>>,
+
+    /// Tempo of the click track in beats per minute
+    ///
+    /// Each click is a short, decaying 75 Hz burst, like a kick drum.  `0` disables the
+    /// clicks.  Defaults to `0`, can also be set from config as `"audio.synth.bpm"`.
+    pub clicks: Option<f32>,
+
+    pub channel_gains: Option<[analyzer::Sample; 2]>,
+}
+
+impl SynthBuilder {
+    pub fn new() -> SynthBuilder {
+        Default::default()
+    }
+
+    pub fn rate(&mut self, rate: usize) -> &mut SynthBuilder {
+        self.rate = Some(rate);
+        self
+    }
+
+    pub fn buffer_size(&mut self, buffer_size: usize) -> &mut SynthBuilder {
+        self.buffer_size = Some(buffer_size);
+        self
+    }
+
+    /// Add a sine wave
+    pub fn sine(
+        &mut self,
+        freq: analyzer::Frequency,
+        amplitude: analyzer::Sample,
+    ) -> &mut SynthBuilder {
+        self.sines
+            .get_or_insert_with(Vec::new)
+            .push((freq, amplitude));
+        self
+    }
+
+    pub fn clicks(&mut self, bpm: f32) -> &mut SynthBuilder {
+        assert!(bpm >= 0.0, "Tempo must not be negative!");
+        self.clicks = Some(bpm);
+        self
+    }
+
+    pub fn channel_gains(&mut self, gains: [analyzer::Sample; 2]) -> &mut SynthBuilder {
+        self.channel_gains = Some(gains);
+        self
+    }
+
+    pub fn create(&self) -> SynthRecorder {
+        SynthRecorder::from_builder(self)
+    }
+
+    pub fn build(&self) -> Box<dyn super::Recorder> {
+        Box::new(self.create())
+    }
+}
+
+/// Recorder synthesizing a test signal
+///
+/// Generates a sum of sine waves and an optional click track instead of capturing audio.
+/// Like the [`WavRecorder`](../file/struct.WavRecorder.html), each
+/// [`sync`](../trait.Recorder.html#method.sync) pushes the samples up to the given time.
+/// Every sample is computed from its absolute time, so the output is reproducible no
+/// matter how the time advances.  Both channels carry the same signal.
+///
+/// # Example
+/// ```
+/// # use vis_core::recorder;
+/// # vis_core::default_config();
+/// let mut recorder = recorder::synth::SynthBuilder::new()
+///     .rate(8000)
+///     .sine(440.0, 0.3)
+///     .sine(1200.0, 0.1)
+///     .clicks(120.0)
+///     .build();
+///
+/// recorder.sync(1.0);
+/// assert_eq!(recorder.sample_buffer().samples_since(0), 8000);
+/// ```
+#[derive(Debug)]
+pub struct SynthRecorder {
+    buffer: analyzer::SampleBuffer,
+    sines: Vec<(analyzer::Frequency, analyzer::Sample)>,
+    click_interval: Option<f64>,
+    gains: [analyzer::Sample; 2],
+    /// Number of frames pushed so far
+    generated: usize,
+    chunk: Vec<[analyzer::Sample; 2]>,
+}
+
+impl SynthRecorder {
+    fn from_builder(build: &SynthBuilder) -> SynthRecorder {
+        let rate = build
+            .rate
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.rate", 8000));
+        let buffer_size = build.buffer_size.unwrap_or_else(super::default_buffer_size);
+        let sines = build.sines.clone().unwrap_or_else(|| {
+            crate::CONFIG
+                .get_vec("audio.synth.sines", vec![[440.0, 0.5]])
+                .into_iter()
+                .map(|[f, a]| (f, a))
+                .collect()
+        });
+        let clicks = build
+            .clicks
+            .unwrap_or_else(|| crate::CONFIG.get_or("audio.synth.bpm", 0.0));
+        let gains = build
+            .channel_gains
+            .unwrap_or_else(super::default_channel_gains);
+
+        log::debug!("SynthRecorder:");
+        log::debug!("    Sample Rate = {:6}", rate);
+        log::debug!("    Buffer Size = {:6}", buffer_size);
+        log::debug!("    Sines       = {:?}", sines);
+        log::debug!("    Clicks      = {:6} BPM", clicks);
+        log::debug!("    Gains       = {:?}", gains);
+
+        SynthRecorder {
+            buffer: analyzer::SampleBuffer::new(buffer_size, rate),
+            sines,
+            click_interval: if clicks > 0.0 {
+                Some(60.0 / clicks as f64)
+            } else {
+                None
+            },
+            gains,
+            generated: 0,
+            chunk: Vec::with_capacity(CHUNK_SIZE),
+        }
+    }
+
+    /// Compute the sample at `time` seconds
+    pub fn sample(&self, time: f64) -> analyzer::Sample {
+        let mut s = self
+            .sines
+            .iter()
+            .map(|(f, a)| (2.0 * PI * *f as f64 * time).sin() * *a as f64)
+            .sum::<f64>();
+
+        if let Some(interval) = self.click_interval {
+            let t = time % interval;
+            if t < CLICK_LENGTH {
+                s += (2.0 * PI * CLICK_FREQUENCY * t).sin() * (-t / CLICK_DECAY).exp();
+            }
+        }
+
+        s as analyzer::Sample
+    }
+}
+
+impl super::Recorder for SynthRecorder {
+    fn sample_buffer(&self) -> &analyzer::SampleBuffer {
+        &self.buffer
+    }
+
+    /// Generate the samples up to `time` seconds
+    fn sync(&mut self, time: f32) -> bool {
+        let rate = self.buffer.rate();
+        let target = (time.max(0.0) as f64 * rate as f64) as usize;
+
+        // Samples which would be overwritten right away need not be generated
+        self.generated = self
+            .generated
+            .max(target.saturating_sub(self.buffer.size()));
+
+        while self.generated < target {
+            let end = target.min(self.generated + CHUNK_SIZE);
+
+            let mut chunk = std::mem::take(&mut self.chunk);
+            chunk.clear();
+            chunk.extend((self.generated..end).map(|i| {
+                let s = self.sample(i as f64 / rate as f64);
+                [s, s]
+            }));
+            super::apply_channel_gains(&mut chunk, self.gains);
+            self.buffer.push(&chunk);
+            self.chunk = chunk;
+
+            self.generated = end;
+        }
+
+        true
+    }
+
+    fn wait_ready(&self, _timeout: std::time::Duration) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+
+    fn latest(rec: &SynthRecorder, n: usize) -> Vec<[analyzer::Sample; 2]> {
+        rec.sample_buffer().iter(n, 1).collect()
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let build = || {
+            SynthBuilder::new()
+                .rate(8000)
+                .buffer_size(4000)
+                .sine(440.0, 0.5)
+                .clicks(120.0)
+                .channel_gains([1.0, 0.5])
+                .create()
+        };
+
+        let mut stepped = build();
+        for i in 0..=100 {
+            stepped.sync(i as f32 * 0.0125);
+        }
+        let mut jumped = build();
+        jumped.sync(1.25);
+
+        assert_eq!(stepped.sample_buffer().samples_since(0), 10000);
+        assert_eq!(latest(&stepped, 4000), latest(&jumped, 4000));
+
+        let last = latest(&jumped, 1)[0];
+        assert_eq!(last[1], last[0] * 0.5);
+        assert_eq!(last[0], jumped.sample(9999.0 / 8000.0));
+    }
+
+    #[test]
+    fn test_spectrum() {
+        let mut rec = SynthBuilder::new()
+            .rate(8000)
+            .buffer_size(4000)
+            .sine(1000.0, 0.5)
+            .sine(2500.0, 0.1)
+            .clicks(0.0)
+            .channel_gains([1.0, 1.0])
+            .create();
+        rec.sync(1.0);
+
+        let mut analyzer = analyzer::FourierBuilder::new()
+            .rate(8000)
+            .length(512)
+            .window(analyzer::window::nuttall)
+            .downsample(1)
+            .channels(analyzer::fourier::Channels::Both)
+            .drop_dc(false)
+            .plan();
+        let [left, _] = analyzer.analyze(rec.sample_buffer());
+
+        assert!((left.argmax_freq() - 1000.0).abs() <= left.bin_width());
+        let peak = left.argmax_freq_in_range(2000.0, 3000.0);
+        assert!((peak - 2500.0).abs() <= left.bin_width());
+    }
+
+    #[test]
+    fn test_clicks() {
+        let mut rec = SynthBuilder {
+            sines: Some(Vec::new()),
+            ..Default::default()
+        }
+        .rate(8000)
+        .buffer_size(8000)
+        .clicks(120.0)
+        .channel_gains([1.0, 1.0])
+        .create();
+
+        // A click every 0.5s, silence in between
+        assert!(rec.sample(1.003).abs() > 0.5);
+        assert_eq!(rec.sample(1.3), 0.0);
+
+        rec.sync(1.0);
+        let samples = latest(&rec, 8000);
+        let loud = samples.iter().filter(|s| s[0].abs() > 0.1).count();
+        assert!(loud > 0 && loud < 8000 / 5);
+    }
+
+    #[test]
+    fn test_beat() {
+        let mut rec = SynthBuilder::new()
+            .rate(8000)
+            .buffer_size(8000)
+            .sine(1000.0, 0.2)
+            .clicks(120.0)
+            .channel_gains([1.0, 1.0])
+            .create();
+
+        let mut beat = analyzer::BeatBuilder::new()
+            .decay(2000.0)
+            .trigger(0.4)
+            .range(50.0, 100.0)
+            .fourier_length(16)
+            .downsample(10)
+            .rate(8000)
+            .volume_smoothing(8.0)
+            .history(8)
+            .build();
+
+        let mut beats = 0;
+        for i in 1..=1000 {
+            rec.sync(i as f32 * 0.01);
+            if beat.detect(rec.sample_buffer()) {
+                beats += 1;
+            }
+        }
+
+        // 10 seconds at 120 BPM
+        assert!((18..=21).contains(&beats), "{} beats", beats);
+        let bpm = beat.bpm().unwrap();
+        assert!((bpm - 120.0).abs() < 2.0, "{} BPM", bpm);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::analyzer;
+use std::{fs, io, path, sync, time};
+
+type _WavWriter = hound::WavWriter<io::BufWriter<fs::File>>;
+
+/// Recorder writing all captured samples to a WAV file
+///
+/// Wraps another recorder and transparently forwards its sample buffer.  The file is
+/// finalized when the recorder is dropped or [`finalize`](#method.finalize) is called.
+///
+/// # Example
+/// ```no_run
+/// # use vis_core::recorder;
+/// # vis_core::default_config();
+/// let inner = recorder::RecorderBuilder::new().build();
+/// let recorder = recorder::tee::TeeRecorder::new(inner, "capture.wav")
+///     .expect("Can't create capture file");
+/// ```
+pub struct TeeRecorder {
+    inner: Box<dyn super::Recorder>,
+    writer: sync::Arc<parking_lot::Mutex<Option<_WavWriter>>>,
+}
+
+impl std::fmt::Debug for TeeRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("TeeRecorder")
+            .field("inner", &self.inner)
+            .field("writing", &self.writer.lock().is_some())
+            .finish()
+    }
+}
+
+impl TeeRecorder {
+    /// Wrap `inner` and write everything it captures to `path`
+    pub fn new<P: AsRef<path::Path>>(
+        inner: Box<dyn super::Recorder>,
+        path: P,
+    ) -> Result<TeeRecorder, hound::Error> {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: inner.sample_buffer().rate() as u32,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let writer = sync::Arc::new(parking_lot::Mutex::new(Some(hound::WavWriter::create(
+            path.as_ref(),
+            spec,
+        )?)));
+
+        log::debug!("Tee:");
+        log::debug!("    File        = {:?}", path.as_ref());
+
+        {
+            let writer = writer.clone();
+            inner.sample_buffer().add_tap(move |chunk| {
+                if let Some(ref mut writer) = *writer.lock() {
+                    for s in chunk.iter().flat_map(|s| s.iter()) {
+                        if let Err(e) = writer.write_sample(*s) {
+                            log::error!("Failed to write sample: {}", e);
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+
+        Ok(TeeRecorder { inner, writer })
+    }
+
+    /// Stop writing and finalize the file
+    ///
+    /// Samples captured afterwards are no longer written.  Calling this more than once
+    /// does nothing.
+    pub fn finalize(&self) -> Result<(), hound::Error> {
+        match self.writer.lock().take() {
+            Some(writer) => writer.finalize(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for TeeRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.finalize() {
+            log::error!("Failed to finalize capture file: {}", e);
+        }
+    }
+}
+
+impl super::Recorder for TeeRecorder {
+    fn sample_buffer(&self) -> &analyzer::SampleBuffer {
+        self.inner.sample_buffer()
+    }
+
+    fn sync(&mut self, time: f32) -> bool {
+        self.inner.sync(time)
+    }
+
+    fn prime(&mut self) {
+        self.inner.prime()
+    }
+
+    fn wait_ready(&self, timeout: time::Duration) -> bool {
+        self.inner.wait_ready(timeout)
+    }
+
+    fn is_running(&self) -> bool {
+        self.inner.is_running()
+    }
+
+    fn latency(&self) -> f32 {
+        self.inner.latency()
+    }
+
+    fn native_rate(&self) -> Option<usize> {
+        self.inner.native_rate()
+    }
+
+    fn stop(&mut self) {
+        self.inner.stop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::TestRecorder;
+
+    #[test]
+    fn test_tee() {
+        let path = std::env::temp_dir().join("vis-core-test-tee.wav");
+
+        let buffer = analyzer::SampleBuffer::new(16, 8000);
+        let recorder = TeeRecorder::new(Box::new(TestRecorder(buffer.clone())), &path).unwrap();
+
+        buffer.push(&[[0.25, -0.25], [0.5, -0.5]]);
+        buffer.push(&[[1.0, -1.0]]);
+        drop(recorder);
+
+        // Not written anymore after the recorder is gone
+        buffer.push(&[[0.0, 0.0]]);
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().sample_rate, 8000);
+        assert_eq!(
+            reader
+                .samples::<f32>()
+                .map(|s| s.unwrap())
+                .collect::<Vec<_>>(),
+            [0.25, -0.25, 0.5, -0.5, 1.0, -1.0]
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,6 +1,21 @@
 use crate::analyzer;
 use crate::recorder;
 
+/// How analyzer results are handed to the render loop
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transport {
+    /// Only keep the latest result.  Intermediate results are dropped if the render loop is
+    /// slower than the analyzer.
+    #[default]
+    Latest,
+    /// Additionally send every published result over a queue holding up to `n` results.
+    ///
+    /// Results can be taken from the queue with
+    /// [`Frame::queued`](../frames/struct.Frame.html#method.queued).  A detached analyzer
+    /// blocks while the queue is full, so the render loop has to drain it regularly.
+    Queue(usize),
+}
+
 /// Builder for a Visualizer
 ///
 /// The "core" of `vis-core`.  Take a look at the crate root for an example on
@@ -26,6 +41,15 @@ where
     ///
     /// Can also be set from config as `"audio.conversions"`.
     pub async_analyzer: Option<usize>,
+    /// Predicate deciding whether a new analyzer result should be published.
+    ///
+    /// Called with the previously published and the new value.  If it returns `false`, the
+    /// new value is not published.  By default, every result is published.
+    pub publish_if: Option<fn(&R, &R) -> bool>,
+    /// How results are handed to the render loop.
+    ///
+    /// By default, only the latest result is kept.
+    pub transport: Transport,
 }
 
 impl<R, A> Visualizer<R, A>
@@ -42,6 +66,8 @@ where
             analyzer,
             recorder: None,
             async_analyzer: None,
+            publish_if: None,
+            transport: Transport::Latest,
         }
     }
 
@@ -62,6 +88,24 @@ where
         self
     }
 
+    /// Only publish analyzer results for which `predicate(old, new)` returns true.
+    ///
+    /// Use this to skip publishing when the result did not change meaningfully, to reduce
+    /// work for consumers.
+    pub fn publish_if(mut self, predicate: fn(&R, &R) -> bool) -> Visualizer<R, A> {
+        self.publish_if = Some(predicate);
+        self
+    }
+
+    /// Select how analyzer results are handed to the render loop.
+    ///
+    /// Use [`Transport::Queue`](enum.Transport.html#variant.Queue) for consumers which must
+    /// see every result, eg. to send a MIDI note for each beat.
+    pub fn transport(mut self, transport: Transport) -> Visualizer<R, A> {
+        self.transport = transport;
+        self
+    }
+
     /// Create a frames iterator from this visualizer config
     ///
     /// The frames iterator should then be iterated over in you main loop:
@@ -69,6 +113,7 @@ where
     /// ```
     /// # vis_core::default_config();
     /// # let mut frames = vis_core::Visualizer::new(0.0, |i, _s| i)
+    /// #     .recorder(vis_core::recorder::null::NullBuilder::new().build())
     /// #     .frames();
     /// 'main: for frame in frames.iter() {
     ///     println!("Time: {}", frame.time);